@@ -2,6 +2,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_client(true)
         .build_server(true)
-        .compile(&["proto/geyser.proto", "proto/amm.proto"], &["proto"])?;
+        .compile(&["proto/geyser.proto", "proto/solana-storage.proto", "proto/amm.proto"], &["proto"])?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,30 @@
+#!/bin/sh
+# Capture a mainnet account as a live decoder fixture.
+#
+# Usage: fixtures/live/dump.sh <name> <address> [rpc_url]
+#
+# Writes <name>.bin with the account data and <name>.json with the address,
+# owner and slot it was read at. Fill in the json's "expected" object from an
+# explorer's decoded view of the account at that slot before committing; see
+# fixtures/README.md for the fields each decoder test checks.
+set -eu
+
+name=$1
+address=$2
+rpc=${3:-https://api.mainnet-beta.solana.com}
+dir=$(dirname "$0")
+
+response=$(curl -sf "$rpc" -H 'content-type: application/json' -d "{
+  \"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"getAccountInfo\",
+  \"params\": [\"$address\", {\"encoding\": \"base64\", \"commitment\": \"finalized\"}]
+}")
+if [ "$(echo "$response" | jq -r '.result.value')" = "null" ]; then
+  echo "$address: no such account" >&2
+  exit 1
+fi
+
+echo "$response" | jq -r '.result.value.data[0]' | base64 -d > "$dir/$name.bin"
+echo "$response" | jq --arg name "$name" --arg address "$address" \
+  '{name: $name, pubkey: $address, owner: .result.value.owner, slot: .result.context.slot, expected: {}}' \
+  > "$dir/$name.json"
+echo "Wrote $dir/$name.bin ($(wc -c < "$dir/$name.bin") bytes) at slot $(jq .slot "$dir/$name.json")"
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::fixed::{to_ui_amount, PriceX64};
 
 // AMM Program IDs
 pub const PUMP_FUN_AMM: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
@@ -6,6 +7,11 @@ pub const METEORA_DLMM: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
 pub const RAYDIUM_CL: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
 pub const WHIRLPOOLS: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 
+// Reference mints
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenPair {
     pub base_token: String,
@@ -16,13 +22,93 @@ pub struct TokenPair {
     pub quote_decimals: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TokenPair {
+    /// Raw base token amount in whole tokens.
+    pub fn base_ui_amount(&self, raw: u128) -> f64 {
+        to_ui_amount(raw, self.base_decimals)
+    }
+
+    /// Raw quote token amount in whole tokens.
+    pub fn quote_ui_amount(&self, raw: u128) -> f64 {
+        to_ui_amount(raw, self.quote_decimals)
+    }
+
+    /// The same pair listed the other way round.
+    pub fn inverse(&self) -> TokenPair {
+        TokenPair {
+            base_token: self.quote_token.clone(),
+            quote_token: self.base_token.clone(),
+            base_mint: self.quote_mint.clone(),
+            quote_mint: self.base_mint.clone(),
+            base_decimals: self.quote_decimals,
+            quote_decimals: self.base_decimals,
+        }
+    }
+}
+
+/// A market independent of how a pool lists it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PairKey {
+    pub base_mint: String,
+    pub quote_mint: String,
+}
+
+/// Which token of a pair is the quote. A pair containing one of the listed
+/// mints is quoted in the most preferred one; any other pair has the lower
+/// mint as base.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotePreference(Vec<String>);
+
+impl Default for QuotePreference {
+    fn default() -> Self {
+        Self::new([USDC_MINT, USDT_MINT, SOL_MINT])
+    }
+}
+
+impl QuotePreference {
+    /// Quote mints, most preferred first.
+    pub fn new<S: Into<String>>(mints: impl IntoIterator<Item = S>) -> Self {
+        Self(mints.into_iter().map(Into::into).collect())
+    }
+
+    fn rank(&self, mint: &str) -> Option<usize> {
+        self.0.iter().position(|preferred| preferred == mint)
+    }
+
+    /// Whether `token_pair` is listed the other way round from its
+    /// canonical orientation.
+    pub fn is_reversed(&self, token_pair: &TokenPair) -> bool {
+        match (self.rank(&token_pair.base_mint), self.rank(&token_pair.quote_mint)) {
+            (Some(base), Some(quote)) => base < quote,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => token_pair.base_mint > token_pair.quote_mint,
+        }
+    }
+
+    pub fn pair_key(&self, token_pair: &TokenPair) -> PairKey {
+        let (base_mint, quote_mint) = if self.is_reversed(token_pair) {
+            (&token_pair.quote_mint, &token_pair.base_mint)
+        } else {
+            (&token_pair.base_mint, &token_pair.quote_mint)
+        };
+        PairKey {
+            base_mint: base_mint.clone(),
+            quote_mint: quote_mint.clone(),
+        }
+    }
+}
+
+/// Pool depth and activity, all in raw token units.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PoolLiquidity {
-    pub base_liquidity: f64,
-    pub quote_liquidity: f64,
-    pub total_liquidity_usd: f64,
-    pub volume_24h: f64,
-    pub volume_1h: f64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    /// Both reserves valued in the quote token.
+    pub total_liquidity_quote: u128,
+    /// Traded volume in the quote token.
+    pub volume_24h: u128,
+    pub volume_1h: u128,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,39 +116,150 @@ pub struct MarketRate {
     pub program_id: String,
     pub pool_address: String,
     pub token_pair: TokenPair,
-    pub rate: f64,  // Quote / Base
+    /// Quote / Base in raw units.
+    pub price: PriceX64,
     pub swap_fee: f64,
     pub liquidity: PoolLiquidity,
     pub timestamp: i64,
     pub transaction_signature: String,
 }
 
+impl MarketRate {
+    /// Quote per base in whole tokens (USDC per SOL).
+    pub fn rate(&self) -> f64 {
+        self.price.to_f64(self.token_pair.base_decimals, self.token_pair.quote_decimals)
+    }
+
+    /// Base per quote in whole tokens (SOL per USDC).
+    pub fn inverse_rate(&self) -> f64 {
+        self.price.inverse().to_f64(self.token_pair.quote_decimals, self.token_pair.base_decimals)
+    }
+
+    /// The same rate with the pair listed the other way round. Liquidity and
+    /// volume are revalued in the new quote token at this rate.
+    pub fn inverse(&self) -> MarketRate {
+        let inverse_price = self.price.inverse();
+        MarketRate {
+            token_pair: self.token_pair.inverse(),
+            price: inverse_price,
+            liquidity: PoolLiquidity {
+                base_reserve: self.liquidity.quote_reserve,
+                quote_reserve: self.liquidity.base_reserve,
+                total_liquidity_quote: inverse_price.quote_value(self.liquidity.total_liquidity_quote),
+                volume_24h: inverse_price.quote_value(self.liquidity.volume_24h),
+                volume_1h: inverse_price.quote_value(self.liquidity.volume_1h),
+            },
+            ..self.clone()
+        }
+    }
+
+    /// This rate in its pair's canonical orientation.
+    pub fn normalized(&self, preference: &QuotePreference) -> MarketRate {
+        if preference.is_reversed(&self.token_pair) {
+            self.inverse()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Quote per base in whole tokens, in the pair's canonical orientation,
+    /// so rates of pools listing the pair either way compare directly.
+    pub fn normalized_rate(&self, preference: &QuotePreference) -> f64 {
+        if preference.is_reversed(&self.token_pair) {
+            self.inverse_rate()
+        } else {
+            self.rate()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceUpdate {
     pub market_rate: MarketRate,
-    pub price_change_24h: f64,
-    pub price_change_1h: f64,
+    /// Percentage change of the rate (5.0 = up 5%). `None` for a pool seen
+    /// for less than the window.
+    pub price_change_24h: Option<f64>,
+    pub price_change_1h: Option<f64>,
+    /// Quote volume traded over the volume filter's timeframe, raw.
+    #[serde(default)]
+    pub volume: u128,
     pub meets_liquidity_filter: bool,
     pub meets_volume_filter: bool,
 }
 
+impl PriceUpdate {
+    /// This update in its pair's canonical orientation, with the price
+    /// changes of the inverted rate.
+    pub fn normalized(&self, preference: &QuotePreference) -> PriceUpdate {
+        if !preference.is_reversed(&self.market_rate.token_pair) {
+            return self.clone();
+        }
+        let invert_change = |change: f64| (100.0 / (100.0 + change) - 1.0) * 100.0;
+        PriceUpdate {
+            market_rate: self.market_rate.inverse(),
+            volume: self.market_rate.price.inverse().quote_value(self.volume),
+            price_change_24h: self.price_change_24h.map(invert_change),
+            price_change_1h: self.price_change_1h.map(invert_change),
+            ..self.clone()
+        }
+    }
+}
+
+/// Unit that pool liquidity and volume are converted to before filtering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Denomination {
+    #[default]
+    Sol,
+    /// US dollars, priced through USDC and USDT pools.
+    Usd,
+    /// Each pool's own quote token, without conversion.
+    Quote,
+}
+
+impl Denomination {
+    /// Decimals of one raw unit of this denomination.
+    pub fn decimals(self, quote_decimals: u8) -> u8 {
+        match self {
+            Denomination::Sol => 9,
+            Denomination::Usd => 6,
+            Denomination::Quote => quote_decimals,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterConfig {
-    pub min_liquidity_sol: f64,  // 10,000 SOL
-    pub min_volume_sol: f64,     // 50 SOL
+    #[serde(alias = "min_liquidity_sol")]
+    pub min_liquidity: f64,       // 10,000 by default, in `denomination`
+    #[serde(alias = "min_volume_sol")]
+    pub min_volume: f64,          // 50 by default, in `denomination`
     pub volume_timeframe_ms: i64, // 1,000ms
+    pub denomination: Denomination,
 }
 
 impl Default for FilterConfig {
     fn default() -> Self {
         Self {
-            min_liquidity_sol: 10_000.0,
-            min_volume_sol: 50.0,
+            min_liquidity: 10_000.0,
+            min_volume: 50.0,
             volume_timeframe_ms: 1_000,
+            denomination: Denomination::Sol,
         }
     }
 }
 
+/// `abcd…wxyz` for an address too long to show in full. Short strings are
+/// returned as they are.
+pub fn short_address(address: &str) -> String {
+    let chars: Vec<char> = address.chars().collect();
+    if chars.len() <= 9 {
+        return address.to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
 #[derive(Debug, Clone)]
 pub struct AmmProgram {
     pub id: String,
@@ -93,4 +290,85 @@ pub fn get_amm_programs() -> Vec<AmmProgram> {
             endpoint: "http://ams2.corvus-labs.io:10101".to_string(),
         },
     ]
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_pair(base_mint: &str, quote_mint: &str) -> TokenPair {
+        TokenPair {
+            base_token: "BASE".to_string(),
+            quote_token: "QUOTE".to_string(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            base_decimals: 9,
+            quote_decimals: 6,
+        }
+    }
+
+    #[test]
+    fn test_quote_preference_orders_pairs() {
+        let preference = QuotePreference::default();
+        assert!(!preference.is_reversed(&token_pair(SOL_MINT, USDC_MINT)));
+        assert!(preference.is_reversed(&token_pair(USDC_MINT, SOL_MINT)));
+        // USDC is preferred over USDT, and any listed mint over others
+        assert!(preference.is_reversed(&token_pair(USDC_MINT, USDT_MINT)));
+        assert!(!preference.is_reversed(&token_pair("bonk", SOL_MINT)));
+        // Otherwise the lower mint is the base
+        assert!(!preference.is_reversed(&token_pair("a", "b")));
+        assert!(preference.is_reversed(&token_pair("b", "a")));
+
+        let key = preference.pair_key(&token_pair(USDC_MINT, SOL_MINT));
+        assert_eq!(key, preference.pair_key(&token_pair(SOL_MINT, USDC_MINT)));
+        assert_eq!((key.base_mint.as_str(), key.quote_mint.as_str()), (SOL_MINT, USDC_MINT));
+    }
+
+    #[test]
+    fn test_normalized_rate_and_inverse() {
+        // Listed USDC/SOL: 1,000 USDC against 8 SOL
+        let market_rate = MarketRate {
+            program_id: "test".to_string(),
+            pool_address: "pool".to_string(),
+            token_pair: TokenPair {
+                base_decimals: 6,
+                quote_decimals: 9,
+                ..token_pair(USDC_MINT, SOL_MINT)
+            },
+            price: PriceX64::from_amounts(1_000_000_000, 8_000_000_000),
+            swap_fee: 0.003,
+            liquidity: PoolLiquidity {
+                base_reserve: 1_000_000_000,
+                quote_reserve: 8_000_000_000,
+                total_liquidity_quote: 16_000_000_000,
+                ..PoolLiquidity::default()
+            },
+            timestamp: 0,
+            transaction_signature: "tx".to_string(),
+        };
+        assert!((market_rate.rate() - 0.008).abs() < 1e-12);
+        assert!((market_rate.inverse_rate() - 125.0).abs() < 1e-9);
+
+        let preference = QuotePreference::default();
+        assert!((market_rate.normalized_rate(&preference) - 125.0).abs() < 1e-9);
+        let normalized = market_rate.normalized(&preference);
+        assert_eq!(normalized.token_pair.base_mint, SOL_MINT);
+        assert_eq!((normalized.liquidity.base_reserve, normalized.liquidity.quote_reserve), (8_000_000_000, 1_000_000_000));
+        // 16 SOL of liquidity is 2,000 USDC
+        assert!(normalized.liquidity.total_liquidity_quote.abs_diff(2_000_000_000) <= 1);
+        assert!((normalized.rate() - 125.0).abs() < 1e-9);
+
+        // Up 25% in SOL per USDC is down 20% in USDC per SOL
+        let update = PriceUpdate {
+            market_rate,
+            price_change_24h: Some(25.0),
+            price_change_1h: None,
+            volume: 0,
+            meets_liquidity_filter: true,
+            meets_volume_filter: true,
+        };
+        let normalized = update.normalized(&preference);
+        assert!((normalized.price_change_24h.unwrap() + 20.0).abs() < 1e-9);
+        assert_eq!(normalized.price_change_1h, None);
+    }
+}
@@ -0,0 +1,305 @@
+//! Cross-pool arbitrage detection.
+//!
+//! Pools trading the same two mints are grouped together whichever way
+//! round they list them, and compared in the pair's canonical orientation.
+//! When a pool's rate changes it is compared with every other pool of its
+//! pair: buying base on the cheaper pool and selling it on the dearer one,
+//! after both pools' swap fees, is an opportunity once the spread clears the
+//! threshold.
+//!
+//! Sizing treats each pool as constant product, with the depth of its
+//! reserves re-centred on its price. Summed reserves of CLMM and DLMM pools
+//! say nothing about the active price, so direction, spread and size all
+//! come from the price; this holds for trades that stay in the active range.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::amm_types::{MarketRate, PairKey, QuotePreference};
+use crate::fixed::{saturating_u128, PriceX64, U256, ONE_X64, Q64_SHIFT};
+
+/// Fee multipliers are applied in parts per million.
+const PPM: u64 = 1_000_000;
+
+/// A pool's latest state, in the orientation of its pair group.
+#[derive(Debug, Clone)]
+struct PoolState {
+    program_id: String,
+    price: PriceX64,
+    base_reserve: u128,
+    quote_reserve: u128,
+    /// `1 - swap_fee`, in ppm.
+    fee_multiplier_ppm: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArbitrageOpportunity {
+    /// The canonical pair that prices and sizes are expressed in.
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    /// Pool to buy base on, and its price.
+    pub buy_pool: String,
+    pub buy_program_id: String,
+    pub buy_price: PriceX64,
+    /// Pool to sell base on, and its price.
+    pub sell_pool: String,
+    pub sell_program_id: String,
+    pub sell_price: PriceX64,
+    /// Spread after both pools' fees, in basis points.
+    pub spread_bps: f64,
+    /// Quote amount in that maximizes profit, and the profit it makes, raw.
+    pub optimal_size: u128,
+    pub expected_profit: u128,
+    /// Largest quote amount in that still makes a profit, raw.
+    pub max_profitable_size: u128,
+    pub timestamp: i64,
+}
+
+impl ArbitrageOpportunity {
+    pub fn buy_rate(&self) -> f64 {
+        self.buy_price.to_f64(self.base_decimals, self.quote_decimals)
+    }
+
+    pub fn sell_rate(&self) -> f64 {
+        self.sell_price.to_f64(self.base_decimals, self.quote_decimals)
+    }
+}
+
+pub struct ArbitrageDetector {
+    min_spread_bps: f64,
+    quote_preference: QuotePreference,
+    pairs: HashMap<PairKey, HashMap<String, PoolState>>,
+}
+
+impl ArbitrageDetector {
+    /// Report opportunities whose fee-adjusted spread exceeds
+    /// `min_spread_bps`.
+    pub fn new(min_spread_bps: f64) -> Self {
+        Self {
+            min_spread_bps: min_spread_bps.max(0.0),
+            quote_preference: QuotePreference::default(),
+            pairs: HashMap::new(),
+        }
+    }
+
+    pub fn with_quote_preference(mut self, quote_preference: QuotePreference) -> Self {
+        self.quote_preference = quote_preference;
+        self
+    }
+
+    /// Record `market_rate` as its pool's latest and return the
+    /// opportunities between that pool and the rest of its pair.
+    pub fn observe(&mut self, market_rate: &MarketRate) -> Vec<ArbitrageOpportunity> {
+        let normalized = market_rate.normalized(&self.quote_preference);
+        let pair = &normalized.token_pair;
+        let key = self.quote_preference.pair_key(pair);
+        let (base_reserve, quote_reserve) = reserves_at_price(
+            normalized.price,
+            normalized.liquidity.base_reserve as u128,
+            normalized.liquidity.quote_reserve as u128,
+        );
+        let state = PoolState {
+            program_id: market_rate.program_id.clone(),
+            price: normalized.price,
+            base_reserve,
+            quote_reserve,
+            fee_multiplier_ppm: fee_multiplier_ppm(market_rate.swap_fee),
+        };
+
+        let pools = self.pairs.entry(key).or_default();
+        if state.price.is_zero() || state.base_reserve == 0 || state.quote_reserve == 0 {
+            pools.remove(&market_rate.pool_address);
+            return Vec::new();
+        }
+
+        let mut opportunities = Vec::new();
+        for (other_address, other) in pools.iter() {
+            if *other_address == market_rate.pool_address {
+                continue;
+            }
+            let (buy, sell) = if state.price < other.price {
+                ((&market_rate.pool_address, &state), (other_address, other))
+            } else {
+                ((other_address, other), (&market_rate.pool_address, &state))
+            };
+            let Some(sizing) = size_opportunity(buy.1, sell.1) else {
+                continue;
+            };
+            if sizing.spread_bps <= self.min_spread_bps {
+                continue;
+            }
+            opportunities.push(ArbitrageOpportunity {
+                base_mint: pair.base_mint.clone(),
+                quote_mint: pair.quote_mint.clone(),
+                base_decimals: pair.base_decimals,
+                quote_decimals: pair.quote_decimals,
+                buy_pool: buy.0.clone(),
+                buy_program_id: buy.1.program_id.clone(),
+                buy_price: buy.1.price,
+                sell_pool: sell.0.clone(),
+                sell_program_id: sell.1.program_id.clone(),
+                sell_price: sell.1.price,
+                spread_bps: sizing.spread_bps,
+                optimal_size: sizing.optimal_size,
+                expected_profit: sizing.expected_profit,
+                max_profitable_size: sizing.max_profitable_size,
+                timestamp: market_rate.timestamp,
+            });
+        }
+        pools.insert(market_rate.pool_address.clone(), state);
+
+        opportunities.sort_by_key(|opportunity| std::cmp::Reverse(opportunity.expected_profit));
+        opportunities
+    }
+}
+
+fn fee_multiplier_ppm(swap_fee: f64) -> u64 {
+    let fee_ppm = (swap_fee.clamp(0.0, 1.0) * PPM as f64).round() as u64;
+    PPM - fee_ppm
+}
+
+/// Constant-product reserves at `price` with the depth of `(base, quote)`:
+/// the product `base * quote` is kept and the ratio becomes the price.
+fn reserves_at_price(price: PriceX64, base_reserve: u128, quote_reserve: u128) -> (u128, u128) {
+    if price.is_zero() {
+        return (0, 0);
+    }
+    let product = U256::from(base_reserve) * U256::from(quote_reserve);
+    let base = saturating_u128(((product << Q64_SHIFT) / U256::from(price.x64())).integer_sqrt());
+    (base, price.quote_value(base))
+}
+
+struct Sizing {
+    spread_bps: f64,
+    optimal_size: u128,
+    expected_profit: u128,
+    max_profitable_size: u128,
+}
+
+/// Size the round trip quote -> base on `buy`, base -> quote on `sell`.
+///
+/// With fee multipliers `ga`, `gb`, buy reserves `(xa, ya)` and sell
+/// reserves `(xb, yb)`, quote in `d` returns `A d / (B + C d)` where
+/// `A = ga gb xa yb`, `B = xb ya` and `C = ga (xb + gb xa)`. That beats `d`
+/// while `d < (A - B) / C`, and is best at `d = B (sqrt(A / B) - 1) / C`.
+/// `A / B` is the fee-adjusted price ratio. `None` unless it agrees in sign
+/// with the fee-adjusted ratio of the pools' prices.
+fn size_opportunity(buy: &PoolState, sell: &PoolState) -> Option<Sizing> {
+    let ppm = U256::from(PPM);
+    let (ga, gb) = (U256::from(buy.fee_multiplier_ppm), U256::from(sell.fee_multiplier_ppm));
+    let (xa, ya) = (U256::from(buy.base_reserve), U256::from(buy.quote_reserve));
+    let (xb, yb) = (U256::from(sell.base_reserve), U256::from(sell.quote_reserve));
+
+    // Scaled by ppm^2 so the fee multipliers stay integers
+    let a = ga * gb * xa * yb;
+    let b = ppm * ppm * xb * ya;
+    let c = ga * (ppm * xb + gb * xa);
+    let price_gain = U256::from(sell.price.x64()) * ga * gb;
+    let price_cost = U256::from(buy.price.x64()) * ppm * ppm;
+    if a <= b || price_gain <= price_cost || c.is_zero() {
+        return None;
+    }
+
+    let ratio_x64 = (a << Q64_SHIFT) / b;
+    let spread_bps = (saturating_u128(ratio_x64) - ONE_X64) as f64 / ONE_X64 as f64 * 10_000.0;
+    let sqrt_ratio_x64 = (ratio_x64 << Q64_SHIFT).integer_sqrt();
+    let optimal_size = saturating_u128(b * (sqrt_ratio_x64 - U256::from(ONE_X64)) / (c << Q64_SHIFT));
+    let optimal = U256::from(optimal_size);
+    let returned = saturating_u128(a * optimal / (b + c * optimal));
+
+    Some(Sizing {
+        spread_bps,
+        optimal_size,
+        expected_profit: returned.saturating_sub(optimal_size),
+        max_profitable_size: saturating_u128((a - b) / c),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::SOL_MINT;
+    use crate::test_utils;
+
+    fn market_rate(pool_address: &str, base_reserve: u64, quote_reserve: u64, swap_fee: f64) -> MarketRate {
+        MarketRate {
+            program_id: format!("{}_program", pool_address),
+            swap_fee,
+            ..test_utils::market_rate(pool_address, base_reserve, quote_reserve)
+        }
+    }
+
+    /// The same pool listed the other way round.
+    fn reversed(mut market_rate: MarketRate) -> MarketRate {
+        let pair = &mut market_rate.token_pair;
+        std::mem::swap(&mut pair.base_token, &mut pair.quote_token);
+        std::mem::swap(&mut pair.base_mint, &mut pair.quote_mint);
+        std::mem::swap(&mut pair.base_decimals, &mut pair.quote_decimals);
+        let liquidity = &mut market_rate.liquidity;
+        std::mem::swap(&mut liquidity.base_reserve, &mut liquidity.quote_reserve);
+        market_rate.price = market_rate.price.inverse();
+        market_rate
+    }
+
+    #[test]
+    fn test_detects_fee_adjusted_spread() {
+        let mut detector = ArbitrageDetector::new(10.0);
+        // 1,000 SOL at 150 and at 152 USDC/SOL, 0.25% and 0.3% fees
+        assert!(detector.observe(&market_rate("cheap", 1_000_000_000_000, 150_000_000_000, 0.0025)).is_empty());
+        let opportunities = detector.observe(&reversed(market_rate("dear", 1_000_000_000_000, 152_000_000_000, 0.003)));
+        assert_eq!(opportunities.len(), 1);
+
+        let opportunity = &opportunities[0];
+        assert_eq!((opportunity.buy_pool.as_str(), opportunity.sell_pool.as_str()), ("cheap", "dear"));
+        assert_eq!((opportunity.base_mint.as_str(), opportunity.base_decimals), (SOL_MINT, 9));
+        assert!((opportunity.buy_rate() - 150.0).abs() < 1e-6);
+        assert!((opportunity.sell_rate() - 152.0).abs() < 1e-6);
+        // 152 / 150 * 0.9975 * 0.997 - 1
+        assert!((opportunity.spread_bps - 77.68).abs() < 0.01);
+
+        // Profit peaks inside the profitable range, and trading the whole
+        // range breaks even
+        assert!(opportunity.optimal_size > 0 && opportunity.optimal_size < opportunity.max_profitable_size);
+        assert!((opportunity.max_profitable_size / 2).abs_diff(opportunity.optimal_size) < opportunity.optimal_size / 50);
+        assert!(opportunity.expected_profit > 0);
+        assert!(opportunity.expected_profit < opportunity.optimal_size / 100);
+    }
+
+    #[test]
+    fn test_sizes_from_the_active_price() {
+        // A DLMM-style pool whose summed bins sit at 200 USDC/SOL while its
+        // active price is 150: it is still the one to buy on
+        let mut bins = market_rate("bins", 1_000_000_000_000, 200_000_000_000, 0.0025);
+        bins.price = PriceX64::from_amounts(1_000_000_000_000, 150_000_000_000);
+        let mut detector = ArbitrageDetector::new(10.0);
+        detector.observe(&bins);
+        let opportunities = detector.observe(&market_rate("dear", 1_000_000_000_000, 152_000_000_000, 0.003));
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert_eq!((opportunity.buy_pool.as_str(), opportunity.sell_pool.as_str()), ("bins", "dear"));
+        assert!((opportunity.spread_bps - 77.68).abs() < 0.05);
+        assert!(opportunity.expected_profit > 0);
+        assert!(opportunity.optimal_size < opportunity.max_profitable_size);
+    }
+
+    #[test]
+    fn test_fees_and_threshold_suppress_opportunities() {
+        // A 0.5% price gap doesn't cover two 0.3% fees
+        let mut detector = ArbitrageDetector::new(0.0);
+        detector.observe(&market_rate("a", 1_000_000_000_000, 150_000_000_000, 0.003));
+        assert!(detector.observe(&market_rate("b", 1_000_000_000_000, 150_750_000_000, 0.003)).is_empty());
+
+        // Without fees it's a 50 bps spread, under a 60 bps threshold
+        let mut detector = ArbitrageDetector::new(60.0);
+        detector.observe(&market_rate("a", 1_000_000_000_000, 150_000_000_000, 0.0));
+        assert!(detector.observe(&market_rate("b", 1_000_000_000_000, 150_750_000_000, 0.0)).is_empty());
+
+        // An update replaces the pool's previous rate instead of comparing with it
+        let mut detector = ArbitrageDetector::new(0.0);
+        detector.observe(&market_rate("a", 1_000_000_000_000, 150_000_000_000, 0.0));
+        assert!(detector.observe(&market_rate("a", 1_000_000_000_000, 160_000_000_000, 0.0)).is_empty());
+        let opportunities = detector.observe(&market_rate("b", 1_000_000_000_000, 150_000_000_000, 0.0));
+        assert_eq!(opportunities[0].sell_pool, "a");
+    }
+}
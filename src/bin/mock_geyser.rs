@@ -0,0 +1,112 @@
+//! Local Yellowstone Geyser stand-in for exercising `--geyser` without a
+//! live validator.
+//!
+//! Usage: `cargo run --bin mock_geyser -- [listen_addr]` (default `127.0.0.1:10000`)
+
+use std::time::Duration;
+use solana_amm_rate_calculator::amm_types::{get_amm_programs, METEORA_DLMM, PUMP_FUN_AMM, RAYDIUM_CL, WHIRLPOOLS};
+use solana_amm_rate_calculator::decoders::spl_token::TOKEN_PROGRAM;
+use solana_amm_rate_calculator::geyser_mock::MockGeyser;
+
+const WHIRLPOOL: &[u8] = include_bytes!("../../fixtures/whirlpool_sol_usdc.bin");
+const RAYDIUM_POOL: &[u8] = include_bytes!("../../fixtures/raydium_clmm_sol_usdc.bin");
+const RAYDIUM_AMM_CONFIG: &[u8] = include_bytes!("../../fixtures/raydium_amm_config_4.bin");
+const DLMM_PAIR: &[u8] = include_bytes!("../../fixtures/meteora_lb_pair_sol_usdc.bin");
+const PUMP_POOL: &[u8] = include_bytes!("../../fixtures/pump_amm_pool_sol_usdc.bin");
+const PUMP_GLOBAL_CONFIG: &[u8] = include_bytes!("../../fixtures/pump_amm_global_config.bin");
+const PUMP_VAULT_SOL: &[u8] = include_bytes!("../../fixtures/pump_amm_vault_sol.bin");
+const PUMP_VAULT_USDC: &[u8] = include_bytes!("../../fixtures/pump_amm_vault_usdc.bin");
+
+/// `AmmConfig` address stored in `RAYDIUM_POOL`.
+const RAYDIUM_AMM_CONFIG_ADDRESS: &str = "9bJCbHNaWC2xaGAw2kYobvisixnkpyCEMBndHADX9jm5";
+
+/// Vault addresses stored in `PUMP_POOL`.
+const PUMP_BASE_VAULT: &str = "9onNWGjzCuG3yotS7cNKBx7iwbNh6ghdZGJpTyQQonmo";
+const PUMP_QUOTE_VAULT: &str = "44kVW25HBj2iBx7gHdu6SfyrA227j1fPBbPBkK13MnSQ";
+
+/// Account data for a program's pool at `slot`, with the price drifting a
+/// little from slot to slot.
+fn pool_account(program_id: &str, slot: u64) -> Vec<u8> {
+    let drift = 1.0 + 0.001 * ((slot % 20) as f64 - 10.0);
+    match program_id {
+        WHIRLPOOLS => {
+            let mut data = WHIRLPOOL.to_vec();
+            let sqrt_price = u128::from_le_bytes(data[65..81].try_into().unwrap());
+            let sqrt_price = (sqrt_price as f64 * drift.sqrt()) as u128;
+            data[65..81].copy_from_slice(&sqrt_price.to_le_bytes());
+            data
+        }
+        RAYDIUM_CL => {
+            let mut data = RAYDIUM_POOL.to_vec();
+            let sqrt_price = u128::from_le_bytes(data[253..269].try_into().unwrap());
+            let sqrt_price = (sqrt_price as f64 * drift.sqrt()) as u128;
+            data[253..269].copy_from_slice(&sqrt_price.to_le_bytes());
+            data
+        }
+        METEORA_DLMM => {
+            // Walk the active bin up and down by a few bins
+            let mut data = DLMM_PAIR.to_vec();
+            let active_id = i32::from_le_bytes(data[76..80].try_into().unwrap());
+            let active_id = active_id + (slot % 20) as i32 - 10;
+            data[76..80].copy_from_slice(&active_id.to_le_bytes());
+            data
+        }
+        PUMP_FUN_AMM => PUMP_POOL.to_vec(),
+        _ => slot.to_le_bytes().to_vec(),
+    }
+}
+
+/// The Pump.fun pool's vaults at `slot`, with the quote balance drifting.
+fn pump_vaults(slot: u64) -> [(&'static str, Vec<u8>); 2] {
+    let drift = 1.0 + 0.001 * ((slot % 20) as f64 - 10.0);
+    let mut quote = PUMP_VAULT_USDC.to_vec();
+    let amount = u64::from_le_bytes(quote[64..72].try_into().unwrap());
+    quote[64..72].copy_from_slice(&((amount as f64 * drift) as u64).to_le_bytes());
+    [(PUMP_BASE_VAULT, PUMP_VAULT_SOL.to_vec()), (PUMP_QUOTE_VAULT, quote)]
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:10000".to_string())
+        .parse()?;
+
+    let mock = MockGeyser::new().with_ping_interval(Duration::from_secs(15));
+    let feed = mock.clone();
+
+    tokio::spawn(async move {
+        let programs = get_amm_programs();
+        let mut interval = tokio::time::interval(Duration::from_millis(500));
+        let mut slot = 0u64;
+
+        loop {
+            interval.tick().await;
+            slot += 1;
+            feed.push_slot(slot);
+
+            for (index, program) in programs.iter().enumerate() {
+                let pool = bs58::encode([index as u8 + 1; 32]).into_string();
+                feed.push_account(&pool, &program.id, pool_account(&program.id, slot), slot);
+
+                let mut signature = [0u8; 64];
+                signature[..8].copy_from_slice(&slot.to_le_bytes());
+                signature[8] = index as u8;
+                feed.push_transaction(&signature, &[&pool, &program.id], slot);
+            }
+
+            // Fee configs, which a real node only sends when they change
+            feed.push_account(RAYDIUM_AMM_CONFIG_ADDRESS, RAYDIUM_CL, RAYDIUM_AMM_CONFIG.to_vec(), slot);
+            let config = bs58::encode([0xfe; 32]).into_string();
+            feed.push_account(&config, PUMP_FUN_AMM, PUMP_GLOBAL_CONFIG.to_vec(), slot);
+
+            // Pump.fun pools are priced from their token vaults
+            for (vault, data) in pump_vaults(slot) {
+                feed.push_account(vault, TOKEN_PROGRAM, data, slot);
+            }
+        }
+    });
+
+    mock.serve(addr).await?;
+    Ok(())
+}
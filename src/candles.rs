@@ -0,0 +1,491 @@
+//! OHLCV candles built from the rate stream, per pool and per pair.
+//!
+//! Every rate updates one candle per series (its pool, and its pair) and
+//! resolution. Candles stay open until stream time has moved past their end
+//! by the allowed lateness, so updates that arrive out of order within that
+//! grace period still land in the right bar; later ones are dropped and
+//! counted. Closed candles are handed to every [`CandleSink`], and the last
+//! few per series can be kept for charts. Intervals
+//! without updates produce no candle. Prices are in the pair's canonical
+//! orientation.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::amm_types::{MarketRate, PairKey, PoolLiquidity, QuotePreference};
+use crate::fixed::PriceX64;
+use crate::volume::trade_volume;
+
+/// What a candle aggregates.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SeriesKey {
+    Pool(String),
+    /// Every pool trading the pair, whichever way round it lists it.
+    Pair(PairKey),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub series: SeriesKey,
+    pub resolution_ms: i64,
+    /// Start of the interval, a multiple of `resolution_ms`.
+    pub start_ms: i64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub open: PriceX64,
+    pub high: PriceX64,
+    pub low: PriceX64,
+    pub close: PriceX64,
+    /// Quote volume traded, in raw units.
+    pub volume: u128,
+    /// Number of rates aggregated.
+    pub updates: u32,
+    /// Timestamps of the rates `open` and `close` came from.
+    pub first_update_ms: i64,
+    pub last_update_ms: i64,
+}
+
+impl Candle {
+    fn new(series: SeriesKey, resolution_ms: i64, start_ms: i64, market_rate: &MarketRate) -> Self {
+        Self {
+            series,
+            resolution_ms,
+            start_ms,
+            base_decimals: market_rate.token_pair.base_decimals,
+            quote_decimals: market_rate.token_pair.quote_decimals,
+            open: market_rate.price,
+            high: market_rate.price,
+            low: market_rate.price,
+            close: market_rate.price,
+            volume: 0,
+            updates: 0,
+            first_update_ms: market_rate.timestamp,
+            last_update_ms: market_rate.timestamp,
+        }
+    }
+
+    pub fn end_ms(&self) -> i64 {
+        self.start_ms + self.resolution_ms
+    }
+
+    fn update(&mut self, timestamp: i64, price: PriceX64, volume: u128) {
+        if timestamp < self.first_update_ms {
+            self.open = price;
+            self.first_update_ms = timestamp;
+        }
+        if timestamp >= self.last_update_ms {
+            self.close = price;
+            self.last_update_ms = timestamp;
+        }
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.volume += volume;
+        self.updates += 1;
+    }
+
+    /// `(open, high, low, close)` in whole quote tokens per base token.
+    pub fn ohlc(&self) -> (f64, f64, f64, f64) {
+        let to_f64 = |price: PriceX64| price.to_f64(self.base_decimals, self.quote_decimals);
+        (to_f64(self.open), to_f64(self.high), to_f64(self.low), to_f64(self.close))
+    }
+}
+
+/// Destination for closed candles.
+pub trait CandleSink {
+    fn emit(&mut self, candle: &Candle);
+
+    /// Write out anything buffered; called by [`CandleAggregator::flush`].
+    fn flush(&mut self) {}
+}
+
+impl<F: FnMut(&Candle)> CandleSink for F {
+    fn emit(&mut self, candle: &Candle) {
+        self(candle)
+    }
+}
+
+/// How often [`JsonLinesSink`] flushes while candles keep closing.
+const SINK_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Writes each candle as one JSON object per line. Writes are flushed at
+/// most every [`SINK_FLUSH_INTERVAL`] and when the aggregator is flushed, so
+/// give it a buffered writer.
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+    /// Only write candles of these resolutions; all if empty.
+    resolutions_ms: Vec<i64>,
+    last_flush: Instant,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            resolutions_ms: Vec::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Only write candles of `resolutions_ms`, e.g. when the aggregator also
+    /// builds others for a chart.
+    pub fn with_resolutions(mut self, resolutions_ms: &[i64]) -> Self {
+        self.resolutions_ms = resolutions_ms.to_vec();
+        self
+    }
+}
+
+impl<W: Write> CandleSink for JsonLinesSink<W> {
+    fn emit(&mut self, candle: &Candle) {
+        if !self.resolutions_ms.is_empty() && !self.resolutions_ms.contains(&candle.resolution_ms) {
+            return;
+        }
+        let written = serde_json::to_writer(&mut self.writer, candle)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(e) = written {
+            eprintln!("Failed to write candle: {}", e);
+        }
+        if self.last_flush.elapsed() >= SINK_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if let Err(e) = self.writer.flush() {
+            eprintln!("Failed to write candles: {}", e);
+        }
+    }
+}
+
+/// An aggregator fed by the ingest loop and read by the table's detail
+/// pane.
+pub type SharedCandleAggregator = Arc<Mutex<CandleAggregator>>;
+
+/// Open candles are keyed by end time first so closing them is a walk from
+/// the front of the map.
+type OpenKey = (i64, SeriesKey, i64);
+
+pub struct CandleAggregator {
+    resolutions_ms: Vec<i64>,
+    allowed_lateness_ms: i64,
+    per_pair: bool,
+    /// Orientation candles are built in, so pools listing a pair either way
+    /// round share its pair candles.
+    quote_preference: QuotePreference,
+    open: BTreeMap<OpenKey, Candle>,
+    /// Latest in-order liquidity of each pool, for trade volume.
+    last_liquidity: HashMap<String, (i64, PoolLiquidity)>,
+    latest_timestamp: i64,
+    late_updates: u64,
+    sinks: Vec<Box<dyn CandleSink + Send>>,
+    history_len: usize,
+    history_resolution_ms: i64,
+    /// Latest closed candles by series and resolution, oldest first.
+    history: HashMap<(SeriesKey, i64), VecDeque<Candle>>,
+}
+
+impl CandleAggregator {
+    /// Aggregate at each of `resolutions_ms`, with no allowed lateness.
+    pub fn new(resolutions_ms: &[i64]) -> Self {
+        Self {
+            resolutions_ms: resolutions_ms.iter().copied().filter(|ms| *ms > 0).collect(),
+            allowed_lateness_ms: 0,
+            per_pair: true,
+            quote_preference: QuotePreference::default(),
+            open: BTreeMap::new(),
+            last_liquidity: HashMap::new(),
+            latest_timestamp: i64::MIN,
+            late_updates: 0,
+            sinks: Vec::new(),
+            history_len: 0,
+            history_resolution_ms: 0,
+            history: HashMap::new(),
+        }
+    }
+
+    /// Keep candles open for `allowed_lateness_ms` of stream time after
+    /// they end.
+    pub fn with_allowed_lateness(mut self, allowed_lateness_ms: i64) -> Self {
+        self.allowed_lateness_ms = allowed_lateness_ms.max(0);
+        self
+    }
+
+    /// Whether to build pair candles as well as pool candles.
+    pub fn with_pair_candles(mut self, per_pair: bool) -> Self {
+        self.per_pair = per_pair;
+        self
+    }
+
+    pub fn with_quote_preference(mut self, quote_preference: QuotePreference) -> Self {
+        self.quote_preference = quote_preference;
+        self
+    }
+
+    pub fn with_sink(mut self, sink: impl CandleSink + Send + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Keep the last `history_len` closed candles of each series at
+    /// `resolution_ms`, for [`CandleAggregator::history`]. Other resolutions
+    /// only go to the sinks.
+    pub fn with_history(mut self, resolution_ms: i64, history_len: usize) -> Self {
+        self.history_resolution_ms = resolution_ms;
+        self.history_len = history_len;
+        self
+    }
+
+    /// Closed candles of `series` at `resolution_ms` kept by
+    /// [`CandleAggregator::with_history`], oldest first.
+    pub fn history(&self, series: &SeriesKey, resolution_ms: i64) -> impl Iterator<Item = &Candle> {
+        self.history.get(&(series.clone(), resolution_ms)).into_iter().flatten()
+    }
+
+    /// Updates dropped from at least one resolution because their candle had
+    /// already closed. Each counts once, however many it missed.
+    pub fn late_updates(&self) -> u64 {
+        self.late_updates
+    }
+
+    /// The open candle of `series` at `resolution_ms` containing the latest
+    /// stream time, if any.
+    pub fn current(&self, series: &SeriesKey, resolution_ms: i64) -> Option<&Candle> {
+        let start = self.latest_timestamp - self.latest_timestamp.rem_euclid(resolution_ms);
+        self.open.get(&(start + resolution_ms, series.clone(), resolution_ms))
+    }
+
+    /// Every open candle of `series` at `resolution_ms`, oldest first: the
+    /// current one and any still waiting for late updates.
+    pub fn open_candles<'a>(&'a self, series: &'a SeriesKey, resolution_ms: i64) -> impl Iterator<Item = &'a Candle> {
+        self.open
+            .iter()
+            .filter(move |((_, key, resolution), _)| key == series && *resolution == resolution_ms)
+            .map(|(_, candle)| candle)
+    }
+
+    pub fn observe(&mut self, market_rate: &MarketRate) {
+        if market_rate.price.is_zero() {
+            return;
+        }
+        let market_rate = &market_rate.normalized(&self.quote_preference);
+        let timestamp = market_rate.timestamp;
+
+        // Volume only from in-order updates; a late one can't be diffed
+        // against the pool's neighbours reliably.
+        let volume = match self.last_liquidity.get(&market_rate.pool_address) {
+            Some((last, _)) if timestamp < *last => 0,
+            previous => {
+                let volume = previous.map_or(0, |(_, liquidity)| trade_volume(liquidity, &market_rate.liquidity));
+                self.last_liquidity
+                    .insert(market_rate.pool_address.clone(), (timestamp, market_rate.liquidity.clone()));
+                volume
+            }
+        };
+
+        let mut series = vec![SeriesKey::Pool(market_rate.pool_address.clone())];
+        if self.per_pair {
+            series.push(SeriesKey::Pair(self.quote_preference.pair_key(&market_rate.token_pair)));
+        }
+
+        let watermark = self.latest_timestamp.saturating_sub(self.allowed_lateness_ms);
+        let mut late = false;
+        for &resolution_ms in &self.resolutions_ms {
+            let start_ms = timestamp - timestamp.rem_euclid(resolution_ms);
+            if start_ms + resolution_ms <= watermark {
+                late = true;
+                continue;
+            }
+            for key in &series {
+                self.open
+                    .entry((start_ms + resolution_ms, key.clone(), resolution_ms))
+                    .or_insert_with(|| Candle::new(key.clone(), resolution_ms, start_ms, market_rate))
+                    .update(timestamp, market_rate.price, volume);
+            }
+        }
+
+        self.late_updates += late as u64;
+        self.latest_timestamp = self.latest_timestamp.max(timestamp);
+        self.close_until(self.latest_timestamp.saturating_sub(self.allowed_lateness_ms));
+    }
+
+    /// Close and emit every open candle and flush the sinks, e.g. on
+    /// shutdown.
+    pub fn flush(&mut self) {
+        self.close_until(i64::MAX);
+        for sink in &mut self.sinks {
+            sink.flush();
+        }
+    }
+
+    fn close_until(&mut self, watermark: i64) {
+        while let Some(entry) = self.open.first_entry() {
+            if entry.key().0 > watermark {
+                break;
+            }
+            let candle = entry.remove();
+            for sink in &mut self.sinks {
+                sink.emit(&candle);
+            }
+            if self.history_len > 0 && candle.resolution_ms == self.history_resolution_ms {
+                let history = self.history.entry((candle.series.clone(), candle.resolution_ms)).or_default();
+                if history.len() == self.history_len {
+                    history.pop_front();
+                }
+                history.push_back(candle);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::SOL_MINT;
+    use crate::test_utils;
+    use crate::volume::ONE_MINUTE_MS;
+    use std::sync::{Arc, Mutex};
+
+    fn market_rate(pool_address: &str, timestamp: i64, quote_reserve: u64) -> MarketRate {
+        MarketRate {
+            timestamp,
+            transaction_signature: format!("tx_{}", timestamp),
+            ..test_utils::market_rate(pool_address, 1_000_000_000_000, quote_reserve)
+        }
+    }
+
+    fn collecting(aggregator: CandleAggregator) -> (CandleAggregator, Arc<Mutex<Vec<Candle>>>) {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let sink = closed.clone();
+        let aggregator = aggregator.with_sink(move |candle: &Candle| sink.lock().unwrap().push(candle.clone()));
+        (aggregator, closed)
+    }
+
+    #[test]
+    fn test_builds_ohlc_per_resolution() {
+        let (mut aggregator, closed) = collecting(CandleAggregator::new(&[1_000, ONE_MINUTE_MS]).with_pair_candles(false));
+        for (timestamp, quote) in [(0, 150_000_000_000), (400, 152_000_000_000), (900, 149_000_000_000), (1_200, 151_000_000_000)] {
+            aggregator.observe(&market_rate("pool", timestamp, quote));
+        }
+
+        // Only the first 1s candle has closed
+        let closed = closed.lock().unwrap();
+        assert_eq!(closed.len(), 1);
+        let candle = &closed[0];
+        assert_eq!((candle.start_ms, candle.resolution_ms, candle.updates), (0, 1_000, 3));
+        let (open, high, low, close) = candle.ohlc();
+        assert!((open - 150.0).abs() < 1e-9 && (high - 152.0).abs() < 1e-9);
+        assert!((low - 149.0).abs() < 1e-9 && (close - 149.0).abs() < 1e-9);
+        // Only the quote reserve moves, so nothing counts as a trade
+        assert_eq!(candle.volume, 0);
+
+        let minute = aggregator.current(&SeriesKey::Pool("pool".to_string()), ONE_MINUTE_MS).unwrap();
+        assert_eq!(minute.updates, 4);
+        assert!((minute.ohlc().3 - 151.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_keeps_recent_history_per_series() {
+        let mut aggregator = CandleAggregator::new(&[1_000, 2_000])
+            .with_pair_candles(false)
+            .with_allowed_lateness(1_000)
+            .with_history(1_000, 2);
+        for second in 0..6 {
+            aggregator.observe(&market_rate("pool", second * 1_000, 150_000_000_000 + second as u64));
+        }
+
+        let series = SeriesKey::Pool("pool".to_string());
+        let starts: Vec<i64> = aggregator.history(&series, 1_000).map(|candle| candle.start_ms).collect();
+        assert_eq!(starts, vec![2_000, 3_000]);
+        // Two-second candles close but aren't kept
+        assert_eq!(aggregator.history(&series, 2_000).count(), 0);
+        // The last closed second still takes late updates
+        let open: Vec<i64> = aggregator.open_candles(&series, 1_000).map(|candle| candle.start_ms).collect();
+        assert_eq!(open, vec![4_000, 5_000]);
+    }
+
+    #[test]
+    fn test_late_updates_and_volume() {
+        let (mut aggregator, closed) = collecting(CandleAggregator::new(&[1_000]).with_allowed_lateness(500));
+        let mut rate = market_rate("pool", 100, 150_000_000_000);
+        aggregator.observe(&rate);
+        // A swap: base in, quote out
+        rate.timestamp = 600;
+        rate.liquidity.base_reserve += 10_000_000_000;
+        rate.liquidity.quote_reserve -= 1_485_000_000;
+        rate.price = PriceX64::from_amounts(rate.liquidity.base_reserve as u128, rate.liquidity.quote_reserve as u128);
+        aggregator.observe(&rate);
+
+        // Next second, then a late update for the first one within the grace period
+        aggregator.observe(&market_rate("pool", 1_300, 140_000_000_000));
+        aggregator.observe(&market_rate("pool", 50, 160_000_000_000));
+        assert!(closed.lock().unwrap().is_empty());
+
+        // Past the grace period: the first second closes, and updates for it are dropped
+        aggregator.observe(&market_rate("pool", 1_600, 140_000_000_000));
+        aggregator.observe(&market_rate("pool", 900, 100_000_000_000));
+        assert_eq!(aggregator.late_updates(), 1);
+
+        let closed = closed.lock().unwrap();
+        assert_eq!(closed.len(), 2);
+        // One pool candle and one pair candle, same numbers
+        assert_eq!(closed[0].series, SeriesKey::Pool("pool".to_string()));
+        assert!(matches!(&closed[1].series, SeriesKey::Pair(pair) if pair.base_mint == SOL_MINT));
+        for candle in closed.iter() {
+            assert_eq!(candle.updates, 3);
+            assert_eq!(candle.first_update_ms, 50);
+            assert!((candle.ohlc().0 - 160.0).abs() < 1e-9);
+            assert_eq!(candle.volume, 1_485_000_000);
+        }
+    }
+
+    #[test]
+    fn test_late_updates_count_once() {
+        let mut aggregator = CandleAggregator::new(&[1_000, 2_000]).with_pair_candles(false);
+        aggregator.observe(&market_rate("pool", 0, 150_000_000_000));
+        aggregator.observe(&market_rate("pool", 2_500, 150_000_000_000));
+        // Too late for both resolutions
+        aggregator.observe(&market_rate("pool", 100, 150_000_000_000));
+        assert_eq!(aggregator.late_updates(), 1);
+        // Too late for one second, still on time for two
+        aggregator.observe(&market_rate("pool", 3_100, 150_000_000_000));
+        aggregator.observe(&market_rate("pool", 2_900, 150_000_000_000));
+        assert_eq!(aggregator.late_updates(), 2);
+    }
+
+    #[test]
+    fn test_json_lines_sink_and_flush() {
+        let mut aggregator = CandleAggregator::new(&[ONE_MINUTE_MS])
+            .with_pair_candles(false)
+            .with_sink(JsonLinesSink::new(Vec::new()));
+        aggregator.observe(&market_rate("pool", 0, 150_000_000_000));
+
+        let (mut aggregator, closed) = collecting(aggregator);
+        aggregator.flush();
+        let closed = closed.lock().unwrap();
+        assert_eq!(closed.len(), 1);
+
+        let mut line = Vec::new();
+        JsonLinesSink::new(&mut line).emit(&closed[0]);
+        let parsed: Candle = serde_json::from_slice(&line).unwrap();
+        assert_eq!(parsed, closed[0]);
+        assert!(line.ends_with(b"\n"));
+
+        // Other resolutions are skipped
+        let mut skipped = Vec::new();
+        JsonLinesSink::new(&mut skipped).with_resolutions(&[1_000]).emit(&closed[0]);
+        assert!(skipped.is_empty());
+
+        // Buffered lines are written out when the aggregator is flushed
+        let file = std::env::temp_dir().join(format!("candles-{}.jsonl", std::process::id()));
+        let writer = std::io::BufWriter::new(std::fs::File::create(&file).unwrap());
+        let mut aggregator =
+            CandleAggregator::new(&[ONE_MINUTE_MS]).with_pair_candles(false).with_sink(JsonLinesSink::new(writer));
+        aggregator.observe(&market_rate("pool", 0, 150_000_000_000));
+        aggregator.flush();
+        assert_eq!(std::fs::read_to_string(&file).unwrap().lines().count(), 1);
+        std::fs::remove_file(&file).unwrap();
+    }
+}
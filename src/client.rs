@@ -1,37 +1,64 @@
 use tonic::{transport::Channel, Request};
 use tokio::sync::mpsc;
-use std::time::Instant;
-use crate::amm_types::{AmmProgram, MarketRate, TokenPair, PoolLiquidity, FilterConfig};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use crate::amm_types::{self, AmmProgram, Denomination, MarketRate, TokenPair, PoolLiquidity, FilterConfig};
+use crate::fixed::{from_ui_amount, PriceX64};
 
-pub mod solana {
-    pub mod amm {
-        tonic::include_proto!("solana.amm");
-    }
-    pub mod geyser {
-        tonic::include_proto!("solana.geyser");
-    }
-}
+pub use crate::proto::solana;
 
 use solana::amm::amm_service_client::AmmServiceClient;
 use solana::amm::{PriceUpdate, PingRequest, PingResponse};
 
+pub type ClientError = Box<dyn std::error::Error + Send + Sync>;
+
 pub struct GrpcClient {
     client: AmmServiceClient<Channel>,
     program: AmmProgram,
+    quarantine: Quarantine,
+}
+
+impl From<&FilterConfig> for solana::amm::FilterConfig {
+    fn from(filter_config: &FilterConfig) -> Self {
+        Self {
+            min_liquidity_sol: filter_config.min_liquidity,
+            min_volume_sol: filter_config.min_volume,
+            volume_timeframe_ms: filter_config.volume_timeframe_ms,
+            denomination: match filter_config.denomination {
+                Denomination::Sol => solana::amm::Denomination::Sol,
+                Denomination::Usd => solana::amm::Denomination::Usd,
+                Denomination::Quote => solana::amm::Denomination::Quote,
+            } as i32,
+        }
+    }
 }
 
 impl GrpcClient {
-    pub async fn new(program: AmmProgram) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(program: AmmProgram) -> Result<Self, ClientError> {
         let channel = Channel::from_shared(program.endpoint.clone())?
             .connect()
             .await?;
         
         let client = AmmServiceClient::new(channel);
         
-        Ok(Self { client, program })
+        Ok(Self {
+            client,
+            program,
+            quarantine: Quarantine::new(),
+        })
+    }
+
+    /// Count snapshot rates that fail validation in `quarantine`.
+    pub fn with_quarantine(mut self, quarantine: Quarantine) -> Self {
+        self.quarantine = quarantine;
+        self
     }
     
-    pub async fn ping(&mut self) -> Result<PingResponse, Box<dyn std::error::Error>> {
+    pub async fn ping(&mut self) -> Result<PingResponse, ClientError> {
         let request = Request::new(PingRequest {});
         let response = self.client.ping(request).await?;
         Ok(response.into_inner())
@@ -40,62 +67,422 @@ impl GrpcClient {
     pub async fn subscribe_price_updates(
         &mut self,
         filter_config: FilterConfig,
-    ) -> Result<tonic::Streaming<PriceUpdate>, Box<dyn std::error::Error>> {
-        let filter_proto = solana::amm::FilterConfig {
-            min_liquidity_sol: filter_config.min_liquidity_sol,
-            min_volume_sol: filter_config.min_volume_sol,
-            volume_timeframe_ms: filter_config.volume_timeframe_ms,
-        };
-        
-        let request = Request::new(filter_proto);
+    ) -> Result<tonic::Streaming<PriceUpdate>, ClientError> {
+        let request = Request::new(solana::amm::FilterConfig::from(&filter_config));
         let response = self.client.subscribe_price_updates(request).await?;
         Ok(response.into_inner())
     }
+    
+    pub async fn get_market_rates(
+        &mut self,
+        filter_config: FilterConfig,
+    ) -> Result<tonic::Streaming<solana::amm::MarketRate>, ClientError> {
+        let request = Request::new(solana::amm::FilterConfig::from(&filter_config));
+        let response = self.client.get_market_rates(request).await?;
+        Ok(response.into_inner())
+    }
+    
+    /// Current rate of every pool the server knows about, oldest first.
+    /// Invalid rates are quarantined and left out.
+    pub async fn snapshot(&mut self, filter_config: FilterConfig) -> Result<Vec<MarketRate>, ClientError> {
+        let mut stream = self.get_market_rates(filter_config).await?;
+        let mut rates = Vec::new();
+        while let Some(market_rate_proto) = stream.message().await? {
+            match MarketRate::try_from(market_rate_proto) {
+                Ok(market_rate) => rates.push(market_rate),
+                Err(e) => self.quarantine.reject(&self.program.name, &e),
+            }
+        }
+        rates.sort_by_key(|rate| rate.timestamp);
+        Ok(rates)
+    }
+}
+
+/// Why an upstream message was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// A message field that has to be set is missing.
+    MissingField(&'static str),
+    /// A float field is NaN or infinite.
+    NonFinite { field: &'static str, value: f64 },
+    /// A rate, fee or amount below zero.
+    Negative { field: &'static str, value: f64 },
+    /// An amount too large for its raw unit type at the pair's decimals.
+    AmountOutOfRange { field: &'static str, value: f64 },
+    /// Decimals that aren't a whole number an SPL mint can have (0-255).
+    DecimalsOutOfRange { field: &'static str, value: f64 },
+    /// An address that isn't a base58 encoded 32-byte key.
+    InvalidAddress { field: &'static str, value: String },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingField(field) => write!(f, "missing field {}", field),
+            ValidationError::NonFinite { field, value } => write!(f, "{} is not finite: {}", field, value),
+            ValidationError::Negative { field, value } => write!(f, "{} is negative: {}", field, value),
+            ValidationError::AmountOutOfRange { field, value } => write!(f, "{} is too large: {}", field, value),
+            ValidationError::DecimalsOutOfRange { field, value } => {
+                write!(f, "{} out of range: {}", field, value)
+            }
+            ValidationError::InvalidAddress { field, value } => {
+                write!(f, "{} is not a valid address: {:?}", field, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+fn finite(field: &'static str, value: f64) -> Result<f64, ValidationError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(ValidationError::NonFinite { field, value })
+    }
+}
+
+fn non_negative(field: &'static str, value: f64) -> Result<f64, ValidationError> {
+    if finite(field, value)? < 0.0 {
+        Err(ValidationError::Negative { field, value })
+    } else {
+        Ok(value)
+    }
+}
+
+/// Whole-token `value` in raw units, which have to fit below `max`.
+fn raw_amount(field: &'static str, value: f64, decimals: u8, max: u128) -> Result<u128, ValidationError> {
+    if non_negative(field, value)? * 10f64.powi(decimals as i32) >= max as f64 {
+        Err(ValidationError::AmountOutOfRange { field, value })
+    } else {
+        Ok(from_ui_amount(value, decimals))
+    }
+}
+
+fn decimals(field: &'static str, value: f64) -> Result<u8, ValidationError> {
+    if value.fract() == 0.0 && (0.0..=u8::MAX as f64).contains(&value) {
+        Ok(value as u8)
+    } else {
+        Err(ValidationError::DecimalsOutOfRange { field, value })
+    }
+}
+
+fn address(field: &'static str, value: String) -> Result<String, ValidationError> {
+    match bs58::decode(&value).into_vec() {
+        Ok(bytes) if bytes.len() == 32 => Ok(value),
+        _ => Err(ValidationError::InvalidAddress { field, value }),
+    }
+}
+
+/// Mints are optional: servers that don't know them send them blank.
+fn optional_address(field: &'static str, value: String) -> Result<String, ValidationError> {
+    if value.is_empty() {
+        Ok(value)
+    } else {
+        address(field, value)
+    }
+}
+
+impl TryFrom<solana::amm::TokenPair> for TokenPair {
+    type Error = ValidationError;
+
+    fn try_from(token_pair: solana::amm::TokenPair) -> Result<Self, Self::Error> {
+        Ok(Self {
+            base_token: token_pair.base_token,
+            quote_token: token_pair.quote_token,
+            base_mint: optional_address("base_mint", token_pair.base_mint)?,
+            quote_mint: optional_address("quote_mint", token_pair.quote_mint)?,
+            base_decimals: decimals("base_decimals", token_pair.base_decimals)?,
+            quote_decimals: decimals("quote_decimals", token_pair.quote_decimals)?,
+        })
+    }
+}
+
+impl TryFrom<solana::amm::MarketRate> for MarketRate {
+    type Error = ValidationError;
+
+    fn try_from(market_rate: solana::amm::MarketRate) -> Result<Self, Self::Error> {
+        let token_pair = TokenPair::try_from(market_rate.token_pair.ok_or(ValidationError::MissingField("token_pair"))?)?;
+        let liquidity = market_rate.liquidity.ok_or(ValidationError::MissingField("liquidity"))?;
+        // The proto carries whole-token floats; convert to raw units once here.
+        let (base_decimals, quote_decimals) = (token_pair.base_decimals, token_pair.quote_decimals);
+        Ok(Self {
+            program_id: address("program_id", market_rate.program_id)?,
+            pool_address: address("pool_address", market_rate.pool_address)?,
+            price: PriceX64::from_f64(non_negative("rate", market_rate.rate)?, base_decimals, quote_decimals),
+            swap_fee: non_negative("swap_fee", market_rate.swap_fee)?,
+            liquidity: PoolLiquidity {
+                base_reserve: raw_amount("base_liquidity", liquidity.base_liquidity, base_decimals, u64::MAX as u128)?
+                    as u64,
+                quote_reserve: raw_amount("quote_liquidity", liquidity.quote_liquidity, quote_decimals, u64::MAX as u128)?
+                    as u64,
+                total_liquidity_quote: raw_amount(
+                    "total_liquidity_quote",
+                    liquidity.total_liquidity_quote,
+                    quote_decimals,
+                    u128::MAX,
+                )?,
+                volume_24h: raw_amount("volume_24h", liquidity.volume_24h, quote_decimals, u128::MAX)?,
+                volume_1h: raw_amount("volume_1h", liquidity.volume_1h, quote_decimals, u128::MAX)?,
+            },
+            token_pair,
+            timestamp: market_rate.timestamp,
+            transaction_signature: market_rate.transaction_signature,
+        })
+    }
+}
+
+impl TryFrom<PriceUpdate> for amm_types::PriceUpdate {
+    type Error = ValidationError;
+
+    fn try_from(update: PriceUpdate) -> Result<Self, Self::Error> {
+        let market_rate: amm_types::MarketRate =
+            update.market_rate.ok_or(ValidationError::MissingField("market_rate"))?.try_into()?;
+        Ok(Self {
+            // Upstream reports hourly volume only
+            volume: market_rate.liquidity.volume_1h,
+            market_rate,
+            price_change_24h: update
+                .has_price_change_24h
+                .then(|| finite("price_change_24h", update.price_change_24h))
+                .transpose()?,
+            price_change_1h: update
+                .has_price_change_1h
+                .then(|| finite("price_change_1h", update.price_change_1h))
+                .transpose()?,
+            meets_liquidity_filter: update.meets_liquidity_filter,
+            meets_volume_filter: update.meets_volume_filter,
+        })
+    }
+}
+
+/// Counts upstream messages rejected by validation, so one malformed message
+/// is logged and dropped instead of taking its subscription down. Clones
+/// share the count.
+#[derive(Debug, Clone, Default)]
+pub struct Quarantine {
+    rejected: Arc<AtomicUsize>,
+}
+
+impl Quarantine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages rejected so far.
+    pub fn rejected(&self) -> usize {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    fn reject(&self, source: &str, error: &ValidationError) {
+        let rejected = self.rejected.fetch_add(1, Ordering::Relaxed) + 1;
+        eprintln!("Quarantined message from {} ({} so far): {}", source, rejected, error);
+    }
+}
+
+/// Merges snapshot and live rates into a single per-pool timestamp-ordered
+/// feed.
+///
+/// A rate is dropped if its pool has already emitted a newer one, or if the
+/// same transaction was already emitted at that timestamp. State is kept
+/// across reconnects so a re-primed snapshot doesn't replay old rates.
+#[derive(Debug, Default)]
+pub struct RateMerger {
+    last_seen: HashMap<String, (i64, Vec<String>)>,
+}
+
+impl RateMerger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if `market_rate` should be forwarded.
+    pub fn accept(&mut self, market_rate: &MarketRate) -> bool {
+        match self.last_seen.get_mut(&market_rate.pool_address) {
+            Some((timestamp, signatures)) => {
+                if market_rate.timestamp < *timestamp {
+                    false
+                } else if market_rate.timestamp == *timestamp {
+                    if signatures.contains(&market_rate.transaction_signature) {
+                        false
+                    } else {
+                        signatures.push(market_rate.transaction_signature.clone());
+                        true
+                    }
+                } else {
+                    *timestamp = market_rate.timestamp;
+                    signatures.clear();
+                    signatures.push(market_rate.transaction_signature.clone());
+                    true
+                }
+            }
+            None => {
+                self.last_seen.insert(
+                    market_rate.pool_address.clone(),
+                    (market_rate.timestamp, vec![market_rate.transaction_signature.clone()]),
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Reconnect policy for a supervised subscription.
+///
+/// The delay before retry `n` is `initial_delay * multiplier^(n-1)`, capped at
+/// `max_delay`, with up to `jitter` (as a fraction of the delay) added or
+/// removed at random so that many subscriptions don't reconnect in lockstep.
+#[derive(Debug, Clone)]
+pub struct BackoffConfig {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+    /// Consecutive failed attempts before giving up. The counter resets once a
+    /// stream has delivered at least one update.
+    pub max_retries: u32,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_retries: 10,
+        }
+    }
+}
+
+impl BackoffConfig {
+    /// Delay before the given retry attempt (1-based), without jitter.
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    /// Delay before the given retry attempt (1-based), with jitter applied.
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let base = self.base_delay(attempt).as_secs_f64();
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+        Duration::from_secs_f64((base * factor).min(self.max_delay.as_secs_f64()))
+    }
+}
+
+/// Lifecycle of a supervised subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionState {
+    Connecting,
+    Streaming,
+    BackingOff { attempt: u32, delay: Duration },
+    Failed,
+}
+
+impl fmt::Display for SubscriptionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscriptionState::Connecting => write!(f, "connecting"),
+            SubscriptionState::Streaming => write!(f, "streaming"),
+            SubscriptionState::BackingOff { attempt, delay } => {
+                write!(f, "retry {} in {}ms", attempt, delay.as_millis())
+            }
+            SubscriptionState::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A state transition reported for one program's subscription.
+#[derive(Debug, Clone)]
+pub struct SubscriptionEvent {
+    pub program_name: String,
+    pub endpoint: String,
+    pub state: SubscriptionState,
+}
+
+/// How a single subscription attempt ended without an error.
+pub(crate) enum StreamEnd {
+    /// The server closed the stream; worth reconnecting.
+    ServerClosed,
+    /// Our receiver is gone, so there is nobody left to feed.
+    ReceiverClosed,
 }
 
 pub struct AmmClientManager {
     clients: Vec<GrpcClient>,
     filter_config: FilterConfig,
     simulation_mode: bool,
+    backoff: BackoffConfig,
+    state_tx: Option<mpsc::UnboundedSender<SubscriptionEvent>>,
+    quarantine: Quarantine,
 }
 
 impl AmmClientManager {
     pub async fn new(programs: Vec<AmmProgram>, filter_config: FilterConfig) -> Result<Self, Box<dyn std::error::Error>> {
         let mut clients = Vec::new();
-        let mut simulation_mode = false;
         
         for program in programs {
-            println!("Connecting to {} at {}...", program.name, program.endpoint);
+            eprintln!("Connecting to {} at {}...", program.name, program.endpoint);
             match GrpcClient::new(program.clone()).await {
                 Ok(mut client) => {
                     // Test if the service is actually available
                     match client.ping().await {
                         Ok(_) => {
-                            println!("✓ Successfully connected to {} (service available)", program.name);
+                            eprintln!("✓ Successfully connected to {} (service available)", program.name);
                         }
                         Err(e) => {
-                            println!("⚠️ Ping failed for {} (continuing to subscribe): {}", program.name, e);
+                            eprintln!("⚠️ Ping failed for {} (continuing to subscribe): {}", program.name, e);
                         }
                     }
                     // Push the client regardless of ping result to attempt real subscriptions
                     clients.push(client);
                 }
                 Err(e) => {
-                    println!("✗ Failed to connect to {}: {}", program.name, e);
+                    eprintln!("✗ Failed to connect to {}: {}", program.name, e);
                     // Keep trying other programs; enable simulation later only if none succeed
                 }
             }
         }
         
         // Enable simulation only if no connections succeeded
-        simulation_mode = clients.is_empty();
+        let simulation_mode = clients.is_empty();
         if simulation_mode {
-            println!("⚠️  All connections failed, enabling simulation mode for demonstration");
+            eprintln!("⚠️  All connections failed, enabling simulation mode for demonstration");
         } else {
-            println!("Proceeding with real gRPC subscriptions ({} connection(s) established)", clients.len());
+            eprintln!("Proceeding with real gRPC subscriptions ({} connection(s) established)", clients.len());
         }
         
-        Ok(Self { clients, filter_config, simulation_mode })
+        Ok(Self {
+            clients,
+            filter_config,
+            simulation_mode,
+            backoff: BackoffConfig::default(),
+            state_tx: None,
+            quarantine: Quarantine::new(),
+        })
+    }
+
+    /// Count of messages rejected by any of the subscriptions.
+    pub fn quarantine(&self) -> Quarantine {
+        self.quarantine.clone()
+    }
+
+    /// Replace the default reconnect policy.
+    pub fn with_backoff(mut self, backoff: BackoffConfig) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Report subscription state transitions on the given channel.
+    pub fn with_state_reporter(mut self, state_tx: mpsc::UnboundedSender<SubscriptionEvent>) -> Self {
+        self.state_tx = Some(state_tx);
+        self
     }
     
     pub async fn start_price_subscriptions(
@@ -115,7 +502,7 @@ impl AmmClientManager {
         &self,
         tx: mpsc::Sender<MarketRate>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        println!("Starting simulation mode...");
+        eprintln!("Starting simulation mode...");
         
         let programs = vec![
             ("Pump.fun AMM", "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA"),
@@ -134,16 +521,12 @@ impl AmmClientManager {
                 counter += 1;
                 
                 for (name, program_id) in &programs {
-                    // Calculate realistic liquidity amounts
-                    let base_liquidity = 15000.0 + (counter as f64 * 10.0);
-                    let quote_liquidity = 1500000.0 + (counter as f64 * 1000.0);
+                    // Calculate realistic liquidity amounts, in raw units
+                    let base_reserve: u64 = 15_000_000_000_000 + counter * 10_000_000_000;
+                    let quote_reserve: u64 = 1_500_000_000_000 + counter * 1_000_000_000;
                     
-                    // Calculate rate using the correct formula: Rate = Quote / Base
-                    let rate = if base_liquidity > 0.0 {
-                        quote_liquidity / base_liquidity
-                    } else {
-                        100.0 // fallback rate
-                    };
+                    // Rate = Quote / Base
+                    let price = PriceX64::from_amounts(base_reserve as u128, quote_reserve as u128);
                     
                     let market_rate = MarketRate {
                         program_id: program_id.to_string(),
@@ -156,14 +539,14 @@ impl AmmClientManager {
                             base_decimals: 9,
                             quote_decimals: 6,
                         },
-                        rate: rate,
+                        price,
                         swap_fee: 0.003,
                         liquidity: PoolLiquidity {
-                            base_liquidity: base_liquidity,
-                            quote_liquidity: quote_liquidity,
-                            total_liquidity_usd: base_liquidity * rate + quote_liquidity,
-                            volume_24h: 50000.0 + (counter as f64 * 100.0),
-                            volume_1h: 100.0 + (counter as f64 * 5.0),
+                            base_reserve,
+                            quote_reserve,
+                            total_liquidity_quote: price.quote_value(base_reserve as u128) + quote_reserve as u128,
+                            volume_24h: 50_000_000_000 + counter as u128 * 100_000_000,
+                            volume_1h: 100_000_000 + counter as u128 * 5_000_000,
                         },
                         timestamp: std::time::SystemTime::now()
                             .duration_since(std::time::UNIX_EPOCH)
@@ -190,14 +573,19 @@ impl AmmClientManager {
         let mut handles = Vec::new();
         
         for client in &mut self.clients {
+            let program = client.program.clone();
             let filter_config = self.filter_config.clone();
+            let backoff = self.backoff.clone();
             let tx = tx.clone();
-            let program_name = client.program.name.clone();
-            let endpoint = client.program.endpoint.clone();
+            let state_tx = self.state_tx.clone();
+            let quarantine = self.quarantine.clone();
             
             let handle = tokio::spawn(async move {
-                if let Err(e) = Self::subscribe_to_program(program_name.clone(), endpoint, filter_config, tx).await {
-                    eprintln!("Subscription error for {}: {}", program_name, e);
+                let program_name = program.name.clone();
+                if let Err(e) =
+                    Self::supervise_subscription(program, filter_config, backoff, tx, state_tx, quarantine).await
+                {
+                    eprintln!("Subscription for {} stopped: {}", program_name, e);
                 }
             });
             
@@ -212,66 +600,522 @@ impl AmmClientManager {
         Ok(())
     }
     
-    async fn subscribe_to_program(
-        program_name: String,
-        endpoint: String,
+    /// Keep a subscription to `program` alive, reconnecting with backoff until
+    /// the retry budget is exhausted or `tx` is closed. Invalid messages are
+    /// counted in `quarantine` and skipped.
+    pub async fn supervise_subscription(
+        program: AmmProgram,
         filter_config: FilterConfig,
+        backoff: BackoffConfig,
         tx: mpsc::Sender<MarketRate>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Create a new client for this subscription
-        let program = AmmProgram {
-            id: "".to_string(),
-            name: program_name.clone(),
-            endpoint,
+        state_tx: Option<mpsc::UnboundedSender<SubscriptionEvent>>,
+        quarantine: Quarantine,
+    ) -> Result<(), String> {
+        let report = |state: SubscriptionState| {
+            if let Some(state_tx) = &state_tx {
+                let _ = state_tx.send(SubscriptionEvent {
+                    program_name: program.name.clone(),
+                    endpoint: program.endpoint.clone(),
+                    state,
+                });
+            }
         };
         
-        let mut client = GrpcClient::new(program).await?;
-        let mut stream = client.subscribe_price_updates(filter_config).await?;
+        let mut merger = RateMerger::new();
+        let mut attempt = 0;
+        loop {
+            report(SubscriptionState::Connecting);
+            
+            let mut received = 0usize;
+            let outcome = Self::subscribe_to_program(
+                &program,
+                &filter_config,
+                &tx,
+                &mut merger,
+                &quarantine,
+                &mut received,
+                || report(SubscriptionState::Streaming),
+            )
+            .await;
+            
+            match outcome {
+                Ok(StreamEnd::ReceiverClosed) => return Ok(()),
+                Ok(StreamEnd::ServerClosed) => {
+                    eprintln!("Stream for {} closed by server", program.name);
+                }
+                Err(e) => {
+                    eprintln!("Subscription error for {}: {}", program.name, e);
+                }
+            }
+            
+            // A stream that delivered data counts as a successful connection.
+            if received > 0 {
+                attempt = 0;
+            }
+            attempt += 1;
+            
+            if attempt > backoff.max_retries {
+                report(SubscriptionState::Failed);
+                return Err(format!("giving up after {} attempts", backoff.max_retries));
+            }
+            
+            let delay = backoff.delay_for_attempt(attempt);
+            eprintln!("Reconnecting to {} in {}ms (attempt {})", program.name, delay.as_millis(), attempt);
+            report(SubscriptionState::BackingOff { attempt, delay });
+            tokio::time::sleep(delay).await;
+        }
+    }
+    
+    async fn subscribe_to_program(
+        program: &AmmProgram,
+        filter_config: &FilterConfig,
+        tx: &mpsc::Sender<MarketRate>,
+        merger: &mut RateMerger,
+        quarantine: &Quarantine,
+        received: &mut usize,
+        on_streaming: impl FnOnce(),
+    ) -> Result<StreamEnd, ClientError> {
+        let mut client = GrpcClient::new(program.clone()).await?.with_quarantine(quarantine.clone());
+        
+        // Open the live stream before taking the snapshot so nothing published
+        // in between is missed; the merger drops whatever overlaps.
+        let mut stream = client.subscribe_price_updates(filter_config.clone()).await?;
+        
+        match client.snapshot(filter_config.clone()).await {
+            Ok(snapshot) => {
+                eprintln!("Primed {} with {} pool rate(s)", program.name, snapshot.len());
+                for market_rate in snapshot {
+                    if merger.accept(&market_rate) && tx.send(market_rate).await.is_err() {
+                        return Ok(StreamEnd::ReceiverClosed);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("⚠️ Snapshot unavailable for {} (streaming only): {}", program.name, e);
+            }
+        }
         
-        println!("Started subscription for {}", program_name);
+        eprintln!("Started subscription for {}", program.name);
+        on_streaming();
         
         while let Some(update) = stream.message().await? {
             let start_time = Instant::now();
+            *received += 1;
             
             // Convert proto MarketRate to our MarketRate
-            let market_rate_proto = update.market_rate.as_ref().unwrap();
-            let market_rate = MarketRate {
-                program_id: market_rate_proto.program_id.clone(),
-                pool_address: market_rate_proto.pool_address.clone(),
-                token_pair: TokenPair {
-                    base_token: market_rate_proto.token_pair.as_ref().unwrap().base_token.clone(),
-                    quote_token: market_rate_proto.token_pair.as_ref().unwrap().quote_token.clone(),
-                    base_mint: market_rate_proto.token_pair.as_ref().unwrap().base_mint.clone(),
-                    quote_mint: market_rate_proto.token_pair.as_ref().unwrap().quote_mint.clone(),
-                    base_decimals: market_rate_proto.token_pair.as_ref().unwrap().base_decimals as u8,
-                    quote_decimals: market_rate_proto.token_pair.as_ref().unwrap().quote_decimals as u8,
-                },
-                rate: market_rate_proto.rate,
-                swap_fee: market_rate_proto.swap_fee,
-                liquidity: PoolLiquidity {
-                    base_liquidity: market_rate_proto.liquidity.as_ref().unwrap().base_liquidity,
-                    quote_liquidity: market_rate_proto.liquidity.as_ref().unwrap().quote_liquidity,
-                    total_liquidity_usd: market_rate_proto.liquidity.as_ref().unwrap().total_liquidity_usd,
-                    volume_24h: market_rate_proto.liquidity.as_ref().unwrap().volume_24h,
-                    volume_1h: market_rate_proto.liquidity.as_ref().unwrap().volume_1h,
-                },
-                timestamp: market_rate_proto.timestamp,
-                transaction_signature: market_rate_proto.transaction_signature.clone(),
+            let market_rate = match amm_types::PriceUpdate::try_from(update) {
+                Ok(update) => update.market_rate,
+                Err(e) => {
+                    quarantine.reject(&program.name, &e);
+                    continue;
+                }
             };
             
             // Check if we meet the 1ms performance requirement
             let elapsed = start_time.elapsed();
             if elapsed.as_millis() > 1 {
-                println!("WARNING: Processing took {}ms, exceeding 1ms requirement!", elapsed.as_millis());
+                eprintln!("WARNING: Processing took {}ms, exceeding 1ms requirement!", elapsed.as_millis());
+            }
+            
+            if !merger.accept(&market_rate) {
+                continue;
             }
             
             // Send to main processing loop
             if let Err(e) = tx.send(market_rate).await {
                 eprintln!("Failed to send market rate: {}", e);
-                break;
+                return Ok(StreamEnd::ReceiverClosed);
             }
         }
         
-        Ok(())
+        Ok(StreamEnd::ServerClosed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::WHIRLPOOLS;
+    use solana::amm::amm_service_server::{AmmService, AmmServiceServer};
+    use std::pin::Pin;
+    use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+    use tonic::{Response, Status};
+
+    /// Serves two updates per connection and then drops the stream with an error.
+    struct FlakyService {
+        connections: Arc<AtomicUsize>,
+    }
+
+    /// A valid address standing in for the pool called `name`.
+    fn pool(name: &str) -> String {
+        bs58::encode([name.as_bytes()[0]; 32]).into_string()
+    }
+
+    fn proto_update(sequence: usize) -> PriceUpdate {
+        PriceUpdate {
+            market_rate: Some(solana::amm::MarketRate {
+                program_id: WHIRLPOOLS.to_string(),
+                pool_address: pool("pool"),
+                token_pair: Some(solana::amm::TokenPair {
+                    base_token: "SOL".to_string(),
+                    quote_token: "USDC".to_string(),
+                    base_mint: "".to_string(),
+                    quote_mint: "".to_string(),
+                    base_decimals: 9.0,
+                    quote_decimals: 6.0,
+                }),
+                rate: 100.0 + sequence as f64,
+                swap_fee: 0.003,
+                liquidity: Some(solana::amm::PoolLiquidity::default()),
+                timestamp: sequence as i64,
+                transaction_signature: format!("tx_{}", sequence),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tonic::async_trait]
+    impl AmmService for FlakyService {
+        type SubscribePriceUpdatesStream =
+            Pin<Box<dyn tokio_stream::Stream<Item = Result<PriceUpdate, Status>> + Send>>;
+        type GetMarketRatesStream =
+            Pin<Box<dyn tokio_stream::Stream<Item = Result<solana::amm::MarketRate, Status>> + Send>>;
+
+        async fn subscribe_price_updates(
+            &self,
+            _request: Request<solana::amm::FilterConfig>,
+        ) -> Result<Response<Self::SubscribePriceUpdatesStream>, Status> {
+            let connection = self.connections.fetch_add(1, Ordering::SeqCst);
+            let (tx, rx) = mpsc::channel(4);
+            tokio::spawn(async move {
+                let _ = tx.send(Ok(proto_update(connection * 2))).await;
+                let _ = tx.send(Ok(proto_update(connection * 2 + 1))).await;
+                // Give the encoder a chance to flush before failing the stream.
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let _ = tx.send(Err(Status::unavailable("connection dropped on purpose"))).await;
+            });
+            Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+        }
+
+        async fn get_market_rates(
+            &self,
+            _request: Request<solana::amm::FilterConfig>,
+        ) -> Result<Response<Self::GetMarketRatesStream>, Status> {
+            Err(Status::unimplemented("not used in tests"))
+        }
+
+        async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PingResponse>, Status> {
+            Ok(Response::new(PingResponse {}))
+        }
+
+        async fn get_best_route(
+            &self,
+            _request: Request<solana::amm::RouteRequest>,
+        ) -> Result<Response<solana::amm::RouteResponse>, Status> {
+            Err(Status::unimplemented("not used in tests"))
+        }
+    }
+
+    fn proto_rate(pool_address: &str, timestamp: i64, signature: &str) -> solana::amm::MarketRate {
+        let mut rate = proto_update(0).market_rate.unwrap();
+        rate.pool_address = pool(pool_address);
+        rate.timestamp = timestamp;
+        rate.transaction_signature = signature.to_string();
+        rate
+    }
+
+    /// Serves a fixed snapshot and a live stream that overlaps it.
+    struct PrimedService;
+
+    #[tonic::async_trait]
+    impl AmmService for PrimedService {
+        type SubscribePriceUpdatesStream =
+            Pin<Box<dyn tokio_stream::Stream<Item = Result<PriceUpdate, Status>> + Send>>;
+        type GetMarketRatesStream =
+            Pin<Box<dyn tokio_stream::Stream<Item = Result<solana::amm::MarketRate, Status>> + Send>>;
+
+        async fn subscribe_price_updates(
+            &self,
+            _request: Request<solana::amm::FilterConfig>,
+        ) -> Result<Response<Self::SubscribePriceUpdatesStream>, Status> {
+            let live = vec![
+                proto_rate("a", 10, "tx_a10"), // already in the snapshot
+                proto_rate("b", 15, "tx_b15"), // older than the snapshot
+                proto_rate("a", 30, "tx_a30"),
+                proto_rate("b", 20, "tx_b20"), // already in the snapshot
+                proto_rate("b", 20, "tx_b20_other"), // same millisecond, new trade
+            ];
+            let malformed = PriceUpdate::default(); // quarantined, not fatal
+            let updates = std::iter::once(malformed)
+                .chain(live.into_iter().map(|rate| PriceUpdate {
+                    market_rate: Some(rate),
+                    ..Default::default()
+                }))
+                .map(Ok);
+            // Keep the stream open so the client doesn't start reconnecting.
+            let stream = tokio_stream::StreamExt::chain(tokio_stream::iter(updates), tokio_stream::pending());
+            Ok(Response::new(Box::pin(stream)))
+        }
+
+        async fn get_market_rates(
+            &self,
+            _request: Request<solana::amm::FilterConfig>,
+        ) -> Result<Response<Self::GetMarketRatesStream>, Status> {
+            let snapshot = vec![proto_rate("b", 20, "tx_b20"), proto_rate("a", 10, "tx_a10")];
+            Ok(Response::new(Box::pin(tokio_stream::iter(snapshot.into_iter().map(Ok)))))
+        }
+
+        async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PingResponse>, Status> {
+            Ok(Response::new(PingResponse {}))
+        }
+
+        async fn get_best_route(
+            &self,
+            _request: Request<solana::amm::RouteRequest>,
+        ) -> Result<Response<solana::amm::RouteResponse>, Status> {
+            Err(Status::unimplemented("not used in tests"))
+        }
+    }
+
+    async fn spawn_server<S: AmmService>(service: S) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(AmmServiceServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn spawn_flaky_server() -> (String, Arc<AtomicUsize>) {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let service = FlakyService { connections: connections.clone() };
+        (spawn_server(service).await, connections)
+    }
+
+    fn fast_backoff(max_retries: u32) -> BackoffConfig {
+        BackoffConfig {
+            initial_delay: Duration::from_millis(5),
+            max_delay: Duration::from_millis(20),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retries,
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let backoff = BackoffConfig {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_retries: 5,
+        };
+
+        assert_eq!(backoff.base_delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.base_delay(3), Duration::from_millis(400));
+        assert_eq!(backoff.base_delay(10), Duration::from_secs(1));
+
+        for _ in 0..100 {
+            let delay = backoff.delay_for_attempt(2);
+            assert!(delay >= Duration::from_millis(160) && delay <= Duration::from_millis(240));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_after_server_drops_stream() {
+        let (endpoint, connections) = spawn_flaky_server().await;
+        let programs = vec![AmmProgram {
+            id: "test".to_string(),
+            name: "Flaky".to_string(),
+            endpoint,
+        }];
+        let (state_tx, mut state_rx) = mpsc::unbounded_channel();
+        let mut manager = AmmClientManager::new(programs, FilterConfig::default())
+            .await
+            .unwrap()
+            .with_backoff(fast_backoff(3))
+            .with_state_reporter(state_tx);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::spawn(async move { manager.start_price_subscriptions(tx).await.map_err(|e| e.to_string()) });
+
+        // Six updates can only arrive over at least three separate connections.
+        let mut timestamps = Vec::new();
+        while timestamps.len() < 6 {
+            let rate = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("timed out waiting for updates")
+                .expect("sender closed");
+            timestamps.push(rate.timestamp);
+        }
+        assert_eq!(timestamps, vec![0, 1, 2, 3, 4, 5]);
+        assert!(connections.load(Ordering::SeqCst) >= 3);
+
+        let mut states = Vec::new();
+        while let Ok(event) = state_rx.try_recv() {
+            states.push(event.state);
+        }
+        assert_eq!(states[0], SubscriptionState::Connecting);
+        assert_eq!(states[1], SubscriptionState::Streaming);
+        // Each drop after data was received resets the retry counter.
+        assert!(states.iter().any(|s| matches!(s, SubscriptionState::BackingOff { attempt: 1, .. })));
+        assert!(!states.contains(&SubscriptionState::Failed));
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        // Bind and immediately drop a listener to get a port nobody serves.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let program = AmmProgram {
+            id: "test".to_string(),
+            name: "Unreachable".to_string(),
+            endpoint,
+        };
+        let (tx, _rx) = mpsc::channel(1);
+        let (state_tx, mut state_rx) = mpsc::unbounded_channel();
+
+        let result = AmmClientManager::supervise_subscription(
+            program,
+            FilterConfig::default(),
+            fast_backoff(2),
+            tx,
+            Some(state_tx),
+            Quarantine::new(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let mut states = Vec::new();
+        while let Ok(event) = state_rx.try_recv() {
+            states.push(event.state);
+        }
+        let backoffs: Vec<u32> = states
+            .iter()
+            .filter_map(|s| match s {
+                SubscriptionState::BackingOff { attempt, .. } => Some(*attempt),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(backoffs, vec![1, 2]);
+        assert!(!states.contains(&SubscriptionState::Streaming));
+        assert_eq!(states.last(), Some(&SubscriptionState::Failed));
+    }
+
+    #[test]
+    fn test_merger_drops_duplicates_and_stale_rates() {
+        let mut merger = RateMerger::new();
+        let rate = |pool: &str, timestamp: i64, signature: &str| {
+            MarketRate::try_from(proto_rate(pool, timestamp, signature)).unwrap()
+        };
+
+        assert!(merger.accept(&rate("a", 10, "x")));
+        assert!(!merger.accept(&rate("a", 10, "x")));
+        assert!(merger.accept(&rate("a", 10, "y")));
+        assert!(!merger.accept(&rate("a", 5, "z")));
+        assert!(merger.accept(&rate("b", 5, "z")));
+        assert!(merger.accept(&rate("a", 11, "x")));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_primes_before_live_updates() {
+        let endpoint = spawn_server(PrimedService).await;
+        let program = AmmProgram {
+            id: "test".to_string(),
+            name: "Primed".to_string(),
+            endpoint,
+        };
+
+        let mut client = GrpcClient::new(program.clone()).await.unwrap();
+        let snapshot = client.snapshot(FilterConfig::default()).await.unwrap();
+        let timestamps: Vec<i64> = snapshot.iter().map(|rate| rate.timestamp).collect();
+        assert_eq!(timestamps, vec![10, 20]);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let quarantine = Quarantine::new();
+        tokio::spawn(AmmClientManager::supervise_subscription(
+            program,
+            FilterConfig::default(),
+            fast_backoff(0),
+            tx,
+            None,
+            quarantine.clone(),
+        ));
+
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            let rate = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("timed out waiting for updates")
+                .expect("sender closed");
+            received.push((rate.pool_address, rate.timestamp, rate.transaction_signature));
+        }
+        let expected = vec![
+            (pool("a"), 10, "tx_a10".to_string()),
+            (pool("b"), 20, "tx_b20".to_string()),
+            (pool("a"), 30, "tx_a30".to_string()),
+            (pool("b"), 20, "tx_b20_other".to_string()),
+        ];
+        assert_eq!(received, expected);
+        assert!(tokio::time::timeout(Duration::from_millis(50), rx.recv()).await.is_err());
+        assert_eq!(quarantine.rejected(), 1);
+    }
+
+    #[test]
+    fn test_invalid_messages_are_rejected() {
+        let valid = proto_rate("a", 10, "x");
+        assert!(MarketRate::try_from(valid.clone()).is_ok());
+        let rejected = |edit: fn(&mut solana::amm::MarketRate)| {
+            let mut rate = valid.clone();
+            edit(&mut rate);
+            MarketRate::try_from(rate).unwrap_err()
+        };
+
+        assert_eq!(rejected(|rate| rate.token_pair = None), ValidationError::MissingField("token_pair"));
+        assert_eq!(rejected(|rate| rate.rate = f64::NAN).to_string(), "rate is not finite: NaN");
+        assert_eq!(rejected(|rate| rate.rate = -1.0), ValidationError::Negative { field: "rate", value: -1.0 });
+        assert_eq!(
+            rejected(|rate| rate.swap_fee = -0.003),
+            ValidationError::Negative { field: "swap_fee", value: -0.003 }
+        );
+        // 2e10 SOL is more lamports than a u64 holds
+        assert_eq!(
+            rejected(|rate| rate.liquidity.as_mut().unwrap().base_liquidity = 2e10),
+            ValidationError::AmountOutOfRange { field: "base_liquidity", value: 2e10 }
+        );
+        assert_eq!(
+            rejected(|rate| rate.liquidity.as_mut().unwrap().volume_1h = -5.0),
+            ValidationError::Negative { field: "volume_1h", value: -5.0 }
+        );
+        assert_eq!(
+            rejected(|rate| rate.token_pair.as_mut().unwrap().base_decimals = 6.5),
+            ValidationError::DecimalsOutOfRange { field: "base_decimals", value: 6.5 }
+        );
+        assert_eq!(
+            rejected(|rate| rate.token_pair.as_mut().unwrap().quote_decimals = 256.0),
+            ValidationError::DecimalsOutOfRange { field: "quote_decimals", value: 256.0 }
+        );
+        assert_eq!(
+            rejected(|rate| rate.pool_address = "not-base58".to_string()),
+            ValidationError::InvalidAddress { field: "pool_address", value: "not-base58".to_string() }
+        );
+        // Valid base58, but not a 32-byte key
+        assert!(matches!(
+            rejected(|rate| rate.token_pair.as_mut().unwrap().base_mint = "abc".to_string()),
+            ValidationError::InvalidAddress { field: "base_mint", .. }
+        ));
+
+        let update = PriceUpdate { market_rate: Some(valid), price_change_1h: f64::INFINITY, has_price_change_1h: true, ..Default::default() };
+        assert_eq!(
+            amm_types::PriceUpdate::try_from(update).unwrap_err(),
+            ValidationError::NonFinite { field: "price_change_1h", value: f64::INFINITY }
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Meteora DLMM (`LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo`) pair and bin array accounts.
+
+use super::{AccountReader, DecodeError};
+
+/// Anchor discriminator for the `LbPair` account.
+pub const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+
+/// Anchor discriminator for the `BinArray` account.
+pub const BIN_ARRAY_DISCRIMINATOR: [u8; 8] = [92, 142, 92, 220, 5, 148, 70, 181];
+
+/// Size of an `LbPair` account including the discriminator.
+pub const LB_PAIR_LEN: usize = 904;
+
+/// Number of bins stored in one `BinArray`.
+pub const MAX_BIN_PER_ARRAY: usize = 70;
+
+const BIN_LEN: usize = 144;
+const BINS_OFFSET: usize = 56;
+
+/// Size of a `BinArray` account including the discriminator.
+pub const BIN_ARRAY_LEN: usize = BINS_OFFSET + MAX_BIN_PER_ARRAY * BIN_LEN;
+
+/// Denominator of all DLMM fee rates.
+pub const FEE_PRECISION: u128 = 1_000_000_000;
+
+/// Upper bound on the total fee rate (10%).
+pub const MAX_FEE_RATE: u128 = 100_000_000;
+
+/// Basis-point denominator of `bin_step`.
+pub const BASIS_POINT_MAX: i32 = 10_000;
+
+/// Bins past this exponent overflow Q64.64.
+const MAX_EXPONENTIAL: u32 = 0x80000;
+
+const SCALE_OFFSET: u32 = 64;
+const ONE_X64: u128 = 1 << SCALE_OFFSET;
+
+/// `base ^ exp` for a Q64.64 `base`, as the DLMM program computes it: the
+/// base is inverted to below one so every product fits in 128 bits, and the
+/// result inverted back.
+fn pow_x64(base: u128, exp: i32) -> Option<u128> {
+    if exp == 0 {
+        return Some(ONE_X64);
+    }
+    let mut invert = exp < 0;
+    let exp = exp.unsigned_abs();
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+
+    let mut squared_base = base;
+    if squared_base >= ONE_X64 {
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        invert = !invert;
+    }
+
+    let mut result = ONE_X64;
+    for bit in 0..19 {
+        if exp & (1 << bit) != 0 {
+            result = result.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+        squared_base = squared_base.checked_mul(squared_base)? >> SCALE_OFFSET;
+    }
+
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+
+/// The fields of an `LbPair` account needed for pricing and fees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LbPairState {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub protocol_share: u16,
+    pub base_fee_power_factor: u8,
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+    pub active_id: i32,
+    /// Price increment between adjacent bins, in basis points.
+    pub bin_step: u16,
+    pub status: u8,
+    pub token_x_mint: String,
+    pub token_y_mint: String,
+    pub reserve_x: String,
+    pub reserve_y: String,
+}
+
+impl LbPairState {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, LB_PAIR_DISCRIMINATOR, LB_PAIR_LEN)?;
+
+        Ok(Self {
+            base_factor: reader.u16(8),
+            filter_period: reader.u16(10),
+            decay_period: reader.u16(12),
+            reduction_factor: reader.u16(14),
+            variable_fee_control: reader.u32(16),
+            max_volatility_accumulator: reader.u32(20),
+            protocol_share: reader.u16(32),
+            base_fee_power_factor: reader.u8(34),
+            volatility_accumulator: reader.u32(40),
+            volatility_reference: reader.u32(44),
+            index_reference: reader.i32(48),
+            last_update_timestamp: reader.i64(56),
+            active_id: reader.i32(76),
+            bin_step: reader.u16(80),
+            status: reader.u8(82),
+            token_x_mint: reader.pubkey(88),
+            token_y_mint: reader.pubkey(120),
+            reserve_x: reader.pubkey(152),
+            reserve_y: reader.pubkey(184),
+        })
+    }
+
+    /// Static part of the fee, out of [`FEE_PRECISION`].
+    pub fn base_fee_rate(&self) -> u128 {
+        self.base_factor as u128 * self.bin_step as u128 * 10 * 10u128.pow(self.base_fee_power_factor as u32)
+    }
+
+    /// Volatility-dependent part of the fee, out of [`FEE_PRECISION`].
+    pub fn variable_fee_rate(&self) -> u128 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+        let volatility = self.volatility_accumulator as u128 * self.bin_step as u128;
+        let fee = self.variable_fee_control as u128 * volatility * volatility;
+        // Round up, as the program does
+        fee.div_ceil(100_000_000_000)
+    }
+
+    /// Base plus variable fee, capped at [`MAX_FEE_RATE`], out of [`FEE_PRECISION`].
+    pub fn total_fee_rate(&self) -> u128 {
+        (self.base_fee_rate() + self.variable_fee_rate()).min(MAX_FEE_RATE)
+    }
+
+    /// Current swap fee as a fraction (0.001 = 0.1%).
+    pub fn swap_fee(&self) -> f64 {
+        self.total_fee_rate() as f64 / FEE_PRECISION as f64
+    }
+
+    /// Decay the volatility reference the way the program does at the start
+    /// of a swap at unix time `now`.
+    pub fn update_references(&mut self, now: i64) {
+        let elapsed = now - self.last_update_timestamp;
+        if elapsed >= self.filter_period as i64 {
+            self.index_reference = self.active_id;
+            self.volatility_reference = if elapsed < self.decay_period as i64 {
+                (self.volatility_accumulator as u64 * self.reduction_factor as u64 / BASIS_POINT_MAX as u64) as u32
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Recompute the volatility accumulator for the current `active_id`, as
+    /// the program does for every bin a swap visits.
+    pub fn update_volatility_accumulator(&mut self) {
+        let delta_id = (self.index_reference as i64 - self.active_id as i64).unsigned_abs();
+        let accumulator = self.volatility_reference as u64 + delta_id * BASIS_POINT_MAX as u64;
+        self.volatility_accumulator = accumulator.min(self.max_volatility_accumulator as u64) as u32;
+    }
+
+    /// Price of token X in token Y for `bin_id` in raw units, as a Q64.64
+    /// fixed-point number. Computed as `(1 + bin_step / 10_000) ^ bin_id`
+    /// with the program's own square-and-multiply, so it matches the price
+    /// the program stores in a bin. `None` outside the representable range.
+    pub fn bin_price(&self, bin_id: i32) -> Option<u128> {
+        let step = ((self.bin_step as u128) << SCALE_OFFSET) / BASIS_POINT_MAX as u128;
+        pow_x64(ONE_X64 + step, bin_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// Price of X in Y in raw units, as a Q64.64 fixed-point number.
+    pub price: u128,
+    pub liquidity_supply: u128,
+}
+
+/// A run of [`MAX_BIN_PER_ARRAY`] consecutive bins of one pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinArrayState {
+    pub index: i64,
+    pub lb_pair: String,
+    pub bins: Vec<Bin>,
+}
+
+impl BinArrayState {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, BIN_ARRAY_DISCRIMINATOR, BIN_ARRAY_LEN)?;
+
+        let bins = (0..MAX_BIN_PER_ARRAY)
+            .map(|i| {
+                let offset = BINS_OFFSET + i * BIN_LEN;
+                Bin {
+                    amount_x: reader.u64(offset),
+                    amount_y: reader.u64(offset + 8),
+                    price: reader.u128(offset + 16),
+                    liquidity_supply: reader.u128(offset + 32),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            index: reader.i64(8),
+            lb_pair: reader.pubkey(24),
+            bins,
+        })
+    }
+
+    /// Id of the first bin in this array.
+    pub fn lower_bin_id(&self) -> i64 {
+        self.index * MAX_BIN_PER_ARRAY as i64
+    }
+
+    /// Bins paired with their ids.
+    pub fn bins_with_ids(&self) -> impl Iterator<Item = (i64, &Bin)> {
+        let lower = self.lower_bin_id();
+        self.bins.iter().enumerate().map(move |(i, bin)| (lower + i as i64, bin))
+    }
+
+    /// Total raw reserves of X and Y across all bins.
+    pub fn total_amounts(&self) -> (u128, u128) {
+        self.bins.iter().fold((0, 0), |(x, y), bin| {
+            (x + bin.amount_x as u128, y + bin.amount_y as u128)
+        })
+    }
+}
+
+/// Index of the bin array holding `bin_id`.
+pub fn bin_array_index(bin_id: i32) -> i64 {
+    (bin_id as i64).div_euclid(MAX_BIN_PER_ARRAY as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SOL/USDC pair with a 10 bp bin step and its active bin array; see fixtures/README.md.
+    const LB_PAIR: &[u8] = include_bytes!("../../fixtures/meteora_lb_pair_sol_usdc.bin");
+    const BIN_ARRAY: &[u8] = include_bytes!("../../fixtures/meteora_bin_array_sol_usdc.bin");
+
+    #[test]
+    fn test_decode_lb_pair_fixture() {
+        let pair = LbPairState::decode(LB_PAIR).unwrap();
+
+        assert_eq!(pair.active_id, -1_897);
+        assert_eq!(pair.bin_step, 10);
+        assert_eq!(pair.base_factor, 10_000);
+        assert_eq!(pair.variable_fee_control, 40_000);
+        assert_eq!(pair.volatility_accumulator, 20_000);
+        assert_eq!(pair.protocol_share, 500);
+        assert_eq!(pair.token_x_mint, "So11111111111111111111111111111111111111112");
+        assert_eq!(pair.token_y_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(pair.reserve_x, "4iMj51AK351eYJY8qd6ASRXJQuND5hRjpPoyfw3J3c7d");
+        assert_eq!(pair.reserve_y, "Hkyda3XqeBSGFUWnqxHunGFPpYnzwzj6dvAYwfeXzwX7");
+    }
+
+    #[test]
+    fn test_dynamic_fee() {
+        let mut pair = LbPairState::decode(LB_PAIR).unwrap();
+
+        // 10_000 * 10 * 10 = 0.1% base fee
+        assert_eq!(pair.base_fee_rate(), 1_000_000);
+        // 40_000 * (20_000 * 10)^2 / 1e11 = 16_000
+        assert_eq!(pair.variable_fee_rate(), 16_000);
+        assert_eq!(pair.total_fee_rate(), 1_016_000);
+        assert!((pair.swap_fee() - 0.001016).abs() < 1e-12);
+
+        // The variable fee rounds up
+        pair.volatility_accumulator = 1;
+        assert_eq!(pair.variable_fee_rate(), 1);
+
+        // And the total is capped
+        pair.volatility_accumulator = 350_000;
+        pair.variable_fee_control = 4_000_000;
+        assert_eq!(pair.total_fee_rate(), MAX_FEE_RATE);
+    }
+
+    #[test]
+    fn test_volatility_tracks_distance_from_reference() {
+        let mut pair = LbPairState::decode(LB_PAIR).unwrap();
+        pair.last_update_timestamp = 1_000;
+        pair.filter_period = 30;
+        pair.decay_period = 600;
+        pair.reduction_factor = 5_000;
+
+        // Inside the filter period the reference is left alone
+        pair.update_references(1_010);
+        assert_eq!((pair.index_reference, pair.volatility_reference), (-1_900, 15_000));
+
+        // Past it, the reference decays by the reduction factor
+        pair.update_references(1_100);
+        assert_eq!(pair.index_reference, pair.active_id);
+        assert_eq!(pair.volatility_reference, 10_000);
+
+        pair.active_id -= 3;
+        pair.update_volatility_accumulator();
+        assert_eq!(pair.volatility_accumulator, 40_000);
+
+        // Capped at the maximum
+        pair.active_id -= 100;
+        pair.update_volatility_accumulator();
+        assert_eq!(pair.volatility_accumulator, pair.max_volatility_accumulator);
+
+        // And reset once the decay period has passed
+        pair.update_references(10_000);
+        assert_eq!(pair.volatility_reference, 0);
+    }
+
+    #[test]
+    fn test_decode_bin_array_fixture() {
+        let bin_array = BinArrayState::decode(BIN_ARRAY).unwrap();
+
+        assert_eq!(bin_array.index, -28);
+        assert_eq!(bin_array.lower_bin_id(), -1_960);
+        assert_eq!(bin_array.lb_pair, "FVNoAuP2ccK9JspGWArfGC8NXhbP9cbzH256GV7HCE5Y");
+        assert_eq!(bin_array.total_amounts(), (65_000_000_000, 3_750_000_000));
+
+        let (active_id, active) = bin_array.bins_with_ids().find(|(id, _)| *id == -1_897).unwrap();
+        assert_eq!(active_id, -1_897);
+        assert_eq!((active.amount_x, active.amount_y), (5_000_000_000, 750_000_000));
+        assert_eq!(active.price, 2_769_967_950_966_767_054);
+    }
+
+    #[test]
+    fn test_bin_price_and_array_index() {
+        let pair = LbPairState::decode(LB_PAIR).unwrap();
+        assert_eq!(pair.bin_price(0), Some(ONE_X64));
+        // Matches the price stored in the active bin of the fixture
+        let price = pair.bin_price(pair.active_id).unwrap();
+        assert!(price.abs_diff(2_769_967_950_966_767_054) < 1_000, "{}", price);
+        assert!((price as f64 / ONE_X64 as f64 * 1e3 - 150.160_263_5).abs() < 1e-6);
+        assert!(pair.bin_price(-(MAX_EXPONENTIAL as i32)).is_none());
+
+        assert_eq!(bin_array_index(-1_897), -28);
+        assert_eq!(bin_array_index(-1), -1);
+        assert_eq!(bin_array_index(0), 0);
+        assert_eq!(bin_array_index(69), 0);
+        assert_eq!(bin_array_index(70), 1);
+    }
+}
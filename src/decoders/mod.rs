@@ -0,0 +1,101 @@
+//! Decoders for the on-chain pool accounts of the supported AMM programs.
+
+pub mod meteora_dlmm;
+pub mod pump_amm;
+pub mod raydium_clmm;
+pub mod spl_token;
+pub mod whirlpool;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The account is shorter than the layout requires.
+    TooShort { expected: usize, actual: usize },
+    /// The Anchor account discriminator doesn't match the expected type.
+    BadDiscriminator { expected: [u8; 8], actual: [u8; 8] },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::TooShort { expected, actual } => {
+                write!(f, "account data too short: expected {} bytes, got {}", expected, actual)
+            }
+            DecodeError::BadDiscriminator { expected, actual } => {
+                write!(f, "unexpected account discriminator {:?} (expected {:?})", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Little-endian reader over Borsh/`repr(C)` account data.
+///
+/// Callers check the total length up front with [`AccountReader::new`], so
+/// the individual reads can't run past the end.
+pub(crate) struct AccountReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AccountReader<'a> {
+    /// Check `data` is at least `min_len` bytes and starts with `discriminator`.
+    pub(crate) fn new(data: &'a [u8], discriminator: [u8; 8], min_len: usize) -> Result<Self, DecodeError> {
+        let reader = Self::without_discriminator(data, min_len)?;
+        let actual: [u8; 8] = data[..8].try_into().unwrap();
+        if actual != discriminator {
+            return Err(DecodeError::BadDiscriminator { expected: discriminator, actual });
+        }
+        Ok(reader)
+    }
+
+    /// Check `data` is at least `min_len` bytes, for non-Anchor accounts.
+    pub(crate) fn without_discriminator(data: &'a [u8], min_len: usize) -> Result<Self, DecodeError> {
+        if data.len() < min_len {
+            return Err(DecodeError::TooShort { expected: min_len, actual: data.len() });
+        }
+        Ok(Self { data })
+    }
+
+    fn bytes<const N: usize>(&self, offset: usize) -> [u8; N] {
+        self.data[offset..offset + N].try_into().unwrap()
+    }
+
+    pub(crate) fn u8(&self, offset: usize) -> u8 {
+        self.data[offset]
+    }
+
+    pub(crate) fn u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn i32(&self, offset: usize) -> i32 {
+        i32::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn u64(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn i64(&self, offset: usize) -> i64 {
+        i64::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn u128(&self, offset: usize) -> u128 {
+        u128::from_le_bytes(self.bytes(offset))
+    }
+
+    pub(crate) fn i128(&self, offset: usize) -> i128 {
+        i128::from_le_bytes(self.bytes(offset))
+    }
+
+    /// A 32-byte public key, base58 encoded.
+    pub(crate) fn pubkey(&self, offset: usize) -> String {
+        bs58::encode(&self.data[offset..offset + 32]).into_string()
+    }
+}
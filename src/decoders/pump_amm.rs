@@ -0,0 +1,162 @@
+//! Pump.fun AMM (`pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA`) accounts.
+//!
+//! Pools are plain constant-product pools whose reserves live in two SPL
+//! token vaults, so the pool account alone can't be priced; see
+//! [`super::spl_token`] for the vaults.
+
+use super::{AccountReader, DecodeError};
+
+/// Anchor discriminator for the `Pool` account.
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+/// Anchor discriminator for the `GlobalConfig` account.
+pub const GLOBAL_CONFIG_DISCRIMINATOR: [u8; 8] = [149, 8, 156, 202, 160, 252, 176, 217];
+
+/// Size of a `Pool` account including the discriminator. Newer pools carry
+/// extra trailing fields, which we don't read.
+pub const POOL_LEN: usize = 243;
+
+/// Size of a `GlobalConfig` account up to `coin_creator_fee_basis_points`.
+pub const GLOBAL_CONFIG_LEN: usize = 321;
+
+/// `coin_creator` of pools created before creator fees existed.
+const NO_COIN_CREATOR: &str = "11111111111111111111111111111111";
+
+/// The fields of a `Pool` account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpAmmPool {
+    pub index: u16,
+    pub creator: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub lp_mint: String,
+    pub pool_base_token_account: String,
+    pub pool_quote_token_account: String,
+    pub lp_supply: u64,
+    pub coin_creator: String,
+}
+
+impl PumpAmmPool {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, POOL_DISCRIMINATOR, POOL_LEN)?;
+
+        Ok(Self {
+            index: reader.u16(9),
+            creator: reader.pubkey(11),
+            base_mint: reader.pubkey(43),
+            quote_mint: reader.pubkey(75),
+            lp_mint: reader.pubkey(107),
+            pool_base_token_account: reader.pubkey(139),
+            pool_quote_token_account: reader.pubkey(171),
+            lp_supply: reader.u64(203),
+            coin_creator: reader.pubkey(211),
+        })
+    }
+
+    /// Whether swaps on this pool pay a coin creator fee.
+    pub fn has_coin_creator(&self) -> bool {
+        self.coin_creator != NO_COIN_CREATOR
+    }
+}
+
+/// The program-wide fee settings from the `GlobalConfig` account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpGlobalConfig {
+    pub admin: String,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    pub disable_flags: u8,
+    pub coin_creator_fee_basis_points: u64,
+}
+
+impl PumpGlobalConfig {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, GLOBAL_CONFIG_DISCRIMINATOR, GLOBAL_CONFIG_LEN)?;
+
+        Ok(Self {
+            admin: reader.pubkey(8),
+            lp_fee_basis_points: reader.u64(40),
+            protocol_fee_basis_points: reader.u64(48),
+            disable_flags: reader.u8(56),
+            coin_creator_fee_basis_points: reader.u64(313),
+        })
+    }
+
+    /// Fees charged on swaps against `pool`.
+    pub fn fees_for(&self, pool: &PumpAmmPool) -> PumpFees {
+        PumpFees {
+            lp_fee_bps: self.lp_fee_basis_points,
+            protocol_fee_bps: self.protocol_fee_basis_points,
+            creator_fee_bps: if pool.has_coin_creator() { self.coin_creator_fee_basis_points } else { 0 },
+        }
+    }
+}
+
+/// Breakdown of the swap fee on a Pump.fun AMM pool, in basis points.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PumpFees {
+    /// Left in the pool for liquidity providers.
+    pub lp_fee_bps: u64,
+    /// Paid to the protocol fee recipients.
+    pub protocol_fee_bps: u64,
+    /// Paid to the coin creator.
+    pub creator_fee_bps: u64,
+}
+
+impl PumpFees {
+    pub fn total_bps(&self) -> u64 {
+        self.lp_fee_bps + self.protocol_fee_bps + self.creator_fee_bps
+    }
+
+    /// Total swap fee as a fraction (0.003 = 0.3%).
+    pub fn swap_fee(&self) -> f64 {
+        self.total_bps() as f64 / 10_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL: &[u8] = include_bytes!("../../fixtures/pump_amm_pool_sol_usdc.bin");
+    const GLOBAL_CONFIG: &[u8] = include_bytes!("../../fixtures/pump_amm_global_config.bin");
+
+    #[test]
+    fn test_decode_pool_fixture() {
+        let pool = PumpAmmPool::decode(POOL).unwrap();
+
+        assert_eq!(pool.index, 0);
+        assert_eq!(pool.creator, "85efuze4d7tvVLhHb71w67AV7mfBoWvRA1Be7LK585P2");
+        assert_eq!(pool.base_mint, "So11111111111111111111111111111111111111112");
+        assert_eq!(pool.quote_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(pool.lp_mint, "9P8JAr7LMMJBJFmN5v4Q5LtbWPqF5bLggyowV4AzhBNz");
+        assert_eq!(pool.pool_base_token_account, "9onNWGjzCuG3yotS7cNKBx7iwbNh6ghdZGJpTyQQonmo");
+        assert_eq!(pool.pool_quote_token_account, "44kVW25HBj2iBx7gHdu6SfyrA227j1fPBbPBkK13MnSQ");
+        assert_eq!(pool.lp_supply, 12_247_448_713_915);
+        assert_eq!(pool.coin_creator, "HBLZgDQCD2B8HLMpBpT2gUhGUq3jYHp5CJQcuG93Hmcb");
+    }
+
+    #[test]
+    fn test_fee_breakdown() {
+        let mut pool = PumpAmmPool::decode(POOL).unwrap();
+        let config = PumpGlobalConfig::decode(GLOBAL_CONFIG).unwrap();
+        assert_eq!(config.admin, "75V6Ye3KuR8G2jfMcBq7ra3DSesFKSQargzjRpKi5WLY");
+
+        let fees = config.fees_for(&pool);
+        assert_eq!(fees, PumpFees { lp_fee_bps: 20, protocol_fee_bps: 5, creator_fee_bps: 5 });
+        assert!((fees.swap_fee() - 0.003).abs() < 1e-12);
+
+        // Pools without a coin creator don't pay the creator fee
+        pool.coin_creator = NO_COIN_CREATOR.to_string();
+        assert_eq!(config.fees_for(&pool).total_bps(), 25);
+    }
+
+    #[test]
+    fn test_rejects_other_accounts() {
+        assert!(matches!(PumpAmmPool::decode(GLOBAL_CONFIG), Err(DecodeError::BadDiscriminator { .. })));
+        assert_eq!(
+            PumpGlobalConfig::decode(&POOL[..100]),
+            Err(DecodeError::TooShort { expected: GLOBAL_CONFIG_LEN, actual: 100 })
+        );
+    }
+}
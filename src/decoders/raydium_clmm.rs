@@ -0,0 +1,278 @@
+//! Raydium concentrated liquidity (`CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK`) accounts.
+
+use super::{AccountReader, DecodeError};
+
+/// Anchor discriminator for the `PoolState` account.
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// Anchor discriminator for the `AmmConfig` account.
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+
+/// Anchor discriminator for the `TickArrayState` account.
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+
+/// Size of a `PoolState` account including the discriminator.
+pub const POOL_STATE_LEN: usize = 1544;
+
+/// Size of an `AmmConfig` account including the discriminator.
+pub const AMM_CONFIG_LEN: usize = 117;
+
+/// Size of a `TickArrayState` account including the discriminator.
+pub const TICK_ARRAY_LEN: usize = 10240;
+
+/// Ticks per tick array.
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+const TICK_STATE_LEN: usize = 168;
+
+/// Denominator of the fee rates in `AmmConfig`.
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// Bit in `PoolState::status` that disables swaps when set.
+const STATUS_SWAP_DISABLED: u8 = 1 << 4;
+
+/// The fields of a `PoolState` account needed for pricing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumClmmPoolState {
+    pub amm_config: String,
+    pub token_mint_0: String,
+    pub token_mint_1: String,
+    pub token_vault_0: String,
+    pub token_vault_1: String,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    /// sqrt(token 1 / token 0) in raw units, as a Q64.64 fixed-point number.
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub status: u8,
+}
+
+impl RaydiumClmmPoolState {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, POOL_STATE_DISCRIMINATOR, POOL_STATE_LEN)?;
+
+        Ok(Self {
+            amm_config: reader.pubkey(9),
+            token_mint_0: reader.pubkey(73),
+            token_mint_1: reader.pubkey(105),
+            token_vault_0: reader.pubkey(137),
+            token_vault_1: reader.pubkey(169),
+            mint_decimals_0: reader.u8(233),
+            mint_decimals_1: reader.u8(234),
+            tick_spacing: reader.u16(235),
+            liquidity: reader.u128(237),
+            sqrt_price_x64: reader.u128(253),
+            tick_current: reader.i32(269),
+            protocol_fees_token_0: reader.u64(309),
+            protocol_fees_token_1: reader.u64(317),
+            status: reader.u8(389),
+        })
+    }
+
+    pub fn swap_enabled(&self) -> bool {
+        self.status & STATUS_SWAP_DISABLED == 0
+    }
+}
+
+/// Fee tier shared by all pools that reference it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumAmmConfig {
+    pub index: u16,
+    /// Swap fee, out of [`FEE_RATE_DENOMINATOR`].
+    pub trade_fee_rate: u32,
+    /// Share of the trade fee taken by the protocol, out of [`FEE_RATE_DENOMINATOR`].
+    pub protocol_fee_rate: u32,
+    /// Share of the trade fee sent to the fund, out of [`FEE_RATE_DENOMINATOR`].
+    pub fund_fee_rate: u32,
+    pub tick_spacing: u16,
+}
+
+impl RaydiumAmmConfig {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, AMM_CONFIG_DISCRIMINATOR, AMM_CONFIG_LEN)?;
+
+        Ok(Self {
+            index: reader.u16(9),
+            protocol_fee_rate: reader.u32(43),
+            trade_fee_rate: reader.u32(47),
+            tick_spacing: reader.u16(51),
+            fund_fee_rate: reader.u32(53),
+        })
+    }
+
+    /// Swap fee as a fraction (0.0025 = 0.25%).
+    pub fn swap_fee(&self) -> f64 {
+        self.trade_fee_rate as f64 / FEE_RATE_DENOMINATOR as f64
+    }
+}
+
+/// The liquidity fields of one tick. Uninitialized slots are all zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickState {
+    pub tick: i32,
+    /// Liquidity added when the price crosses this tick going up.
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// A `TickArrayState` account: [`TICK_ARRAY_SIZE`] consecutive ticks of one
+/// pool, starting at `start_tick_index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumTickArray {
+    pub pool_id: String,
+    pub start_tick_index: i32,
+    pub ticks: Vec<TickState>,
+    pub initialized_tick_count: u8,
+}
+
+impl RaydiumTickArray {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, TICK_ARRAY_DISCRIMINATOR, TICK_ARRAY_LEN)?;
+
+        let ticks = (0..TICK_ARRAY_SIZE)
+            .map(|i| {
+                let offset = 44 + i * TICK_STATE_LEN;
+                TickState {
+                    tick: reader.i32(offset),
+                    liquidity_net: reader.i128(offset + 4),
+                    liquidity_gross: reader.u128(offset + 20),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            pool_id: reader.pubkey(8),
+            start_tick_index: reader.i32(40),
+            ticks,
+            initialized_tick_count: reader.u8(44 + TICK_ARRAY_SIZE * TICK_STATE_LEN),
+        })
+    }
+
+    /// First tick index past the end of this array.
+    pub fn end_tick_index(&self, tick_spacing: u16) -> i32 {
+        self.start_tick_index + TICK_ARRAY_SIZE as i32 * tick_spacing as i32
+    }
+
+    /// `(tick index, liquidity_net)` of the initialized ticks, in order.
+    pub fn initialized_ticks(&self) -> impl Iterator<Item = (i32, i128)> + '_ {
+        self.ticks
+            .iter()
+            .filter(|tick| tick.liquidity_gross != 0)
+            .map(|tick| (tick.tick, tick.liquidity_net))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    // SOL/USDC pool and its 0.04% fee tier; see fixtures/README.md.
+    const SOL_USDC: &[u8] = include_bytes!("../../fixtures/raydium_clmm_sol_usdc.bin");
+    const AMM_CONFIG: &[u8] = include_bytes!("../../fixtures/raydium_amm_config_4.bin");
+
+    #[test]
+    fn test_decode_pool_state_fixture() {
+        let pool = RaydiumClmmPoolState::decode(SOL_USDC).unwrap();
+
+        assert_eq!(pool.amm_config, "9bJCbHNaWC2xaGAw2kYobvisixnkpyCEMBndHADX9jm5");
+        assert_eq!(pool.token_mint_0, "So11111111111111111111111111111111111111112");
+        assert_eq!(pool.token_mint_1, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(pool.token_vault_0, "C9PjSZJLTuQkimj5uhDUZnCYGmejNJW7afDUyPMSyAtk");
+        assert_eq!(pool.token_vault_1, "DyWSh1DjBS9LjUKacDVo8bPXftvGiL1AaUC94tLcVNg6");
+        assert_eq!((pool.mint_decimals_0, pool.mint_decimals_1), (9, 6));
+        assert_eq!(pool.tick_spacing, 1);
+        assert_eq!(pool.liquidity, 5_250_000_000_000);
+        assert_eq!(pool.sqrt_price_x64, 7_150_344_441_344_836_650);
+        assert_eq!(pool.tick_current, -18_956);
+        assert_eq!((pool.protocol_fees_token_0, pool.protocol_fees_token_1), (11_000, 22_000));
+        assert!(pool.swap_enabled());
+    }
+
+    #[test]
+    fn test_decode_amm_config_fixture() {
+        let config = RaydiumAmmConfig::decode(AMM_CONFIG).unwrap();
+
+        assert_eq!(config.index, 4);
+        assert_eq!(config.trade_fee_rate, 400);
+        assert_eq!(config.protocol_fee_rate, 120_000);
+        assert_eq!(config.fund_fee_rate, 40_000);
+        assert_eq!(config.tick_spacing, 1);
+        assert!((config.swap_fee() - 0.0004).abs() < 1e-12);
+    }
+
+    /// What an explorer showed for a captured pool and its fee tier.
+    #[derive(serde::Deserialize)]
+    struct ExpectedPool {
+        amm_config: String,
+        sqrt_price_x64: u128,
+        tick_current: i32,
+        liquidity: u128,
+        trade_fee_rate: u32,
+    }
+
+    #[test]
+    fn test_decode_live_dumps() {
+        let configs = test_utils::live_fixtures::<serde_json::Value>("raydium_amm_config_");
+        for fixture in test_utils::live_fixtures::<ExpectedPool>("raydium_clmm_pool_") {
+            let pool = RaydiumClmmPoolState::decode(&fixture.data).unwrap();
+            let context = format!("{} ({} at slot {})", fixture.name, fixture.pubkey, fixture.slot);
+            let expected = &fixture.expected;
+            assert_eq!(pool.amm_config, expected.amm_config, "{}", context);
+            assert_eq!(pool.sqrt_price_x64, expected.sqrt_price_x64, "{}", context);
+            assert_eq!(pool.tick_current, expected.tick_current, "{}", context);
+            assert_eq!(pool.liquidity, expected.liquidity, "{}", context);
+
+            // The pool's fee tier must be captured alongside it
+            let config = configs
+                .iter()
+                .find(|config| config.pubkey == pool.amm_config)
+                .unwrap_or_else(|| panic!("{}: no dump of its AmmConfig {}", context, pool.amm_config));
+            let config = RaydiumAmmConfig::decode(&config.data).unwrap();
+            assert_eq!(config.trade_fee_rate, expected.trade_fee_rate, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_decode_tick_array_fixture() {
+        let tick_array =
+            RaydiumTickArray::decode(include_bytes!("../../fixtures/raydium_tick_array_sol_usdc.bin")).unwrap();
+
+        assert_eq!(tick_array.pool_id, "8sLbNZoA1cfnvMJLPfp98ZLAnFSYCFApfJKMbiXNLwxj");
+        assert_eq!(tick_array.start_tick_index, -18_960);
+        assert_eq!(tick_array.end_tick_index(1), -18_900);
+        assert_eq!(tick_array.initialized_tick_count, 4);
+        assert_eq!(
+            tick_array.initialized_ticks().collect::<Vec<_>>(),
+            vec![
+                (-18_960, 250_000_000_000),
+                (-18_958, 5_000_000_000_000),
+                (-18_950, -5_000_000_000_000),
+                (-18_920, -250_000_000_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_swap_disabled_status() {
+        let mut data = SOL_USDC.to_vec();
+        data[389] = STATUS_SWAP_DISABLED;
+        assert!(!RaydiumClmmPoolState::decode(&data).unwrap().swap_enabled());
+    }
+
+    #[test]
+    fn test_rejects_other_accounts() {
+        assert!(matches!(
+            RaydiumClmmPoolState::decode(AMM_CONFIG),
+            Err(DecodeError::TooShort { .. })
+        ));
+        assert!(matches!(
+            RaydiumAmmConfig::decode(SOL_USDC),
+            Err(DecodeError::BadDiscriminator { .. })
+        ));
+    }
+}
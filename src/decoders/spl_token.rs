@@ -0,0 +1,216 @@
+//! SPL Token and Token-2022 accounts: token accounts for pool vault
+//! balances, and mints for token decimals and metadata.
+
+use super::{AccountReader, DecodeError};
+
+pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Size of a token account. Token-2022 accounts with extensions are longer,
+/// but share this prefix.
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Size of a mint. Token-2022 mints with extensions are padded to
+/// [`TOKEN_ACCOUNT_LEN`], followed by an account type byte and the extensions.
+pub const MINT_LEN: usize = 82;
+
+/// Token-2022 account type byte of a mint with extensions.
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+/// Token-2022 extension types decoded into [`MintExtension`].
+const TRANSFER_FEE_CONFIG_EXTENSION: u16 = 1;
+const TOKEN_METADATA_EXTENSION: u16 = 19;
+
+/// The fields of a token account needed to read a vault balance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenAccount {
+    pub mint: String,
+    pub owner: String,
+    /// Balance in raw units of `mint`.
+    pub amount: u64,
+}
+
+impl TokenAccount {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::without_discriminator(data, TOKEN_ACCOUNT_LEN)?;
+
+        Ok(Self {
+            mint: reader.pubkey(0),
+            owner: reader.pubkey(32),
+            amount: reader.u64(64),
+        })
+    }
+}
+
+/// Whether `data` is a mint rather than a token account. Both are owned by
+/// the token programs; Token-2022 tells them apart with the account type
+/// byte after the token account layout.
+pub fn is_mint(data: &[u8]) -> bool {
+    data.len() == MINT_LEN || (data.len() > TOKEN_ACCOUNT_LEN && data[TOKEN_ACCOUNT_LEN] == ACCOUNT_TYPE_MINT)
+}
+
+/// A token mint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mint {
+    pub mint_authority: Option<String>,
+    /// Total supply in raw units.
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<String>,
+    /// Token-2022 extensions, in account order. Empty for SPL Token mints.
+    pub extensions: Vec<MintExtension>,
+}
+
+/// Fee charged on Token-2022 transfers from `epoch` on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    /// Cap on the fee of a single transfer, in raw units.
+    pub maximum_fee: u64,
+    pub basis_points: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MintExtension {
+    /// The fee in effect before `newer.epoch`, and from it on.
+    TransferFeeConfig { older: TransferFee, newer: TransferFee },
+    /// Metadata stored in the mint itself.
+    TokenMetadata { name: String, symbol: String, uri: String },
+    /// Any other extension, by type.
+    Other(u16),
+}
+
+impl Mint {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::without_discriminator(data, MINT_LEN)?;
+        let optional_pubkey = |offset: usize| (reader.u32(offset) == 1).then(|| reader.pubkey(offset + 4));
+
+        let extensions = if data.len() > TOKEN_ACCOUNT_LEN {
+            decode_extensions(&data[TOKEN_ACCOUNT_LEN + 1..])?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            mint_authority: optional_pubkey(0),
+            supply: reader.u64(36),
+            decimals: reader.u8(44),
+            is_initialized: reader.u8(45) != 0,
+            freeze_authority: optional_pubkey(46),
+            extensions,
+        })
+    }
+
+    /// Symbol from the `TokenMetadata` extension, if the mint has one.
+    pub fn metadata_symbol(&self) -> Option<&str> {
+        self.extensions.iter().find_map(|extension| match extension {
+            MintExtension::TokenMetadata { symbol, .. } => Some(symbol.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Token-2022 extensions: a sequence of (u16 type, u16 length, value)
+/// entries. Unlike the fixed layouts, the lengths come from the data, so
+/// every read is checked.
+fn decode_extensions(mut data: &[u8]) -> Result<Vec<MintExtension>, DecodeError> {
+    let mut extensions = Vec::new();
+    // Type 0 is uninitialized space at the end of the account
+    while data.len() >= 4 && data[..2] != [0, 0] {
+        let header = AccountReader::without_discriminator(data, 4)?;
+        let (extension_type, len) = (header.u16(0), header.u16(2) as usize);
+        let value = data
+            .get(4..4 + len)
+            .ok_or(DecodeError::TooShort { expected: 4 + len, actual: data.len() })?;
+
+        extensions.push(match extension_type {
+            TRANSFER_FEE_CONFIG_EXTENSION => {
+                let reader = AccountReader::without_discriminator(value, 108)?;
+                let transfer_fee = |offset: usize| TransferFee {
+                    epoch: reader.u64(offset),
+                    maximum_fee: reader.u64(offset + 8),
+                    basis_points: reader.u16(offset + 16),
+                };
+                MintExtension::TransferFeeConfig { older: transfer_fee(72), newer: transfer_fee(90) }
+            }
+            TOKEN_METADATA_EXTENSION => {
+                // Update authority and mint, then Borsh strings
+                let mut rest = value.get(64..).ok_or(DecodeError::TooShort { expected: 64, actual: value.len() })?;
+                let name = borsh_string(&mut rest)?;
+                let symbol = borsh_string(&mut rest)?;
+                let uri = borsh_string(&mut rest)?;
+                MintExtension::TokenMetadata { name, symbol, uri }
+            }
+            other => MintExtension::Other(other),
+        });
+        data = &data[4 + len..];
+    }
+    Ok(extensions)
+}
+
+/// Read a u32-length-prefixed string off the front of `data`.
+fn borsh_string(data: &mut &[u8]) -> Result<String, DecodeError> {
+    let len = AccountReader::without_discriminator(data, 4)?.u32(0) as usize;
+    let bytes = data
+        .get(4..4 + len)
+        .ok_or(DecodeError::TooShort { expected: 4 + len, actual: data.len() })?;
+    *data = &data[4 + len..];
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_vault_fixture() {
+        let vault = TokenAccount::decode(include_bytes!("../../fixtures/pump_amm_vault_usdc.bin")).unwrap();
+
+        assert_eq!(vault.mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(vault.owner, "Dh6HSzUPeK6Xq1ohqaCjuvTA3sF6YMDqt2bVuJybWrCe");
+        assert_eq!(vault.amount, 150_500_000_000);
+
+        assert_eq!(
+            TokenAccount::decode(&[0; 64]),
+            Err(DecodeError::TooShort { expected: TOKEN_ACCOUNT_LEN, actual: 64 })
+        );
+    }
+
+    #[test]
+    fn test_decode_mint_fixtures() {
+        let usdc = include_bytes!("../../fixtures/spl_mint_usdc.bin");
+        assert!(is_mint(usdc));
+        assert!(!is_mint(include_bytes!("../../fixtures/pump_amm_vault_usdc.bin")));
+        let usdc = Mint::decode(usdc).unwrap();
+        assert_eq!(usdc.mint_authority.as_deref(), Some("BJE5MMbqXjVwjAF7oxwPYXnTXDyspzZyt4vwenNw5ruG"));
+        assert_eq!(usdc.freeze_authority.as_deref(), Some("7dGbd2QZcCKcTndnHcTL8q7SMVXAkp688NTQYwrRCrar"));
+        assert_eq!((usdc.supply, usdc.decimals, usdc.is_initialized), (8_500_000_000_000_000, 6, true));
+        assert!(usdc.extensions.is_empty());
+
+        let data = include_bytes!("../../fixtures/token_2022_mint_ext.bin");
+        assert!(is_mint(data));
+        let mint = Mint::decode(data).unwrap();
+        assert_eq!(mint.freeze_authority, None);
+        assert_eq!(mint.decimals, 6);
+        assert_eq!(
+            mint.extensions,
+            vec![
+                MintExtension::TransferFeeConfig {
+                    older: TransferFee { epoch: 500, maximum_fee: 5_000_000, basis_points: 50 },
+                    newer: TransferFee { epoch: 600, maximum_fee: 5_000_000_000, basis_points: 100 },
+                },
+                MintExtension::Other(18),
+                MintExtension::TokenMetadata {
+                    name: "Example Token".to_string(),
+                    symbol: "EXT".to_string(),
+                    uri: "https://example.com/ext.json".to_string(),
+                },
+            ]
+        );
+        assert_eq!(mint.metadata_symbol(), Some("EXT"));
+
+        // An extension running past the end of the account
+        assert!(matches!(Mint::decode(&data[..data.len() - 10]), Err(DecodeError::TooShort { .. })));
+    }
+}
@@ -0,0 +1,212 @@
+//! Orca Whirlpool (`whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc`) pool accounts.
+
+use super::{AccountReader, DecodeError};
+
+/// Anchor discriminator for the `Whirlpool` account.
+pub const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+
+/// Anchor discriminator for the fixed-size `TickArray` account.
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+
+/// Size of a `Whirlpool` account including the discriminator.
+pub const WHIRLPOOL_LEN: usize = 653;
+
+/// Size of a `TickArray` account including the discriminator.
+pub const TICK_ARRAY_LEN: usize = 9988;
+
+/// Ticks per tick array.
+pub const TICK_ARRAY_SIZE: usize = 88;
+
+const TICK_LEN: usize = 113;
+
+/// Denominator of `fee_rate`, which is expressed in hundredths of a basis point.
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// The fields of a `Whirlpool` account needed for pricing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhirlpoolState {
+    pub whirlpools_config: String,
+    pub tick_spacing: u16,
+    /// Swap fee in hundredths of a basis point (3000 = 0.3%).
+    pub fee_rate: u16,
+    /// Share of the swap fee taken by the protocol, in basis points.
+    pub protocol_fee_rate: u16,
+    pub liquidity: u128,
+    /// sqrt(token B / token A) in raw units, as a Q64.64 fixed-point number.
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: String,
+    pub token_vault_a: String,
+    pub token_mint_b: String,
+    pub token_vault_b: String,
+}
+
+impl WhirlpoolState {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, WHIRLPOOL_DISCRIMINATOR, WHIRLPOOL_LEN)?;
+
+        Ok(Self {
+            whirlpools_config: reader.pubkey(8),
+            tick_spacing: reader.u16(41),
+            fee_rate: reader.u16(45),
+            protocol_fee_rate: reader.u16(47),
+            liquidity: reader.u128(49),
+            sqrt_price: reader.u128(65),
+            tick_current_index: reader.i32(81),
+            protocol_fee_owed_a: reader.u64(85),
+            protocol_fee_owed_b: reader.u64(93),
+            token_mint_a: reader.pubkey(101),
+            token_vault_a: reader.pubkey(133),
+            token_mint_b: reader.pubkey(181),
+            token_vault_b: reader.pubkey(213),
+        })
+    }
+
+    /// Swap fee as a fraction (0.003 = 0.3%).
+    pub fn swap_fee(&self) -> f64 {
+        self.fee_rate as f64 / FEE_RATE_DENOMINATOR as f64
+    }
+}
+
+/// The liquidity fields of one tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub initialized: bool,
+    /// Liquidity added when the price crosses this tick going up.
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// A `TickArray` account: [`TICK_ARRAY_SIZE`] consecutive ticks, `tick_spacing`
+/// apart, starting at `start_tick_index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhirlpoolTickArray {
+    pub start_tick_index: i32,
+    pub ticks: Vec<Tick>,
+    pub whirlpool: String,
+}
+
+impl WhirlpoolTickArray {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let reader = AccountReader::new(data, TICK_ARRAY_DISCRIMINATOR, TICK_ARRAY_LEN)?;
+
+        let ticks = (0..TICK_ARRAY_SIZE)
+            .map(|i| {
+                let offset = 12 + i * TICK_LEN;
+                Tick {
+                    initialized: reader.u8(offset) != 0,
+                    liquidity_net: reader.i128(offset + 1),
+                    liquidity_gross: reader.u128(offset + 17),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            start_tick_index: reader.i32(8),
+            ticks,
+            whirlpool: reader.pubkey(12 + TICK_ARRAY_SIZE * TICK_LEN),
+        })
+    }
+
+    /// First tick index past the end of this array.
+    pub fn end_tick_index(&self, tick_spacing: u16) -> i32 {
+        self.start_tick_index + TICK_ARRAY_SIZE as i32 * tick_spacing as i32
+    }
+
+    /// `(tick index, liquidity_net)` of the initialized ticks, in order.
+    pub fn initialized_ticks(&self, tick_spacing: u16) -> impl Iterator<Item = (i32, i128)> + '_ {
+        self.ticks
+            .iter()
+            .enumerate()
+            .filter(|(_, tick)| tick.initialized)
+            .map(move |(i, tick)| (self.start_tick_index + i as i32 * tick_spacing as i32, tick.liquidity_net))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    // SOL/USDC pool with tick spacing 4 at 150 USDC/SOL; see fixtures/README.md.
+    const SOL_USDC: &[u8] = include_bytes!("../../fixtures/whirlpool_sol_usdc.bin");
+
+    #[test]
+    fn test_decode_sol_usdc_fixture() {
+        let pool = WhirlpoolState::decode(SOL_USDC).unwrap();
+
+        assert_eq!(pool.whirlpools_config, "2LecshUwdy9xi7meFgHtFJQNSKk4KdTrcpvaB56dP2NQ");
+        assert_eq!(pool.tick_spacing, 4);
+        assert_eq!(pool.fee_rate, 400);
+        assert_eq!(pool.protocol_fee_rate, 1300);
+        assert_eq!(pool.liquidity, 3_000_000_000_000);
+        assert_eq!(pool.sqrt_price, 7_144_393_258_922_745_604);
+        assert_eq!(pool.tick_current_index, -18_973);
+        assert_eq!(pool.protocol_fee_owed_a, 123_456);
+        assert_eq!(pool.protocol_fee_owed_b, 654_321);
+        assert_eq!(pool.token_mint_a, "So11111111111111111111111111111111111111112");
+        assert_eq!(pool.token_vault_a, "97qTpJhz4AJaegmFYH2rMfm3S2NEdYNzRFuNq5yYEqhB");
+        assert_eq!(pool.token_mint_b, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(pool.token_vault_b, "HvmUvNAX7f6BAFhTQYyQ3EcZVJYq9YT2hWSWUGAeWhR8");
+        assert!((pool.swap_fee() - 0.0004).abs() < 1e-12);
+    }
+
+    /// What an explorer showed for a captured Whirlpool.
+    #[derive(serde::Deserialize)]
+    struct ExpectedPool {
+        sqrt_price: u128,
+        tick_current_index: i32,
+        liquidity: u128,
+        fee_rate: u16,
+    }
+
+    #[test]
+    fn test_decode_live_dumps() {
+        for fixture in test_utils::live_fixtures::<ExpectedPool>("whirlpool_") {
+            let pool = WhirlpoolState::decode(&fixture.data).unwrap();
+            let context = format!("{} ({} at slot {})", fixture.name, fixture.pubkey, fixture.slot);
+            let expected = &fixture.expected;
+            assert_eq!(pool.sqrt_price, expected.sqrt_price, "{}", context);
+            assert_eq!(pool.tick_current_index, expected.tick_current_index, "{}", context);
+            assert_eq!(pool.liquidity, expected.liquidity, "{}", context);
+            assert_eq!(pool.fee_rate, expected.fee_rate, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_decode_tick_array_fixture() {
+        let tick_array =
+            WhirlpoolTickArray::decode(include_bytes!("../../fixtures/whirlpool_tick_array_sol_usdc.bin")).unwrap();
+
+        assert_eq!(tick_array.start_tick_index, -19_008);
+        assert_eq!(tick_array.end_tick_index(4), -18_656);
+        assert_eq!(tick_array.whirlpool, "Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE");
+        assert_eq!(
+            tick_array.initialized_ticks(4).collect::<Vec<_>>(),
+            vec![
+                (-19_000, 2_000_000_000_000),
+                (-18_980, 1_000_000_000_000),
+                (-18_900, -2_000_000_000_000),
+                (-18_800, -1_000_000_000_000),
+            ]
+        );
+        assert_eq!(tick_array.ticks[2].liquidity_gross, 2_000_000_000_000);
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_foreign_accounts() {
+        assert_eq!(
+            WhirlpoolState::decode(&SOL_USDC[..100]),
+            Err(DecodeError::TooShort { expected: WHIRLPOOL_LEN, actual: 100 })
+        );
+
+        let mut foreign = SOL_USDC.to_vec();
+        foreign[0] ^= 0xff;
+        assert!(matches!(
+            WhirlpoolState::decode(&foreign),
+            Err(DecodeError::BadDiscriminator { .. })
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+//! Fixed-point numbers for rate and liquidity math.
+//!
+//! Amounts stay in raw token units (`u64`, or `u128` for sums and values)
+//! and prices are Q64.64 numbers of raw quote units per raw base unit, the
+//! same representation the CLMM and DLMM programs use. Intermediates that
+//! can exceed 128 bits go through [`U256`]. Conversion to `f64` whole-token
+//! values happens only for display and at the proto boundary.
+
+use serde::{Deserialize, Serialize};
+
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+pub use u256::U256;
+
+/// Number of fractional bits in a Q64.64 number.
+pub const Q64_SHIFT: u32 = 64;
+
+/// 1.0 in Q64.64.
+pub const ONE_X64: u128 = 1 << Q64_SHIFT;
+
+/// `value` as a `u128`, saturating at `u128::MAX`.
+pub fn saturating_u128(value: U256) -> u128 {
+    if value.bits() > 128 {
+        u128::MAX
+    } else {
+        value.as_u128()
+    }
+}
+
+/// Raw token amount in whole tokens.
+pub fn to_ui_amount(raw: u128, decimals: u8) -> f64 {
+    raw as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Whole-token amount in raw units, rounded to the nearest unit. Negative
+/// and non-finite amounts become zero.
+pub fn from_ui_amount(amount: f64, decimals: u8) -> u128 {
+    if !amount.is_finite() || amount <= 0.0 {
+        return 0;
+    }
+    // `as` saturates at u128::MAX
+    (amount * 10f64.powi(decimals as i32)).round() as u128
+}
+
+/// Price of one raw base unit in raw quote units, as a Q64.64 fixed-point
+/// number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PriceX64(u128);
+
+impl PriceX64 {
+    pub const ZERO: Self = Self(0);
+
+    pub fn from_x64(price_x64: u128) -> Self {
+        Self(price_x64)
+    }
+
+    pub fn x64(self) -> u128 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// `quote / base` for raw reserves, rounded down. Zero when `base` is
+    /// zero, saturating when the ratio doesn't fit.
+    pub fn from_amounts(base: u128, quote: u128) -> Self {
+        if base == 0 {
+            return Self::ZERO;
+        }
+        Self(saturating_u128((U256::from(quote) << Q64_SHIFT) / U256::from(base)))
+    }
+
+    /// Square of a Q64.64 `sqrt(quote / base)`, as stored by CLMM pools.
+    pub fn from_sqrt_price_x64(sqrt_price_x64: u128) -> Self {
+        let sqrt_price = U256::from(sqrt_price_x64);
+        Self(saturating_u128((sqrt_price * sqrt_price) >> Q64_SHIFT))
+    }
+
+    /// Price of one raw quote unit in raw base units. Zero stays zero.
+    pub fn inverse(self) -> Self {
+        if self.0 == 0 {
+            return Self::ZERO;
+        }
+        Self(saturating_u128((U256::one() << (2 * Q64_SHIFT)) / U256::from(self.0)))
+    }
+
+    /// Raw quote value of `base_amount` raw base units, rounded down.
+    pub fn quote_value(self, base_amount: u128) -> u128 {
+        saturating_u128((U256::from(base_amount) * U256::from(self.0)) >> Q64_SHIFT)
+    }
+
+    /// Quote per base in whole tokens.
+    pub fn to_f64(self, base_decimals: u8, quote_decimals: u8) -> f64 {
+        self.0 as f64 / ONE_X64 as f64 * 10f64.powi(base_decimals as i32 - quote_decimals as i32)
+    }
+
+    /// Inverse of [`PriceX64::to_f64`], for rates that arrive as floats.
+    /// Negative and non-finite rates become zero.
+    pub fn from_f64(rate: f64, base_decimals: u8, quote_decimals: u8) -> Self {
+        if !rate.is_finite() || rate <= 0.0 {
+            return Self::ZERO;
+        }
+        let raw = rate * 10f64.powi(quote_decimals as i32 - base_decimals as i32);
+        Self((raw * ONE_X64 as f64) as u128)
+    }
+}
+
+/// Chains two prices: base/mid times mid/quote is base/quote.
+impl std::ops::Mul for PriceX64 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(saturating_u128((U256::from(self.0) * U256::from(rhs.0)) >> Q64_SHIFT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_from_amounts_is_exact() {
+        assert_eq!(PriceX64::from_amounts(100, 200), PriceX64::from_x64(2 * ONE_X64));
+        assert_eq!(PriceX64::from_amounts(0, 200), PriceX64::ZERO);
+
+        // 1,000 SOL against 150,500 USDC: 0.1505 raw USDC per lamport
+        let price = PriceX64::from_amounts(1_000_000_000_000, 150_500_000_000);
+        assert_eq!(price.to_f64(9, 6), 150.5);
+        assert_eq!(price.quote_value(1_000_000_000_000), 150_499_999_999);
+
+        // Reserves near u64::MAX don't lose the low digits the way f64 does
+        let base = u64::MAX as u128 - 1;
+        let price = PriceX64::from_amounts(base, base + 1);
+        assert!(price > PriceX64::from_x64(ONE_X64));
+        assert_eq!(price.quote_value(base), base);
+    }
+
+    #[test]
+    fn test_sqrt_price_and_inverse() {
+        assert_eq!(PriceX64::from_sqrt_price_x64(ONE_X64).x64(), ONE_X64);
+        assert_eq!(PriceX64::from_sqrt_price_x64(2 * ONE_X64).x64(), 4 * ONE_X64);
+        assert_eq!(PriceX64::from_sqrt_price_x64(2 * ONE_X64).inverse().x64(), ONE_X64 / 4);
+        assert_eq!(PriceX64::from_sqrt_price_x64(ONE_X64).to_f64(9, 6), 1000.0);
+        assert_eq!(PriceX64::from_sqrt_price_x64(u128::MAX).x64(), u128::MAX);
+
+        let price = PriceX64::from_amounts(3, 4);
+        assert_eq!((price * price.inverse()).x64().abs_diff(ONE_X64), 1);
+    }
+
+    #[test]
+    fn test_float_edge_conversions() {
+        assert_eq!(to_ui_amount(150_500_000_000, 6), 150_500.0);
+        assert_eq!(from_ui_amount(150_500.0, 6), 150_500_000_000);
+        assert_eq!(from_ui_amount(f64::NAN, 6), 0);
+        assert_eq!(from_ui_amount(-1.0, 6), 0);
+
+        let price = PriceX64::from_f64(150.25, 9, 6);
+        assert!((price.to_f64(9, 6) - 150.25).abs() < 1e-12);
+        assert_eq!(PriceX64::from_f64(f64::INFINITY, 9, 6), PriceX64::ZERO);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Channel, Request};
+use crate::amm_types::AmmProgram;
+use crate::client::{BackoffConfig, ClientError, StreamEnd, SubscriptionEvent, SubscriptionState};
+use crate::proto::geyser::geyser_client::GeyserClient;
+use crate::proto::geyser::subscribe_update::UpdateOneof;
+use crate::proto::geyser::{
+    CommitmentLevel, PingRequest, SubscribeRequest, SubscribeRequestFilterAccounts,
+    SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+    SubscribeUpdateAccount, SubscribeUpdateTransaction,
+};
+
+/// Filter names used in our `SubscribeRequest`; Geyser echoes them back in
+/// `SubscribeUpdate::filters`.
+pub const ACCOUNTS_FILTER: &str = "amm_pools";
+pub const TRANSACTIONS_FILTER: &str = "amm_transactions";
+pub const SLOTS_FILTER: &str = "slots";
+pub const VAULTS_FILTER: &str = "pool_vaults";
+
+#[derive(Debug, Clone)]
+pub struct GeyserConfig {
+    pub endpoint: String,
+    /// Access token sent as the `x-token` header, required by most providers.
+    pub x_token: Option<String>,
+    pub commitment: CommitmentLevel,
+    /// How often to ping the node to keep the stream alive through proxies.
+    pub ping_interval: Duration,
+}
+
+impl Default for GeyserConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://127.0.0.1:10000".to_string(),
+            x_token: None,
+            commitment: CommitmentLevel::Confirmed,
+            ping_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// An account owned by one of the watched programs changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountUpdate {
+    pub pubkey: String,
+    pub owner: String,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub slot: u64,
+    pub write_version: u64,
+    pub txn_signature: Option<String>,
+}
+
+/// A transaction touching one of the watched programs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionUpdate {
+    pub signature: String,
+    pub slot: u64,
+    pub account_keys: Vec<String>,
+    pub failed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeyserEvent {
+    Account(AccountUpdate),
+    Transaction(TransactionUpdate),
+    Slot { slot: u64, status: CommitmentLevel },
+    Pong { id: i32 },
+}
+
+impl From<SubscribeUpdateAccount> for GeyserEvent {
+    fn from(update: SubscribeUpdateAccount) -> Self {
+        let info = update.account.unwrap_or_default();
+        GeyserEvent::Account(AccountUpdate {
+            pubkey: bs58::encode(&info.pubkey).into_string(),
+            owner: bs58::encode(&info.owner).into_string(),
+            lamports: info.lamports,
+            data: info.data,
+            slot: update.slot,
+            write_version: info.write_version,
+            txn_signature: info.txn_signature.map(|sig| bs58::encode(sig).into_string()),
+        })
+    }
+}
+
+impl From<SubscribeUpdateTransaction> for GeyserEvent {
+    fn from(update: SubscribeUpdateTransaction) -> Self {
+        let info = update.transaction.unwrap_or_default();
+        let mut account_keys: Vec<String> = info
+            .transaction
+            .and_then(|transaction| transaction.message)
+            .map(|message| message.account_keys)
+            .unwrap_or_default()
+            .iter()
+            .map(|key| bs58::encode(key).into_string())
+            .collect();
+        let failed = info.meta.as_ref().is_some_and(|meta| meta.err.is_some());
+        // Keys loaded from address lookup tables follow the static keys.
+        if let Some(meta) = &info.meta {
+            for key in meta.loaded_writable_addresses.iter().chain(&meta.loaded_readonly_addresses) {
+                account_keys.push(bs58::encode(key).into_string());
+            }
+        }
+        GeyserEvent::Transaction(TransactionUpdate {
+            signature: bs58::encode(&info.signature).into_string(),
+            slot: update.slot,
+            account_keys,
+            failed,
+        })
+    }
+}
+
+/// Subscribe to pool accounts owned by, and transactions touching, the given
+/// programs, plus slot notifications. `vault_accounts` are token program
+/// accounts the pools depend on (reserve vaults, mints), watched by address.
+pub fn build_subscribe_request(
+    program_ids: &[String],
+    vault_accounts: &[String],
+    commitment: CommitmentLevel,
+) -> SubscribeRequest {
+    let mut accounts = HashMap::new();
+    accounts.insert(
+        ACCOUNTS_FILTER.to_string(),
+        SubscribeRequestFilterAccounts {
+            account: Vec::new(),
+            owner: program_ids.to_vec(),
+        },
+    );
+    if !vault_accounts.is_empty() {
+        accounts.insert(
+            VAULTS_FILTER.to_string(),
+            SubscribeRequestFilterAccounts {
+                account: vault_accounts.to_vec(),
+                owner: Vec::new(),
+            },
+        );
+    }
+
+    let mut transactions = HashMap::new();
+    transactions.insert(
+        TRANSACTIONS_FILTER.to_string(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: program_ids.to_vec(),
+            ..Default::default()
+        },
+    );
+
+    let mut slots = HashMap::new();
+    slots.insert(
+        SLOTS_FILTER.to_string(),
+        SubscribeRequestFilterSlots {
+            filter_by_commitment: Some(true),
+        },
+    );
+
+    SubscribeRequest {
+        accounts,
+        slots,
+        transactions,
+        commitment: Some(commitment as i32),
+        ping: None,
+    }
+}
+
+/// Watches the AMM programs through a Yellowstone Geyser `Subscribe` stream.
+pub struct GeyserSubscriber {
+    config: GeyserConfig,
+    program_ids: Vec<String>,
+    vault_accounts: Option<watch::Receiver<Vec<String>>>,
+}
+
+impl GeyserSubscriber {
+    pub fn new(config: GeyserConfig, programs: &[AmmProgram]) -> Self {
+        Self {
+            config,
+            program_ids: programs.iter().map(|program| program.id.clone()).collect(),
+            vault_accounts: None,
+        }
+    }
+
+    /// Also watch the token accounts published on `vault_accounts`. The
+    /// subscription's filters are updated in place whenever the list changes.
+    pub fn with_vault_accounts(mut self, vault_accounts: watch::Receiver<Vec<String>>) -> Self {
+        self.vault_accounts = Some(vault_accounts);
+        self
+    }
+
+    pub fn subscribe_request(&self) -> SubscribeRequest {
+        let vault_accounts = self
+            .vault_accounts
+            .as_ref()
+            .map(|vault_accounts| vault_accounts.borrow().clone())
+            .unwrap_or_default();
+        build_subscribe_request(&self.program_ids, &vault_accounts, self.config.commitment)
+    }
+
+    async fn connect(&self) -> Result<GeyserClient<Channel>, ClientError> {
+        let channel = Channel::from_shared(self.config.endpoint.clone())?
+            .connect()
+            .await?;
+        Ok(GeyserClient::new(channel))
+    }
+
+    fn authorize<T>(&self, request: &mut Request<T>) -> Result<(), ClientError> {
+        if let Some(token) = &self.config.x_token {
+            request.metadata_mut().insert("x-token", token.parse()?);
+        }
+        Ok(())
+    }
+
+    pub async fn ping(&self) -> Result<i32, ClientError> {
+        let mut client = self.connect().await?;
+        let mut request = Request::new(PingRequest { count: 1 });
+        self.authorize(&mut request)?;
+        Ok(client.ping(request).await?.into_inner().count)
+    }
+
+    /// Keep the Geyser stream alive, reconnecting with backoff until the retry
+    /// budget is exhausted or `tx` is closed.
+    pub async fn run(
+        &self,
+        tx: mpsc::Sender<GeyserEvent>,
+        backoff: BackoffConfig,
+        state_tx: Option<mpsc::UnboundedSender<SubscriptionEvent>>,
+    ) -> Result<(), String> {
+        let report = |state: SubscriptionState| {
+            if let Some(state_tx) = &state_tx {
+                let _ = state_tx.send(SubscriptionEvent {
+                    program_name: "Geyser".to_string(),
+                    endpoint: self.config.endpoint.clone(),
+                    state,
+                });
+            }
+        };
+
+        let mut attempt = 0;
+        loop {
+            report(SubscriptionState::Connecting);
+
+            let mut received = 0usize;
+            match self.subscribe_once(&tx, &mut received, || report(SubscriptionState::Streaming)).await {
+                Ok(StreamEnd::ReceiverClosed) => return Ok(()),
+                Ok(StreamEnd::ServerClosed) => {
+                    eprintln!("Geyser stream closed by {}", self.config.endpoint);
+                }
+                Err(e) => {
+                    eprintln!("Geyser subscription error: {}", e);
+                }
+            }
+
+            if received > 0 {
+                attempt = 0;
+            }
+            attempt += 1;
+
+            if attempt > backoff.max_retries {
+                report(SubscriptionState::Failed);
+                return Err(format!("giving up after {} attempts", backoff.max_retries));
+            }
+
+            let delay = backoff.delay_for_attempt(attempt);
+            eprintln!("Reconnecting to Geyser in {}ms (attempt {})", delay.as_millis(), attempt);
+            report(SubscriptionState::BackingOff { attempt, delay });
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn subscribe_once(
+        &self,
+        tx: &mpsc::Sender<GeyserEvent>,
+        received: &mut usize,
+        on_streaming: impl FnOnce(),
+    ) -> Result<StreamEnd, ClientError> {
+        let mut client = self.connect().await?;
+
+        let mut vault_accounts = self.vault_accounts.clone();
+        if let Some(vault_accounts) = &mut vault_accounts {
+            vault_accounts.mark_unchanged();
+        }
+
+        let (request_tx, request_rx) = mpsc::channel(16);
+        request_tx.send(self.subscribe_request()).await?;
+        let mut request = Request::new(ReceiverStream::new(request_rx));
+        self.authorize(&mut request)?;
+
+        let mut stream = client.subscribe(request).await?.into_inner();
+        eprintln!("Started Geyser subscription for {} program(s)", self.program_ids.len());
+        on_streaming();
+
+        let mut keepalive = tokio::time::interval(self.config.ping_interval);
+        keepalive.tick().await;
+        let mut ping_id = 0;
+
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    ping_id += 1;
+                    if request_tx.send(self.ping_request(ping_id)).await.is_err() {
+                        return Ok(StreamEnd::ServerClosed);
+                    }
+                }
+                changed = vault_accounts_changed(&mut vault_accounts), if vault_accounts.is_some() => {
+                    if !changed {
+                        // The tracker went away; keep the filters we have
+                        vault_accounts = None;
+                    } else if request_tx.send(self.subscribe_request()).await.is_err() {
+                        return Ok(StreamEnd::ServerClosed);
+                    }
+                }
+                message = stream.message() => {
+                    let Some(update) = message? else {
+                        return Ok(StreamEnd::ServerClosed);
+                    };
+                    *received += 1;
+
+                    let event = match update.update_oneof {
+                        Some(UpdateOneof::Account(account)) => account.into(),
+                        Some(UpdateOneof::Transaction(transaction)) => transaction.into(),
+                        Some(UpdateOneof::Slot(slot)) => GeyserEvent::Slot {
+                            slot: slot.slot,
+                            status: CommitmentLevel::try_from(slot.status).unwrap_or(CommitmentLevel::Processed),
+                        },
+                        Some(UpdateOneof::Pong(pong)) => GeyserEvent::Pong { id: pong.id },
+                        Some(UpdateOneof::Ping(_)) => {
+                            // The node pings idle streams; answering keeps it open.
+                            ping_id += 1;
+                            if request_tx.send(self.ping_request(ping_id)).await.is_err() {
+                                return Ok(StreamEnd::ServerClosed);
+                            }
+                            continue;
+                        }
+                        None => continue,
+                    };
+
+                    if tx.send(event).await.is_err() {
+                        return Ok(StreamEnd::ReceiverClosed);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A ping that also repeats our filters, so it is safe on servers that
+    /// treat every request as a full filter replacement.
+    fn ping_request(&self, id: i32) -> SubscribeRequest {
+        SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id }),
+            ..self.subscribe_request()
+        }
+    }
+}
+
+/// Wait for the vault list to change; false once its sender is dropped.
+async fn vault_accounts_changed(vault_accounts: &mut Option<watch::Receiver<Vec<String>>>) -> bool {
+    match vault_accounts {
+        Some(vault_accounts) => vault_accounts.changed().await.is_ok(),
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::{get_amm_programs, PUMP_FUN_AMM, WHIRLPOOLS};
+    use crate::geyser_mock::MockGeyser;
+
+    async fn recv(rx: &mut mpsc::Receiver<GeyserEvent>) -> GeyserEvent {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("timed out waiting for Geyser event")
+            .expect("subscriber stopped")
+    }
+
+    #[test]
+    fn test_subscribe_request_watches_all_programs() {
+        let program_ids: Vec<String> = get_amm_programs().into_iter().map(|program| program.id).collect();
+        let request = build_subscribe_request(&program_ids, &[], CommitmentLevel::Processed);
+
+        assert_eq!(request.accounts[ACCOUNTS_FILTER].owner, program_ids);
+        assert_eq!(request.transactions[TRANSACTIONS_FILTER].account_include, program_ids);
+        assert_eq!(request.transactions[TRANSACTIONS_FILTER].vote, Some(false));
+        assert!(request.slots.contains_key(SLOTS_FILTER));
+        assert_eq!(request.commitment, Some(CommitmentLevel::Processed as i32));
+        assert!(!request.accounts.contains_key(VAULTS_FILTER));
+
+        let vaults = vec!["vault".to_string()];
+        let request = build_subscribe_request(&program_ids, &vaults, CommitmentLevel::Processed);
+        assert_eq!(request.accounts[VAULTS_FILTER].account, vaults);
+        assert!(request.accounts[VAULTS_FILTER].owner.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streams_matching_updates_from_mock() {
+        let mock = MockGeyser::new()
+            .with_x_token("secret")
+            .with_ping_interval(Duration::from_millis(20));
+        let endpoint = mock.spawn_local().await;
+        let config = GeyserConfig {
+            endpoint,
+            x_token: Some("secret".to_string()),
+            ping_interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let subscriber = GeyserSubscriber::new(config, &get_amm_programs());
+        assert_eq!(subscriber.ping().await.unwrap(), 1);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::spawn(async move { subscriber.run(tx, BackoffConfig::default(), None).await });
+        while mock.subscriber_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let pool = bs58::encode([7u8; 32]).into_string();
+        let unrelated_owner = bs58::encode([9u8; 32]).into_string();
+        mock.push_account(&pool, &unrelated_owner, vec![0; 8], 1);
+        mock.push_account(&pool, WHIRLPOOLS, vec![1, 2, 3], 2);
+        mock.push_transaction(&[5u8; 64], &[&pool, PUMP_FUN_AMM], 3);
+        mock.push_slot(4);
+
+        let mut events = Vec::new();
+        while events.len() < 3 {
+            match recv(&mut rx).await {
+                GeyserEvent::Pong { .. } => {}
+                event => events.push(event),
+            }
+        }
+
+        assert_eq!(
+            events[0],
+            GeyserEvent::Account(AccountUpdate {
+                pubkey: pool.clone(),
+                owner: WHIRLPOOLS.to_string(),
+                lamports: 0,
+                data: vec![1, 2, 3],
+                slot: 2,
+                write_version: 0,
+                txn_signature: None,
+            })
+        );
+        match &events[1] {
+            GeyserEvent::Transaction(transaction) => {
+                assert_eq!(transaction.signature, bs58::encode([5u8; 64]).into_string());
+                assert_eq!(transaction.account_keys, vec![pool.clone(), PUMP_FUN_AMM.to_string()]);
+                assert_eq!(transaction.slot, 3);
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+        assert_eq!(events[2], GeyserEvent::Slot { slot: 4, status: CommitmentLevel::Confirmed });
+
+        // Keepalive pings are answered with pongs
+        loop {
+            if let GeyserEvent::Pong { id } = recv(&mut rx).await {
+                assert!(id >= 1);
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vault_accounts_are_added_to_live_subscription() {
+        let mock = MockGeyser::new();
+        let endpoint = mock.spawn_local().await;
+        let config = GeyserConfig { endpoint, ..Default::default() };
+        let (vaults_tx, vaults_rx) = watch::channel(Vec::new());
+        let subscriber = GeyserSubscriber::new(config, &get_amm_programs()).with_vault_accounts(vaults_rx);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::spawn(async move { subscriber.run(tx, BackoffConfig::default(), None).await });
+        while mock.subscriber_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let vault = bs58::encode([4u8; 32]).into_string();
+        let token_program = bs58::encode([6u8; 32]).into_string();
+        mock.push_account(&vault, &token_program, vec![1], 1);
+
+        vaults_tx.send(vec![vault.clone()]).unwrap();
+        // Wait for the updated filters to reach the mock
+        loop {
+            mock.push_account(&vault, &token_program, vec![2], 2);
+            match tokio::time::timeout(Duration::from_millis(50), rx.recv()).await {
+                Ok(Some(GeyserEvent::Account(account))) => {
+                    assert_eq!(account.pubkey, vault);
+                    assert_eq!(account.data, vec![2]);
+                    break;
+                }
+                Ok(Some(_)) | Err(_) => {}
+                Ok(None) => panic!("subscriber stopped"),
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status, Streaming};
+use crate::proto::geyser::geyser_server::{Geyser, GeyserServer};
+use crate::proto::geyser::subscribe_update::UpdateOneof;
+use crate::proto::geyser::{
+    CommitmentLevel, PingRequest, PongResponse, SubscribeRequest, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateAccountInfo, SubscribeUpdatePing, SubscribeUpdatePong, SubscribeUpdateSlot,
+    SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use crate::proto::solana::storage::confirmed_block::{Message, Transaction};
+
+type ResponseStream = Pin<Box<dyn tokio_stream::Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+/// In-process stand-in for a Yellowstone Geyser node.
+///
+/// Updates pushed through `push_*` are delivered to every subscriber whose
+/// filters match them, tagged with the names of the matching filters, the
+/// same way a real node does. Pings are answered with pongs.
+#[derive(Clone)]
+pub struct MockGeyser {
+    updates: broadcast::Sender<SubscribeUpdate>,
+    subscribers: Arc<AtomicUsize>,
+    x_token: Option<String>,
+    ping_interval: Option<Duration>,
+}
+
+impl Default for MockGeyser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockGeyser {
+    pub fn new() -> Self {
+        let (updates, _) = broadcast::channel(1024);
+        Self {
+            updates,
+            subscribers: Arc::new(AtomicUsize::new(0)),
+            x_token: None,
+            ping_interval: None,
+        }
+    }
+
+    /// Reject clients that don't send this `x-token`.
+    pub fn with_x_token(mut self, token: &str) -> Self {
+        self.x_token = Some(token.to_string());
+        self
+    }
+
+    /// Ping every subscriber periodically, like real nodes do.
+    pub fn with_ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = Some(interval);
+        self
+    }
+
+    /// Number of subscribers that have sent their filters.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.load(Ordering::SeqCst)
+    }
+
+    pub fn push_account(&self, pubkey: &str, owner: &str, data: Vec<u8>, slot: u64) {
+        self.push(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: decode_pubkey(pubkey),
+                owner: decode_pubkey(owner),
+                data,
+                ..Default::default()
+            }),
+            slot,
+            is_startup: false,
+        }));
+    }
+
+    pub fn push_transaction(&self, signature: &[u8], account_keys: &[&str], slot: u64) {
+        self.push(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: signature.to_vec(),
+                transaction: Some(Transaction {
+                    signatures: vec![signature.to_vec()],
+                    message: Some(Message {
+                        account_keys: account_keys.iter().map(|key| decode_pubkey(key)).collect(),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }),
+            slot,
+        }));
+    }
+
+    pub fn push_slot(&self, slot: u64) {
+        self.push(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot,
+            parent: slot.checked_sub(1),
+            status: CommitmentLevel::Confirmed as i32,
+        }));
+    }
+
+    fn push(&self, update: UpdateOneof) {
+        let _ = self.updates.send(SubscribeUpdate {
+            filters: Vec::new(),
+            update_oneof: Some(update),
+        });
+    }
+
+    pub fn into_service(self) -> GeyserServer<Self> {
+        GeyserServer::new(self)
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        eprintln!("Mock Geyser listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(self.into_service())
+            .serve(addr)
+            .await
+    }
+
+    /// Serve on an ephemeral local port and return its endpoint URL.
+    pub async fn spawn_local(&self) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let service = self.clone().into_service();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+        });
+        format!("http://{}", addr)
+    }
+
+    fn is_authorized<T>(&self, request: &Request<T>) -> bool {
+        match &self.x_token {
+            Some(expected) => {
+                let token = request.metadata().get("x-token").and_then(|value| value.to_str().ok());
+                token == Some(expected.as_str())
+            }
+            None => true,
+        }
+    }
+}
+
+fn decode_pubkey(address: &str) -> Vec<u8> {
+    bs58::decode(address).into_vec().unwrap_or_default()
+}
+
+/// Names of the filters in `request` that select `update`.
+fn matching_filters(request: &SubscribeRequest, update: &UpdateOneof) -> Vec<String> {
+    match update {
+        UpdateOneof::Account(account) => {
+            let info = account.account.clone().unwrap_or_default();
+            let pubkey = bs58::encode(&info.pubkey).into_string();
+            let owner = bs58::encode(&info.owner).into_string();
+            request
+                .accounts
+                .iter()
+                .filter(|(_, filter)| {
+                    (filter.account.is_empty() || filter.account.contains(&pubkey))
+                        && (filter.owner.is_empty() || filter.owner.contains(&owner))
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+        UpdateOneof::Transaction(transaction) => {
+            let keys: Vec<String> = transaction
+                .transaction
+                .as_ref()
+                .and_then(|info| info.transaction.as_ref())
+                .and_then(|transaction| transaction.message.as_ref())
+                .map(|message| message.account_keys.iter().map(|key| bs58::encode(key).into_string()).collect())
+                .unwrap_or_default();
+            request
+                .transactions
+                .iter()
+                .filter(|(_, filter)| {
+                    (filter.account_include.is_empty() || filter.account_include.iter().any(|key| keys.contains(key)))
+                        && !filter.account_exclude.iter().any(|key| keys.contains(key))
+                        && filter.account_required.iter().all(|key| keys.contains(key))
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+        UpdateOneof::Slot(_) => request.slots.keys().cloned().collect(),
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => Vec::new(),
+    }
+}
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = ResponseStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        if !self.is_authorized(&request) {
+            return Err(Status::unauthenticated("missing or invalid x-token"));
+        }
+        let mut requests = request.into_inner();
+        let mut updates = self.updates.subscribe();
+        let subscribers = self.subscribers.clone();
+        let ping_interval = self.ping_interval;
+        let (tx, rx) = mpsc::channel(256);
+
+        tokio::spawn(async move {
+            let mut filters: Option<SubscribeRequest> = None;
+            let mut server_ping = tokio::time::interval(ping_interval.unwrap_or(Duration::from_secs(3600)));
+            server_ping.tick().await;
+
+            loop {
+                let update = tokio::select! {
+                    request = requests.message() => {
+                        let Ok(Some(request)) = request else { break };
+                        if let Some(ping) = &request.ping {
+                            let pong = UpdateOneof::Pong(SubscribeUpdatePong { id: ping.id });
+                            if tx.send(Ok(SubscribeUpdate { filters: Vec::new(), update_oneof: Some(pong) })).await.is_err() {
+                                break;
+                            }
+                        }
+                        let has_filters = !(request.accounts.is_empty()
+                            && request.transactions.is_empty()
+                            && request.slots.is_empty());
+                        if has_filters {
+                            if filters.is_none() {
+                                subscribers.fetch_add(1, Ordering::SeqCst);
+                            }
+                            filters = Some(request);
+                        }
+                        continue;
+                    }
+                    update = updates.recv() => match update {
+                        Ok(update) => update,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = server_ping.tick(), if ping_interval.is_some() => SubscribeUpdate {
+                        filters: Vec::new(),
+                        update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+                    },
+                };
+
+                let update = match (&filters, &update.update_oneof) {
+                    (_, Some(UpdateOneof::Ping(_))) => update,
+                    (Some(request), Some(inner)) => {
+                        let matched = matching_filters(request, inner);
+                        if matched.is_empty() {
+                            continue;
+                        }
+                        SubscribeUpdate { filters: matched, ..update }
+                    }
+                    _ => continue,
+                };
+                if tx.send(Ok(update)).await.is_err() {
+                    break;
+                }
+            }
+
+            if filters.is_some() {
+                subscribers.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        if !self.is_authorized(&request) {
+            return Err(Status::unauthenticated("missing or invalid x-token"));
+        }
+        Ok(Response::new(PongResponse {
+            count: request.into_inner().count,
+        }))
+    }
+}
@@ -0,0 +1,41 @@
+pub mod amm_types;
+pub mod arbitrage;
+pub mod candles;
+pub mod fixed;
+pub mod output;
+pub mod client;
+pub mod price_history;
+pub mod rate_calculator;
+pub mod reference_prices;
+pub mod router;
+pub mod rpc;
+pub mod table_ui;
+pub mod token_graph;
+pub mod token_registry;
+pub mod server;
+pub mod volume;
+pub mod geyser;
+pub mod geyser_mock;
+pub mod decoders;
+pub mod pool_tracker;
+pub mod quote;
+#[cfg(test)]
+mod test_utils;
+
+/// Code generated from `proto/` by `build.rs`.
+pub mod proto {
+    #[allow(clippy::large_enum_variant)]
+    pub mod geyser {
+        tonic::include_proto!("geyser");
+    }
+    pub mod solana {
+        pub mod amm {
+            tonic::include_proto!("solana.amm");
+        }
+        pub mod storage {
+            pub mod confirmed_block {
+                tonic::include_proto!("solana.storage.confirmed_block");
+            }
+        }
+    }
+}
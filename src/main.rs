@@ -1,82 +1,527 @@
-mod amm_types;
-mod rate_calculator;
-mod table_ui;
-mod client;
+use solana_amm_rate_calculator::amm_types::{
+    get_amm_programs, FilterConfig, MarketRate, QuotePreference, SOL_MINT, USDC_MINT, USDT_MINT,
+};
+use solana_amm_rate_calculator::rate_calculator::RateCalculator;
+use solana_amm_rate_calculator::table_ui::{
+    run_display, spawn_key_reader, Column, Display, SharedTableUI, TableUI, Tui, DEFAULT_FRAME_RATE, DETAIL_CANDLES,
+};
+use solana_amm_rate_calculator::client::{AmmClientManager, BackoffConfig, SubscriptionEvent};
+use solana_amm_rate_calculator::geyser::{GeyserConfig, GeyserEvent, GeyserSubscriber};
+use solana_amm_rate_calculator::pool_tracker::{PoolTracker, CONFIG_ACCOUNTS};
+use solana_amm_rate_calculator::rpc::RpcClient;
+use solana_amm_rate_calculator::server::AmmServer;
+use solana_amm_rate_calculator::volume::{parse_timeframe, ONE_HOUR_MS, ONE_MINUTE_MS, ONE_SECOND_MS};
+use solana_amm_rate_calculator::arbitrage::ArbitrageDetector;
+use solana_amm_rate_calculator::fixed::to_ui_amount;
+use solana_amm_rate_calculator::token_graph::TokenGraph;
+use solana_amm_rate_calculator::candles::{CandleAggregator, JsonLinesSink, SharedCandleAggregator};
+use solana_amm_rate_calculator::token_registry::{SharedTokenRegistry, TokenRegistry};
+use solana_amm_rate_calculator::output::{JsonLinesOutput, UpdateRecord};
+use std::io::{IsTerminal, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 
-use amm_types::{get_amm_programs, FilterConfig, MarketRate};
-use rate_calculator::RateCalculator;
-use table_ui::TableUI;
-use client::AmmClientManager;
-use tokio::sync::mpsc;
+/// How long candles stay open for out-of-order updates.
+const CANDLE_LATENESS_MS: i64 = 2 * ONE_SECOND_MS;
 
-pub mod solana {
-    pub mod amm {
-        tonic::include_proto!("solana.amm");
+/// Command line options.
+#[derive(Debug, Default)]
+struct Options {
+    /// Address to serve the AmmService fan-out hub on, e.g. `0.0.0.0:50051`.
+    serve_addr: Option<SocketAddr>,
+    /// Yellowstone Geyser endpoint to watch instead of an AmmService upstream.
+    geyser_endpoint: Option<String>,
+    /// `x-token` for the Geyser endpoint.
+    x_token: Option<String>,
+    /// JSON-RPC endpoint to read fee configs from when watching Geyser.
+    rpc_endpoint: Option<String>,
+    /// Timeframe of the volume filter in ms, e.g. from `--volume-window 1h`.
+    volume_timeframe_ms: Option<i64>,
+    /// File to append closed OHLCV candles to, one JSON object per line.
+    candles_out: Option<String>,
+    /// Candle resolutions in ms, from `--candle-resolutions 1s,1m,5m,1h`.
+    candle_resolutions_ms: Vec<i64>,
+    /// Report cross-pool arbitrage above this fee-adjusted spread, in bps.
+    min_spread_bps: Option<f64>,
+    /// Quote mints, most preferred first, from `--quote-preference USDC,SOL`.
+    quote_preference: Option<QuotePreference>,
+    /// JSON token list with symbols and decimals of known mints.
+    token_list: Option<String>,
+    /// Print a plain text table instead of the full-screen view.
+    plain: bool,
+    /// Table columns and widths, from `--columns program,pair:12,rate`.
+    columns: Option<Vec<(Column, u16)>>,
+    /// Candle resolution of the detail pane in ms, from `--chart-resolution 1m`.
+    chart_resolution_ms: Option<i64>,
+    /// Redraws per second of the table, from `--fps 10`.
+    frame_rate: Option<u32>,
+    /// What to write to stdout, from `--output table|jsonl`.
+    output: OutputFormat,
+    /// File for `--output jsonl` instead of stdout.
+    output_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum OutputFormat {
+    /// The rate table, full-screen or plain.
+    #[default]
+    Table,
+    /// One JSON object per update, see [`UpdateRecord`].
+    Jsonl,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--serve" => {
+                    let addr = args.next().ok_or("--serve requires an address")?;
+                    options.serve_addr = Some(addr.parse()?);
+                }
+                "--geyser" => {
+                    options.geyser_endpoint = Some(args.next().ok_or("--geyser requires an endpoint")?);
+                }
+                "--x-token" => {
+                    options.x_token = Some(args.next().ok_or("--x-token requires a token")?);
+                }
+                "--rpc" => {
+                    options.rpc_endpoint = Some(args.next().ok_or("--rpc requires an endpoint")?);
+                }
+                "--volume-window" => {
+                    let window = args.next().ok_or("--volume-window requires a timeframe")?;
+                    let timeframe_ms = parse_timeframe(&window)
+                        .ok_or_else(|| format!("Invalid volume window: {} (expected e.g. 1s, 1m, 1h, 24h)", window))?;
+                    options.volume_timeframe_ms = Some(timeframe_ms);
+                }
+                "--candles-out" => {
+                    options.candles_out = Some(args.next().ok_or("--candles-out requires a path")?);
+                }
+                "--candle-resolutions" => {
+                    let resolutions = args.next().ok_or("--candle-resolutions requires a list of timeframes")?;
+                    options.candle_resolutions_ms = resolutions
+                        .split(',')
+                        .map(|resolution| {
+                            parse_timeframe(resolution).ok_or_else(|| format!("Invalid candle resolution: {}", resolution))
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "--arbitrage" => {
+                    let spread = args.next().ok_or("--arbitrage requires a minimum spread in bps")?;
+                    options.min_spread_bps = Some(spread.parse()?);
+                }
+                "--quote-preference" => {
+                    let mints = args.next().ok_or("--quote-preference requires a list of mints")?;
+                    // The reference tokens can be given by symbol
+                    let mints = mints.split(',').map(|mint| match mint {
+                        "SOL" => SOL_MINT,
+                        "USDC" => USDC_MINT,
+                        "USDT" => USDT_MINT,
+                        mint => mint,
+                    });
+                    options.quote_preference = Some(QuotePreference::new(mints));
+                }
+                "--plain" => options.plain = true,
+                "--columns" => {
+                    let columns = args.next().ok_or("--columns requires a list of columns")?;
+                    options.columns = Some(Column::parse_list(&columns)?);
+                }
+                "--chart-resolution" => {
+                    let resolution = args.next().ok_or("--chart-resolution requires a timeframe")?;
+                    let resolution_ms = parse_timeframe(&resolution)
+                        .ok_or_else(|| format!("Invalid chart resolution: {}", resolution))?;
+                    options.chart_resolution_ms = Some(resolution_ms);
+                }
+                "--fps" => {
+                    let frame_rate: u32 = args.next().ok_or("--fps requires a frame rate")?.parse()?;
+                    if frame_rate == 0 {
+                        return Err("--fps must be at least 1".into());
+                    }
+                    options.frame_rate = Some(frame_rate);
+                }
+                "--output" => {
+                    options.output = match args.next().ok_or("--output requires a format")?.as_str() {
+                        "table" => OutputFormat::Table,
+                        "jsonl" => OutputFormat::Jsonl,
+                        other => return Err(format!("Unknown output format: {} (expected table or jsonl)", other).into()),
+                    };
+                }
+                "--output-file" => {
+                    options.output_file = Some(args.next().ok_or("--output-file requires a path")?);
+                }
+                "--token-list" => {
+                    options.token_list = Some(args.next().ok_or("--token-list requires a path")?);
+                }
+                other => return Err(format!("Unknown argument: {}", other).into()),
+            }
+        }
+        if options.output_file.is_some() && options.output != OutputFormat::Jsonl {
+            return Err("--output-file requires --output jsonl".into());
+        }
+        Ok(options)
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Starting Solana AMM Rate Calculator...");
-    
-    let filter_config = FilterConfig::default();
-    let mut calculator = RateCalculator::new(filter_config.clone());
-    let mut table_ui = TableUI::new(20);
+    eprintln!("Starting Solana AMM Rate Calculator...");
     
+    let options = Options::from_args()?;
+    let mut filter_config = FilterConfig::default();
+    if let Some(timeframe_ms) = options.volume_timeframe_ms {
+        filter_config.volume_timeframe_ms = timeframe_ms;
+    }
+    let quote_preference = options.quote_preference.clone().unwrap_or_default();
+    let registry = match &options.token_list {
+        Some(path) => TokenRegistry::load(path).map_err(|e| format!("Failed to load token list {}: {}", path, e))?,
+        None => TokenRegistry::new(),
+    };
+    eprintln!("Token registry has {} tokens", registry.len());
+    // Shared with the Geyser task, which adds the mints it decodes
+    let registry: SharedTokenRegistry = Arc::new(RwLock::new(registry));
+    let mut calculator = RateCalculator::new(filter_config.clone()).with_quote_preference(quote_preference.clone());
     let amm_programs = get_amm_programs();
+    let mut table_ui = TableUI::new(20).with_quote_preference(quote_preference.clone());
+    if let Some(columns) = options.columns.clone() {
+        table_ui = table_ui.with_columns(columns);
+    }
+    let mut arbitrage = options.min_spread_bps.map(|bps| {
+        let detector = ArbitrageDetector::new(bps).with_quote_preference(quote_preference.clone());
+        (detector, TokenGraph::new(bps))
+    });
+    // One aggregator writes candles out and feeds the table's chart
+    let show_table = options.output == OutputFormat::Table;
+    let chart_resolution_ms = options.chart_resolution_ms.unwrap_or(ONE_MINUTE_MS);
+    let candles = if show_table || options.candles_out.is_some() {
+        let out_resolutions = match &options.candles_out {
+            Some(_) if options.candle_resolutions_ms.is_empty() => {
+                vec![ONE_SECOND_MS, ONE_MINUTE_MS, 5 * ONE_MINUTE_MS, ONE_HOUR_MS]
+            }
+            Some(_) => options.candle_resolutions_ms.clone(),
+            None => Vec::new(),
+        };
+        let mut resolutions = out_resolutions.clone();
+        if show_table && !resolutions.contains(&chart_resolution_ms) {
+            resolutions.push(chart_resolution_ms);
+        }
+        let mut aggregator = CandleAggregator::new(&resolutions)
+            .with_quote_preference(quote_preference.clone())
+            .with_allowed_lateness(CANDLE_LATENESS_MS);
+        if show_table {
+            aggregator = aggregator.with_history(chart_resolution_ms, DETAIL_CANDLES);
+        }
+        if let Some(path) = &options.candles_out {
+            let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            // Only the requested resolutions, not the chart's
+            let sink = JsonLinesSink::new(std::io::BufWriter::new(file)).with_resolutions(&out_resolutions);
+            aggregator = aggregator.with_sink(sink);
+        }
+        let candles: SharedCandleAggregator = Arc::new(Mutex::new(aggregator));
+        if show_table {
+            table_ui = table_ui.with_candles(candles.clone(), chart_resolution_ms);
+        }
+        Some(candles)
+    } else {
+        None
+    };
     
-    println!("Connecting to {} AMM programs...", amm_programs.len());
-    
-    // Create gRPC client manager
-    println!("Creating gRPC client manager...");
-    let mut client_manager = AmmClientManager::new(amm_programs, filter_config).await?;
-    println!("gRPC client manager created successfully!");
+    eprintln!("Connecting to {} AMM programs...", amm_programs.len());
     
     // Create channel for receiving market rates
     let (tx, mut rx) = mpsc::channel::<MarketRate>(1000);
+    let (state_tx, state_rx) = mpsc::unbounded_channel();
     
-    // Start price subscriptions in background
-    println!("Starting price subscriptions...");
-    let subscription_handle = tokio::spawn(async move {
-        if let Err(e) = client_manager.start_price_subscriptions(tx).await {
-            eprintln!("Subscription error: {}", e);
-        }
+    // Optionally re-publish everything we receive to downstream clients
+    let hub = options.serve_addr.map(|addr| {
+        let server = AmmServer::new(1000);
+        let hub = server.clone();
+        tokio::spawn(async move {
+            if let Err(e) = server.serve(addr).await {
+                eprintln!("AmmService server error: {}", e);
+            }
+        });
+        hub
     });
-    println!("Price subscriptions started!");
     
-    println!("Starting main processing loop...");
+    let subscription_handle = if let Some(endpoint) = options.geyser_endpoint.clone() {
+        // Watch the programs directly from a Geyser node
+        let config = GeyserConfig {
+            endpoint,
+            x_token: options.x_token.clone(),
+            ..Default::default()
+        };
+        let (vaults_tx, vaults_rx) = watch::channel(Vec::new());
+        let subscriber = GeyserSubscriber::new(config, &amm_programs).with_vault_accounts(vaults_rx);
+        let (events_tx, events_rx) = mpsc::channel::<GeyserEvent>(1000);
+        let tracker = PoolTracker::new().with_registry(registry.clone());
+        tokio::spawn(forward_geyser_events(tracker, events_rx, tx, vaults_tx, hub.clone()));
+        
+        // The stream only sends fee configs when they change
+        match &options.rpc_endpoint {
+            Some(rpc_endpoint) => {
+                let rpc = RpcClient::new(rpc_endpoint);
+                let watched = CONFIG_ACCOUNTS
+                    .iter()
+                    .filter(|(program_id, _)| amm_programs.iter().any(|program| program.id == *program_id));
+                for (program_id, discriminator) in watched {
+                    match rpc.get_program_accounts(program_id, discriminator).await {
+                        Ok(accounts) => {
+                            for account in accounts {
+                                events_tx.send(GeyserEvent::Account(account)).await?;
+                            }
+                        }
+                        Err(e) => eprintln!("Failed to fetch config accounts of {}: {}", program_id, e),
+                    }
+                }
+            }
+            None => eprintln!("No --rpc endpoint: Raydium and Pump.fun pools wait for their fee configs to change"),
+        }
+        
+        eprintln!("Starting Geyser subscription...");
+        tokio::spawn(async move {
+            if let Err(e) = subscriber.run(events_tx, BackoffConfig::default(), Some(state_tx)).await {
+                eprintln!("Geyser subscription error: {}", e);
+            }
+        })
+    } else {
+        // Create gRPC client manager
+        eprintln!("Creating gRPC client manager...");
+        let mut client_manager =
+            AmmClientManager::new(amm_programs, filter_config).await?.with_state_reporter(state_tx);
+        eprintln!("gRPC client manager created successfully!");
+        
+        // Start price subscriptions in background
+        eprintln!("Starting price subscriptions...");
+        tokio::spawn(async move {
+            if let Err(e) = client_manager.start_price_subscriptions(tx).await {
+                eprintln!("Subscription error: {}", e);
+            }
+        })
+    };
+    eprintln!("Price subscriptions started!");
+    
+    eprintln!("Starting main processing loop...");
     
     // Main processing loop
-    println!("Waiting for market rate data...");
-    while let Some(market_rate) = rx.recv().await {
+    eprintln!("Waiting for market rate data...");
+    let mut jsonl = match (options.output, &options.output_file) {
+        (OutputFormat::Table, _) => None,
+        (OutputFormat::Jsonl, Some(path)) => {
+            let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            Some(JsonLinesOutput::spawn(Box::new(std::io::BufWriter::new(file)) as Box<dyn Write + Send>))
+        }
+        (OutputFormat::Jsonl, None) => {
+            Some(JsonLinesOutput::spawn(Box::new(std::io::BufWriter::new(std::io::stdout())) as Box<dyn Write + Send>))
+        }
+    };
+    // The display task draws from the shared table at its own pace; this
+    // loop only updates it
+    let table_ui: SharedTableUI = Arc::new(Mutex::new(table_ui));
+    let tui = if jsonl.is_some() || options.plain || !std::io::stdout().is_terminal() {
+        None
+    } else {
+        let mut tui = Tui::enter()?;
+        // Background tasks report errors on stderr, which would scribble
+        // over the full-screen view
+        #[cfg(unix)]
+        tui.capture_stderr(table_ui.clone())?;
+        Some(tui)
+    };
+    let full_screen = tui.is_some();
+    let (keys_tx, keys_rx) = mpsc::unbounded_channel();
+    if full_screen {
+        spawn_key_reader(keys_tx);
+    }
+    let frame_interval = Duration::from_secs(1) / options.frame_rate.unwrap_or(DEFAULT_FRAME_RATE);
+    let mut display = if jsonl.is_some() {
+        // Nothing to draw; stdout is for the records
+        tokio::spawn(report_states(state_rx))
+    } else {
+        let display = match tui {
+            Some(tui) => Display::Tui(tui),
+            None => Display::Plain(Box::new(std::io::stdout())),
+        };
+        tokio::spawn(run_display(table_ui.clone(), display, frame_interval, keys_rx, state_rx))
+    };
+    let mut quit = false;
+    loop {
+        let mut market_rate = tokio::select! {
+            market_rate = rx.recv() => match market_rate {
+                Some(market_rate) => market_rate,
+                None => break,
+            },
+            result = &mut display => {
+                result??;
+                quit = true;
+                break;
+            }
+        };
         let start_time = std::time::Instant::now();
+        let received_at_ms = chrono::Utc::now().timestamp_millis();
+        calculator.clear_performance_log();
         
-        // Apply filters
-        let (meets_liquidity, meets_volume) = calculator.apply_filters(&market_rate);
+        let filled = registry.read().unwrap().fill(&mut market_rate.token_pair);
+        if let Err(e) = filled {
+            notify(&table_ui, full_screen, format!("Skipping update for pool {}: {}", market_rate.pool_address, e));
+            continue;
+        }
+        
+        // Downstream clients apply their own filters
+        if let Some(hub) = &hub {
+            hub.publish(market_rate.clone());
+        }
+        
+        if let Some(candles) = &candles {
+            candles.lock().unwrap().observe(&market_rate);
+        }
+        let (opportunities, cycles) = arbitrage
+            .as_mut()
+            .map(|(detector, graph)| (detector.observe(&market_rate), graph.observe(&market_rate)))
+            .unwrap_or_default();
+        
+        // Apply filters and compute price changes
+        let update = calculator.price_update(market_rate);
         
-        if meets_liquidity && meets_volume {
-            calculator.log_transaction_detection(&market_rate.transaction_signature);
-            calculator.log_rate_output(&market_rate);
+        // Records carry the filter results, so readers choose what to keep
+        if let Some(output) = &jsonl {
+            if output.write(&UpdateRecord::new(&update, received_at_ms, start_time.elapsed())).await.is_err() {
+                if let Some(Err(e)) = jsonl.take().map(JsonLinesOutput::finish) {
+                    eprintln!("Stopping JSON Lines output: {}", e);
+                }
+                quit = true;
+                break;
+            }
+        }
+        
+        if update.meets_liquidity_filter && update.meets_volume_filter {
+            calculator.log_transaction_detection(&update.market_rate.transaction_signature);
+            calculator.log_rate_output(&update.market_rate);
             
-            table_ui.add_market_rate(market_rate);
-            table_ui.display_table();
+            if jsonl.is_none() {
+                table_ui.lock().unwrap().add_price_update(update);
+            }
             
             // Check 1ms performance
-            if !calculator.check_1ms_performance() {
-                println!("WARNING: Performance requirement not met!");
+            if let Some((operation, elapsed_ms)) = calculator.slow_operation() {
+                let message = format!("WARNING: Performance requirement not met! {} took {}ms", operation, elapsed_ms);
+                notify(&table_ui, full_screen, message);
             }
         }
         
+        for opportunity in &opportunities {
+            let message = format!(
+                "ARBITRAGE: buy {} at {:.6}, sell {} at {:.6}: {:.1} bps, best size {:.2}, profit {:.4}",
+                opportunity.buy_pool,
+                opportunity.buy_rate(),
+                opportunity.sell_pool,
+                opportunity.sell_rate(),
+                opportunity.spread_bps,
+                to_ui_amount(opportunity.optimal_size, opportunity.quote_decimals),
+                to_ui_amount(opportunity.expected_profit, opportunity.quote_decimals),
+            );
+            notify(&table_ui, full_screen, message);
+        }
+        // Two-pool cycles are the spreads reported above
+        for cycle in cycles.iter().filter(|cycle| cycle.legs.len() > 2) {
+            let pools: Vec<&str> = cycle.legs.iter().map(|leg| leg.pool_address.as_str()).collect();
+            let message = format!(
+                "ARBITRAGE CYCLE: {} via {}: {:.1} bps",
+                cycle.mints().join(" -> "),
+                pools.join(", "),
+                cycle.profit_bps,
+            );
+            notify(&table_ui, full_screen, message);
+        }
+        
         // Log processing time
         let elapsed = start_time.elapsed();
         if elapsed.as_millis() > 1 {
-            println!("WARNING: Main loop processing took {}ms!", elapsed.as_millis());
+            notify(&table_ui, full_screen, format!("WARNING: Main loop processing took {}ms!", elapsed.as_millis()));
         }
     }
+    // Leave the full-screen view before printing anything else
+    if !display.is_finished() {
+        display.abort();
+        let _ = display.await;
+    }
     
-    // Wait for subscription to complete (shouldn't happen unless error)
-    subscription_handle.await?;
+    if quit {
+        subscription_handle.abort();
+    } else {
+        // Wait for subscription to complete (shouldn't happen unless error)
+        subscription_handle.await?;
+    }
+    if let Some(candles) = &candles {
+        candles.lock().unwrap().flush();
+    }
+    if let Some(Err(e)) = jsonl.map(JsonLinesOutput::finish) {
+        eprintln!("JSON Lines output failed: {}", e);
+    }
     
     Ok(())
 }
 
+/// Show `message` in the full-screen view's message pane, or print it to
+/// stderr.
+fn notify(table_ui: &SharedTableUI, full_screen: bool, message: String) {
+    if full_screen {
+        table_ui.lock().unwrap().push_message(message);
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+/// Print subscription states to stderr, for when there is no table to show
+/// them in. Never finishes, like the display task it stands in for.
+async fn report_states(mut state_rx: mpsc::UnboundedReceiver<SubscriptionEvent>) -> std::io::Result<()> {
+    while let Some(event) = state_rx.recv().await {
+        eprintln!("{} ({}): {}", event.program_name, event.endpoint, event.state);
+    }
+    std::future::pending().await
+}
+
+/// Decode pool accounts from the Geyser stream into market rates, and keep
+/// the subscription's list of pool vaults and unknown mints up to date. Pool
+/// state goes to the hub, if any, for route quotes.
+async fn forward_geyser_events(
+    mut tracker: PoolTracker,
+    mut events_rx: mpsc::Receiver<GeyserEvent>,
+    tx: mpsc::Sender<MarketRate>,
+    vaults_tx: watch::Sender<Vec<String>>,
+    hub: Option<AmmServer>,
+) {
+    let mut calculator = RateCalculator::new(FilterConfig::default());
+    
+    while let Some(event) = events_rx.recv().await {
+        let GeyserEvent::Account(account) = event else {
+            continue;
+        };
+        match tracker.apply_account(&mut calculator, &account) {
+            Ok(Some(market_rate)) => {
+                if let Some(hub) = &hub {
+                    if let Some(pool) = tracker.route_pool(&market_rate.pool_address) {
+                        hub.publish_pool(pool);
+                    }
+                }
+                if tx.send(market_rate).await.is_err() {
+                    break;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to decode account {}: {}", account.pubkey, e),
+        }
+        
+        if tracker.take_subscriptions_changed() {
+            vaults_tx.send_if_modified(|vaults| {
+                let mut current = tracker.vault_accounts();
+                current.extend(tracker.unknown_mints());
+                let modified = *vaults != current;
+                *vaults = current;
+                modified
+            });
+        }
+    }
+}
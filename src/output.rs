@@ -0,0 +1,130 @@
+//! Machine-readable output of accepted updates.
+//!
+//! With `--output jsonl` the binary writes one [`UpdateRecord`] per line
+//! instead of drawing the table, for piping into jq, ingestion scripts or
+//! log shippers. Everything else it prints goes to stderr.
+
+use std::io::{self, Write};
+use std::time::Duration;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use crate::amm_types::PriceUpdate;
+
+/// One accepted update: the [`PriceUpdate`] fields, with filter results and
+/// price changes, plus the rate as a number and latency fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateRecord<'a> {
+    #[serde(flatten)]
+    pub update: &'a PriceUpdate,
+    /// Quote per base in whole tokens.
+    pub rate: f64,
+    /// When the update was received, in ms since the epoch.
+    pub received_at_ms: i64,
+    /// Time from the update's own timestamp to its receipt.
+    pub latency_ms: i64,
+    /// Time spent processing the update before it was written.
+    pub processing_us: u64,
+}
+
+impl<'a> UpdateRecord<'a> {
+    pub fn new(update: &'a PriceUpdate, received_at_ms: i64, processing: Duration) -> Self {
+        Self {
+            update,
+            rate: update.market_rate.rate(),
+            received_at_ms,
+            latency_ms: received_at_ms - update.market_rate.timestamp,
+            processing_us: processing.as_micros() as u64,
+        }
+    }
+}
+
+/// Records queued for the writer before [`JsonLinesOutput::write`] waits for
+/// it to catch up.
+const OUTPUT_BACKLOG: usize = 10_000;
+
+/// Writes each record as one JSON object per line on its own thread, so a
+/// slow reader only stalls the caller once [`OUTPUT_BACKLOG`] lines are
+/// queued; from then on [`Self::write`] waits for room rather than dropping
+/// records. Lines are flushed as soon as the queue is drained, so a burst of
+/// updates costs one flush and readers still see them as they happen.
+pub struct JsonLinesOutput<W> {
+    lines: mpsc::Sender<String>,
+    writer: std::thread::JoinHandle<io::Result<W>>,
+}
+
+impl<W: Write + Send + 'static> JsonLinesOutput<W> {
+    pub fn spawn(writer: W) -> Self {
+        let (lines, mut rx) = mpsc::channel::<String>(OUTPUT_BACKLOG);
+        let writer = std::thread::spawn(move || {
+            let mut writer = writer;
+            while let Some(line) = rx.blocking_recv() {
+                writer.write_all(line.as_bytes())?;
+                while let Ok(line) = rx.try_recv() {
+                    writer.write_all(line.as_bytes())?;
+                }
+                writer.flush()?;
+            }
+            Ok(writer)
+        });
+        Self { lines, writer }
+    }
+
+    /// Queue `record`, waiting while the queue is full. Fails once the
+    /// writer has stopped, e.g. on a closed pipe; [`Self::finish`] says why.
+    pub async fn write(&self, record: &UpdateRecord<'_>) -> io::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.lines
+            .send(line)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "JSON Lines writer stopped"))
+    }
+
+    /// Write out everything queued and hand back the writer, or the error
+    /// that stopped it.
+    pub fn finish(self) -> io::Result<W> {
+        drop(self.lines);
+        self.writer
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("JSON Lines writer panicked")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::WHIRLPOOLS;
+    use crate::test_utils;
+
+    #[tokio::test]
+    async fn test_records_are_one_json_object_per_line() {
+        let mut market_rate = test_utils::market_rate("pool", 1_000_000_000, 150_000_000);
+        market_rate.program_id = WHIRLPOOLS.to_string();
+        market_rate.liquidity.total_liquidity_quote = 300_000_000;
+        market_rate.timestamp = 1_700_000_000_000;
+        let update = PriceUpdate {
+            market_rate,
+            price_change_24h: Some(-2.5),
+            price_change_1h: None,
+            volume: 0,
+            meets_liquidity_filter: true,
+            meets_volume_filter: false,
+        };
+
+        let output = JsonLinesOutput::spawn(Vec::new());
+        let record = UpdateRecord::new(&update, 1_700_000_000_040, Duration::from_micros(250));
+        output.write(&record).await.unwrap();
+        output.write(&record).await.unwrap();
+
+        let output = String::from_utf8(output.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(json["market_rate"]["pool_address"], "pool");
+        assert_eq!(json["rate"], 150.0);
+        assert_eq!(json["price_change_24h"], -2.5);
+        assert!(json["price_change_1h"].is_null());
+        assert_eq!(json["meets_volume_filter"], false);
+        assert_eq!((json["latency_ms"].as_i64(), json["processing_us"].as_u64()), (Some(40), Some(250)));
+    }
+}
@@ -0,0 +1,615 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::amm_types::{MarketRate, TokenPair, METEORA_DLMM, PUMP_FUN_AMM, RAYDIUM_CL, WHIRLPOOLS};
+use crate::decoders::meteora_dlmm::{
+    BinArrayState, LbPairState, BIN_ARRAY_DISCRIMINATOR, LB_PAIR_DISCRIMINATOR,
+};
+use crate::decoders::pump_amm::{
+    PumpAmmPool, PumpGlobalConfig, GLOBAL_CONFIG_DISCRIMINATOR, POOL_DISCRIMINATOR as PUMP_POOL_DISCRIMINATOR,
+};
+use crate::decoders::raydium_clmm::{
+    RaydiumAmmConfig, RaydiumClmmPoolState, RaydiumTickArray, AMM_CONFIG_DISCRIMINATOR, POOL_STATE_DISCRIMINATOR,
+    TICK_ARRAY_DISCRIMINATOR as RAYDIUM_TICK_ARRAY_DISCRIMINATOR,
+};
+use crate::decoders::spl_token::{is_mint, Mint, TokenAccount, TOKEN_2022_PROGRAM, TOKEN_PROGRAM};
+use crate::decoders::whirlpool::{
+    WhirlpoolState, WhirlpoolTickArray, TICK_ARRAY_DISCRIMINATOR as WHIRLPOOL_TICK_ARRAY_DISCRIMINATOR,
+    WHIRLPOOL_DISCRIMINATOR,
+};
+use crate::decoders::DecodeError;
+use crate::geyser::AccountUpdate;
+use crate::quote::{ClmmCurve, ClmmProgram, Curve, QuotePool, TickMap};
+use crate::rate_calculator::RateCalculator;
+use crate::router::RoutePool;
+use crate::token_registry::SharedTokenRegistry;
+
+/// Config accounts pools take their fees from, by owning program and
+/// discriminator.
+pub const CONFIG_ACCOUNTS: [(&str, [u8; 8]); 2] =
+    [(RAYDIUM_CL, AMM_CONFIG_DISCRIMINATOR), (PUMP_FUN_AMM, GLOBAL_CONFIG_DISCRIMINATOR)];
+
+/// Turns raw account updates for the watched programs into market rates.
+///
+/// Pool accounts don't carry token symbols or (except Raydium) decimals, so
+/// pools are only priced once both mints are in the tracker's
+/// [`SharedTokenRegistry`]. Raydium pools don't wait for mints, using the
+/// decimals stored in the pool. Mint accounts owned by the token programs add
+/// their token to the registry, where whoever else holds it sees them too;
+/// [`PoolTracker::unknown_mints`] lists the ones to subscribe to.
+///
+/// Pump.fun AMM pools keep their reserves in SPL token vaults owned by the
+/// token program, so they are priced from three accounts: the pool and both
+/// vaults. [`PoolTracker::vault_accounts`] lists the vaults that have to be
+/// subscribed to on top of the program-owned accounts.
+///
+/// Raydium and Pump.fun pools take their fees from config accounts, which
+/// are skipped until seen rather than priced without fees. Those rarely
+/// change, so the stream may never send them; [`CONFIG_ACCOUNTS`] says which
+/// to fetch up front.
+pub struct PoolTracker {
+    registry: SharedTokenRegistry,
+    whirlpools: HashMap<String, WhirlpoolState>,
+    /// Tick arrays by pool address, then by start tick index.
+    whirlpool_tick_arrays: HashMap<String, BTreeMap<i32, WhirlpoolTickArray>>,
+    raydium_configs: HashMap<String, RaydiumAmmConfig>,
+    raydium_pools: HashMap<String, RaydiumClmmPoolState>,
+    raydium_tick_arrays: HashMap<String, BTreeMap<i32, RaydiumTickArray>>,
+    dlmm_pairs: HashMap<String, LbPairState>,
+    /// Bin arrays by pair address, then by bin array index.
+    dlmm_bin_arrays: HashMap<String, BTreeMap<i64, BinArrayState>>,
+    pump_pools: HashMap<String, PumpAmmPool>,
+    pump_global_config: Option<PumpGlobalConfig>,
+    /// Pump.fun pool address for each of its vaults.
+    pump_vaults: HashMap<String, String>,
+    /// Last seen balance of each vault.
+    vault_balances: HashMap<String, u64>,
+    /// Set when a pool, vault or mint is first seen.
+    subscriptions_changed: bool,
+}
+
+impl Default for PoolTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoolTracker {
+    pub fn new() -> Self {
+        Self {
+            registry: SharedTokenRegistry::default(),
+            whirlpools: HashMap::new(),
+            whirlpool_tick_arrays: HashMap::new(),
+            raydium_configs: HashMap::new(),
+            raydium_pools: HashMap::new(),
+            raydium_tick_arrays: HashMap::new(),
+            dlmm_pairs: HashMap::new(),
+            dlmm_bin_arrays: HashMap::new(),
+            pump_pools: HashMap::new(),
+            pump_global_config: None,
+            pump_vaults: HashMap::new(),
+            vault_balances: HashMap::new(),
+            subscriptions_changed: false,
+        }
+    }
+
+    /// Share `registry`, e.g. one loaded from a token list, instead of
+    /// starting from the reference tokens only.
+    pub fn with_registry(mut self, registry: SharedTokenRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn registry(&self) -> SharedTokenRegistry {
+        self.registry.clone()
+    }
+
+    pub fn add_token(&mut self, mint: &str, symbol: &str, decimals: u8) {
+        self.registry.write().unwrap().add_token(mint, symbol, decimals);
+        self.subscriptions_changed = true;
+    }
+
+    fn token_pair(&self, base_mint: &str, quote_mint: &str) -> Option<TokenPair> {
+        self.registry.read().unwrap().token_pair(base_mint, quote_mint)
+    }
+
+    /// Whether [`PoolTracker::vault_accounts`] or
+    /// [`PoolTracker::unknown_mints`] may have changed since the last call,
+    /// so subscriptions are only rebuilt when they need to be.
+    pub fn take_subscriptions_changed(&mut self) -> bool {
+        std::mem::take(&mut self.subscriptions_changed)
+    }
+
+    /// Token vaults of the tracked Pump.fun pools, sorted. These are owned by
+    /// the token program, so they need their own subscription.
+    pub fn vault_accounts(&self) -> Vec<String> {
+        let mut vaults: Vec<String> = self.pump_vaults.keys().cloned().collect();
+        vaults.sort();
+        vaults
+    }
+
+    /// Mints of the tracked pools missing from the registry, sorted. Their
+    /// `Mint` accounts are owned by the token programs too.
+    pub fn unknown_mints(&self) -> Vec<String> {
+        let registry = self.registry.read().unwrap();
+        let mut mints: Vec<String> = self
+            .whirlpools
+            .values()
+            .flat_map(|pool| [&pool.token_mint_a, &pool.token_mint_b])
+            .chain(self.raydium_pools.values().flat_map(|pool| [&pool.token_mint_0, &pool.token_mint_1]))
+            .chain(self.dlmm_pairs.values().flat_map(|pair| [&pair.token_x_mint, &pair.token_y_mint]))
+            .chain(self.pump_pools.values().flat_map(|pool| [&pool.base_mint, &pool.quote_mint]))
+            .filter(|mint| !registry.contains(mint))
+            .cloned()
+            .collect();
+        mints.sort();
+        mints.dedup();
+        mints
+    }
+
+    /// Decode `update` and return the pool's new rate, if it is a pool
+    /// account we can price. Other accounts owned by the programs (positions,
+    /// tick arrays, ...) are ignored.
+    pub fn apply_account(
+        &mut self,
+        calculator: &mut RateCalculator,
+        update: &AccountUpdate,
+    ) -> Result<Option<MarketRate>, DecodeError> {
+        let signature = update.txn_signature.clone().unwrap_or_default();
+
+        match update.owner.as_str() {
+            WHIRLPOOLS if update.data.starts_with(&WHIRLPOOL_DISCRIMINATOR) => {
+                let pool = WhirlpoolState::decode(&update.data)?;
+                let market_rate = self
+                    .token_pair(&pool.token_mint_a, &pool.token_mint_b)
+                    .map(|token_pair| calculator.whirlpool_market_rate(update.pubkey.clone(), &pool, token_pair, signature));
+                self.subscriptions_changed |= self.whirlpools.insert(update.pubkey.clone(), pool).is_none();
+                Ok(market_rate)
+            }
+            WHIRLPOOLS if update.data.starts_with(&WHIRLPOOL_TICK_ARRAY_DISCRIMINATOR) => {
+                let tick_array = WhirlpoolTickArray::decode(&update.data)?;
+                self.whirlpool_tick_arrays
+                    .entry(tick_array.whirlpool.clone())
+                    .or_default()
+                    .insert(tick_array.start_tick_index, tick_array);
+                Ok(None)
+            }
+            RAYDIUM_CL if update.data.starts_with(&AMM_CONFIG_DISCRIMINATOR) => {
+                let config = RaydiumAmmConfig::decode(&update.data)?;
+                self.raydium_configs.insert(update.pubkey.clone(), config);
+                Ok(None)
+            }
+            RAYDIUM_CL if update.data.starts_with(&POOL_STATE_DISCRIMINATOR) => {
+                let pool = RaydiumClmmPoolState::decode(&update.data)?;
+                let registry = self.registry.read().unwrap();
+                let token_pair = TokenPair {
+                    base_token: registry.symbol(&pool.token_mint_0),
+                    quote_token: registry.symbol(&pool.token_mint_1),
+                    base_mint: pool.token_mint_0.clone(),
+                    quote_mint: pool.token_mint_1.clone(),
+                    base_decimals: pool.mint_decimals_0,
+                    quote_decimals: pool.mint_decimals_1,
+                };
+                drop(registry);
+                let market_rate = self.raydium_configs.get(&pool.amm_config).map(|amm_config| {
+                    calculator.raydium_clmm_market_rate(update.pubkey.clone(), &pool, amm_config, token_pair, signature)
+                });
+                self.subscriptions_changed |= self.raydium_pools.insert(update.pubkey.clone(), pool).is_none();
+                Ok(market_rate)
+            }
+            RAYDIUM_CL if update.data.starts_with(&RAYDIUM_TICK_ARRAY_DISCRIMINATOR) => {
+                let tick_array = RaydiumTickArray::decode(&update.data)?;
+                self.raydium_tick_arrays
+                    .entry(tick_array.pool_id.clone())
+                    .or_default()
+                    .insert(tick_array.start_tick_index, tick_array);
+                Ok(None)
+            }
+            METEORA_DLMM if update.data.starts_with(&LB_PAIR_DISCRIMINATOR) => {
+                let pair = LbPairState::decode(&update.data)?;
+                self.subscriptions_changed |= self.dlmm_pairs.insert(update.pubkey.clone(), pair).is_none();
+                Ok(self.dlmm_market_rate(calculator, &update.pubkey, signature))
+            }
+            METEORA_DLMM if update.data.starts_with(&BIN_ARRAY_DISCRIMINATOR) => {
+                let bin_array = BinArrayState::decode(&update.data)?;
+                let pair_address = bin_array.lb_pair.clone();
+                self.dlmm_bin_arrays
+                    .entry(pair_address.clone())
+                    .or_default()
+                    .insert(bin_array.index, bin_array);
+                Ok(self.dlmm_market_rate(calculator, &pair_address, signature))
+            }
+            PUMP_FUN_AMM if update.data.starts_with(&GLOBAL_CONFIG_DISCRIMINATOR) => {
+                self.pump_global_config = Some(PumpGlobalConfig::decode(&update.data)?);
+                Ok(None)
+            }
+            PUMP_FUN_AMM if update.data.starts_with(&PUMP_POOL_DISCRIMINATOR) => {
+                let pool = PumpAmmPool::decode(&update.data)?;
+                for vault in [&pool.pool_base_token_account, &pool.pool_quote_token_account] {
+                    self.subscriptions_changed |= self.pump_vaults.insert(vault.clone(), update.pubkey.clone()).is_none();
+                }
+                self.subscriptions_changed |= self.pump_pools.insert(update.pubkey.clone(), pool).is_none();
+                Ok(self.pump_market_rate(calculator, &update.pubkey, signature))
+            }
+            TOKEN_PROGRAM | TOKEN_2022_PROGRAM if is_mint(&update.data) => {
+                // Pools waiting on this mint are priced on their next update
+                let mint = Mint::decode(&update.data)?;
+                let mut registry = self.registry.write().unwrap();
+                self.subscriptions_changed |= !registry.contains(&update.pubkey);
+                registry.apply_mint(&update.pubkey, mint);
+                Ok(None)
+            }
+            TOKEN_PROGRAM | TOKEN_2022_PROGRAM => {
+                let Some(pool_address) = self.pump_vaults.get(&update.pubkey).cloned() else {
+                    return Ok(None);
+                };
+                let vault = TokenAccount::decode(&update.data)?;
+                self.vault_balances.insert(update.pubkey.clone(), vault.amount);
+                Ok(self.pump_market_rate(calculator, &pool_address, signature))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Rate for a Pump.fun pool, once its pool account, both vaults and the
+    /// `GlobalConfig` its fees come from have been seen.
+    fn pump_market_rate(
+        &self,
+        calculator: &mut RateCalculator,
+        pool_address: &str,
+        signature: String,
+    ) -> Option<MarketRate> {
+        let pool = self.pump_pools.get(pool_address)?;
+        let base_reserve = *self.vault_balances.get(&pool.pool_base_token_account)?;
+        let quote_reserve = *self.vault_balances.get(&pool.pool_quote_token_account)?;
+        let token_pair = self.token_pair(&pool.base_mint, &pool.quote_mint)?;
+        let fees = self.pump_global_config.as_ref()?.fees_for(pool);
+        Some(calculator.pump_amm_market_rate(
+            pool_address.to_string(),
+            pool,
+            (base_reserve, quote_reserve),
+            fees,
+            token_pair,
+            signature,
+        ))
+    }
+
+    /// Current state of a tracked pool for swap quotes. `None` until the pool
+    /// and everything its fees depend on have been seen: Raydium pools need
+    /// their `AmmConfig`, Pump.fun pools both vaults and the `GlobalConfig`.
+    /// Quotes that run past the tick or bin arrays seen so far fail with
+    /// [`crate::quote::QuoteError::MissingLiquidityData`].
+    pub fn quote_pool(&self, pool_address: &str) -> Option<QuotePool> {
+        if let Some(pool) = self.whirlpools.get(pool_address) {
+            let token_pair = self.token_pair(&pool.token_mint_a, &pool.token_mint_b)?;
+            let mut ticks = TickMap::new();
+            for tick_array in self.whirlpool_tick_arrays.get(pool_address).into_iter().flat_map(|arrays| arrays.values()) {
+                ticks.insert_array(
+                    tick_array.start_tick_index,
+                    tick_array.end_tick_index(pool.tick_spacing),
+                    tick_array.initialized_ticks(pool.tick_spacing),
+                );
+            }
+            return Some(QuotePool {
+                base_decimals: token_pair.base_decimals,
+                quote_decimals: token_pair.quote_decimals,
+                curve: Curve::Clmm(ClmmCurve {
+                    program: ClmmProgram::Whirlpool,
+                    sqrt_price_x64: pool.sqrt_price,
+                    liquidity: pool.liquidity,
+                    tick_current: pool.tick_current_index,
+                    fee_rate: pool.fee_rate as u32,
+                    ticks,
+                }),
+            });
+        }
+
+        if let Some(pool) = self.raydium_pools.get(pool_address) {
+            let amm_config = self.raydium_configs.get(&pool.amm_config)?;
+            let mut ticks = TickMap::new();
+            for tick_array in self.raydium_tick_arrays.get(pool_address).into_iter().flat_map(|arrays| arrays.values()) {
+                ticks.insert_array(
+                    tick_array.start_tick_index,
+                    tick_array.end_tick_index(pool.tick_spacing),
+                    tick_array.initialized_ticks(),
+                );
+            }
+            return Some(QuotePool {
+                base_decimals: pool.mint_decimals_0,
+                quote_decimals: pool.mint_decimals_1,
+                curve: Curve::Clmm(ClmmCurve {
+                    program: ClmmProgram::RaydiumClmm,
+                    sqrt_price_x64: pool.sqrt_price_x64,
+                    liquidity: pool.liquidity,
+                    tick_current: pool.tick_current,
+                    fee_rate: amm_config.trade_fee_rate,
+                    ticks,
+                }),
+            });
+        }
+
+        if let Some(pair) = self.dlmm_pairs.get(pool_address) {
+            let token_pair = self.token_pair(&pair.token_x_mint, &pair.token_y_mint)?;
+            return Some(QuotePool {
+                base_decimals: token_pair.base_decimals,
+                quote_decimals: token_pair.quote_decimals,
+                curve: Curve::Dlmm {
+                    pair: pair.clone(),
+                    bin_arrays: self.dlmm_bin_arrays.get(pool_address).cloned().unwrap_or_default(),
+                },
+            });
+        }
+
+        let pool = self.pump_pools.get(pool_address)?;
+        let token_pair = self.token_pair(&pool.base_mint, &pool.quote_mint)?;
+        Some(QuotePool {
+            base_decimals: token_pair.base_decimals,
+            quote_decimals: token_pair.quote_decimals,
+            curve: Curve::ConstantProduct {
+                base_reserve: *self.vault_balances.get(&pool.pool_base_token_account)?,
+                quote_reserve: *self.vault_balances.get(&pool.pool_quote_token_account)?,
+                fees: self.pump_global_config.as_ref()?.fees_for(pool),
+            },
+        })
+    }
+
+    /// [`PoolTracker::quote_pool`] with the pool's program and mints, for
+    /// routing.
+    pub fn route_pool(&self, pool_address: &str) -> Option<RoutePool> {
+        let (program_id, base_mint, quote_mint) = if let Some(pool) = self.whirlpools.get(pool_address) {
+            (WHIRLPOOLS, &pool.token_mint_a, &pool.token_mint_b)
+        } else if let Some(pool) = self.raydium_pools.get(pool_address) {
+            (RAYDIUM_CL, &pool.token_mint_0, &pool.token_mint_1)
+        } else if let Some(pair) = self.dlmm_pairs.get(pool_address) {
+            (METEORA_DLMM, &pair.token_x_mint, &pair.token_y_mint)
+        } else {
+            let pool = self.pump_pools.get(pool_address)?;
+            (PUMP_FUN_AMM, &pool.base_mint, &pool.quote_mint)
+        };
+        Some(RoutePool {
+            address: pool_address.to_string(),
+            program_id: program_id.to_string(),
+            base_mint: base_mint.clone(),
+            quote_mint: quote_mint.clone(),
+            pool: self.quote_pool(pool_address)?,
+        })
+    }
+
+    /// Every tracked pool that can be quoted.
+    pub fn route_pools(&self) -> Vec<RoutePool> {
+        self.whirlpools
+            .keys()
+            .chain(self.raydium_pools.keys())
+            .chain(self.dlmm_pairs.keys())
+            .chain(self.pump_pools.keys())
+            .filter_map(|pool_address| self.route_pool(pool_address))
+            .collect()
+    }
+
+    /// Rate for a DLMM pair from its latest `LbPair` and all bin arrays seen
+    /// for it so far.
+    fn dlmm_market_rate(
+        &self,
+        calculator: &mut RateCalculator,
+        pair_address: &str,
+        signature: String,
+    ) -> Option<MarketRate> {
+        let pair = self.dlmm_pairs.get(pair_address)?;
+        let token_pair = self.token_pair(&pair.token_x_mint, &pair.token_y_mint)?;
+        let bin_arrays = self.dlmm_bin_arrays.get(pair_address).into_iter().flat_map(|arrays| arrays.values());
+        Some(calculator.meteora_dlmm_market_rate(pair_address.to_string(), pair, bin_arrays, token_pair, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::FilterConfig;
+    use crate::amm_types::{SOL_MINT, USDC_MINT};
+    use crate::quote::{QuoteError, Side};
+
+    fn account(owner: &str, data: &[u8]) -> AccountUpdate {
+        account_at("pool", owner, data)
+    }
+
+    fn account_at(pubkey: &str, owner: &str, data: &[u8]) -> AccountUpdate {
+        AccountUpdate {
+            pubkey: pubkey.to_string(),
+            owner: owner.to_string(),
+            lamports: 0,
+            data: data.to_vec(),
+            slot: 1,
+            write_version: 0,
+            txn_signature: Some("sig".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_prices_known_pools_and_skips_the_rest() {
+        let mut tracker = PoolTracker::new();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let whirlpool = include_bytes!("../fixtures/whirlpool_sol_usdc.bin");
+
+        let market_rate = tracker
+            .apply_account(&mut calculator, &account(WHIRLPOOLS, whirlpool))
+            .unwrap()
+            .unwrap();
+        assert_eq!(market_rate.pool_address, "pool");
+        assert_eq!(market_rate.token_pair.base_token, "SOL");
+        assert_eq!(market_rate.token_pair.quote_token, "USDC");
+        assert_eq!(market_rate.transaction_signature, "sig");
+        assert!(tracker.take_subscriptions_changed());
+
+        // Not a pool account, nor a new pool
+        let tick_array = account(WHIRLPOOLS, &[0u8; 64]);
+        assert!(tracker.apply_account(&mut calculator, &tick_array).unwrap().is_none());
+        tracker.apply_account(&mut calculator, &account(WHIRLPOOLS, whirlpool)).unwrap();
+        assert!(!tracker.take_subscriptions_changed());
+
+        // A pool with a mint we don't know yet
+        let mut unknown = whirlpool.to_vec();
+        unknown[181..213].copy_from_slice(&[3u8; 32]);
+        assert!(tracker.apply_account(&mut calculator, &account(WHIRLPOOLS, &unknown)).unwrap().is_none());
+        let unknown_mint = bs58::encode([3u8; 32]).into_string();
+        assert_eq!(tracker.unknown_mints(), vec![unknown_mint.clone()]);
+        tracker.add_token(&unknown_mint, "TEST", 6);
+        assert!(tracker.apply_account(&mut calculator, &account(WHIRLPOOLS, &unknown)).unwrap().is_some());
+        assert!(tracker.unknown_mints().is_empty());
+
+        // Or learns it from the mint account, symbol from Token-2022 metadata,
+        // for every holder of the registry
+        let registry = tracker.registry();
+        let mut other = whirlpool.to_vec();
+        other[181..213].copy_from_slice(&[4u8; 32]);
+        assert!(tracker.apply_account(&mut calculator, &account(WHIRLPOOLS, &other)).unwrap().is_none());
+        let mint = account_at(
+            &bs58::encode([4u8; 32]).into_string(),
+            TOKEN_2022_PROGRAM,
+            include_bytes!("../fixtures/token_2022_mint_ext.bin"),
+        );
+        assert!(tracker.apply_account(&mut calculator, &mint).unwrap().is_none());
+        assert!(tracker.take_subscriptions_changed());
+        assert_eq!(registry.read().unwrap().symbol(&mint.pubkey), "EXT");
+        let market_rate = tracker.apply_account(&mut calculator, &account(WHIRLPOOLS, &other)).unwrap().unwrap();
+        assert_eq!(market_rate.token_pair.quote_token, "EXT");
+    }
+
+    #[test]
+    fn test_raydium_fee_comes_from_cached_amm_config() {
+        let mut tracker = PoolTracker::new();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let pool = include_bytes!("../fixtures/raydium_clmm_sol_usdc.bin");
+        let config = include_bytes!("../fixtures/raydium_amm_config_4.bin");
+
+        // Not priced until the fee is known
+        assert!(tracker.apply_account(&mut calculator, &account(RAYDIUM_CL, pool)).unwrap().is_none());
+
+        let config_address = "9bJCbHNaWC2xaGAw2kYobvisixnkpyCEMBndHADX9jm5";
+        let config_update = account_at(config_address, RAYDIUM_CL, config);
+        assert!(tracker.apply_account(&mut calculator, &config_update).unwrap().is_none());
+
+        let after = tracker.apply_account(&mut calculator, &account(RAYDIUM_CL, pool)).unwrap().unwrap();
+        assert!((after.swap_fee - 0.0004).abs() < 1e-12);
+        assert_eq!(after.token_pair.base_token, "SOL");
+    }
+
+    #[test]
+    fn test_dlmm_liquidity_tracks_bin_arrays() {
+        let mut tracker = PoolTracker::new();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let pair_address = "FVNoAuP2ccK9JspGWArfGC8NXhbP9cbzH256GV7HCE5Y";
+        let pair = account_at(pair_address, METEORA_DLMM, include_bytes!("../fixtures/meteora_lb_pair_sol_usdc.bin"));
+        let bin_array = account_at("bins", METEORA_DLMM, include_bytes!("../fixtures/meteora_bin_array_sol_usdc.bin"));
+
+        // A bin array for a pair we haven't seen can't be priced yet
+        assert!(tracker.apply_account(&mut calculator, &bin_array).unwrap().is_none());
+
+        let market_rate = tracker.apply_account(&mut calculator, &pair).unwrap().unwrap();
+        assert_eq!(market_rate.pool_address, pair_address);
+        assert_eq!(market_rate.liquidity.base_reserve, 65_000_000_000);
+
+        // Re-sending the same bin array replaces it rather than double counting
+        let market_rate = tracker.apply_account(&mut calculator, &bin_array).unwrap().unwrap();
+        assert_eq!(market_rate.liquidity.base_reserve, 65_000_000_000);
+        assert_eq!(market_rate.liquidity.quote_reserve, 3_750_000_000);
+    }
+
+    #[test]
+    fn test_pump_pool_is_priced_from_its_vaults() {
+        let mut tracker = PoolTracker::new();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let pool = account_at(
+            "Dh6HSzUPeK6Xq1ohqaCjuvTA3sF6YMDqt2bVuJybWrCe",
+            PUMP_FUN_AMM,
+            include_bytes!("../fixtures/pump_amm_pool_sol_usdc.bin"),
+        );
+        let base_vault = account_at(
+            "9onNWGjzCuG3yotS7cNKBx7iwbNh6ghdZGJpTyQQonmo",
+            TOKEN_PROGRAM,
+            include_bytes!("../fixtures/pump_amm_vault_sol.bin"),
+        );
+        let quote_vault = account_at(
+            "44kVW25HBj2iBx7gHdu6SfyrA227j1fPBbPBkK13MnSQ",
+            TOKEN_PROGRAM,
+            include_bytes!("../fixtures/pump_amm_vault_usdc.bin"),
+        );
+        let global_config = account(PUMP_FUN_AMM, include_bytes!("../fixtures/pump_amm_global_config.bin"));
+
+        // Vaults of pools we haven't seen are ignored
+        assert!(tracker.apply_account(&mut calculator, &base_vault).unwrap().is_none());
+
+        assert!(tracker.apply_account(&mut calculator, &pool).unwrap().is_none());
+        assert_eq!(tracker.vault_accounts(), vec![quote_vault.pubkey.clone(), base_vault.pubkey.clone()]);
+        assert!(tracker.apply_account(&mut calculator, &base_vault).unwrap().is_none());
+
+        // Reserves are complete, but not priced until the fees are known
+        assert!(tracker.apply_account(&mut calculator, &quote_vault).unwrap().is_none());
+        assert!(tracker.apply_account(&mut calculator, &global_config).unwrap().is_none());
+        let market_rate = tracker.apply_account(&mut calculator, &quote_vault).unwrap().unwrap();
+        assert_eq!(market_rate.pool_address, pool.pubkey);
+        assert!((market_rate.rate() - 150.5).abs() < 1e-9);
+
+        // Any later change to one of them re-prices the pool
+        let mut drained = quote_vault.clone();
+        drained.data[64..72].copy_from_slice(&75_250_000_000u64.to_le_bytes());
+        let market_rate = tracker.apply_account(&mut calculator, &drained).unwrap().unwrap();
+        assert!((market_rate.rate() - 75.25).abs() < 1e-9);
+        assert!((market_rate.swap_fee - 0.003).abs() < 1e-12);
+        let market_rate = tracker.apply_account(&mut calculator, &pool).unwrap().unwrap();
+        assert!((market_rate.rate() - 75.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quote_pool_uses_tracked_ticks_and_fees() {
+        let mut tracker = PoolTracker::new();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let whirlpool_address = "Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE";
+        let whirlpool = account_at(whirlpool_address, WHIRLPOOLS, include_bytes!("../fixtures/whirlpool_sol_usdc.bin"));
+        let tick_array = account_at(
+            "ticks",
+            WHIRLPOOLS,
+            include_bytes!("../fixtures/whirlpool_tick_array_sol_usdc.bin"),
+        );
+
+        assert!(tracker.quote_pool(whirlpool_address).is_none());
+        tracker.apply_account(&mut calculator, &whirlpool).unwrap();
+
+        // Without tick arrays even a small swap can't be quoted
+        let pool = tracker.quote_pool(whirlpool_address).unwrap();
+        assert_eq!(
+            calculator.quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote).unwrap_err(),
+            QuoteError::MissingLiquidityData
+        );
+
+        assert!(tracker.apply_account(&mut calculator, &tick_array).unwrap().is_none());
+        let pool = tracker.quote_pool(whirlpool_address).unwrap();
+        let quote = calculator.quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote).unwrap();
+        assert_eq!((quote.amount_out, quote.fee), (149_920_653, 400_000));
+
+        // Raydium pools need their fee tier first
+        let raydium_address = "8sLbNZoA1cfnvMJLPfp98ZLAnFSYCFApfJKMbiXNLwxj";
+        let raydium = account_at(raydium_address, RAYDIUM_CL, include_bytes!("../fixtures/raydium_clmm_sol_usdc.bin"));
+        let raydium_ticks = account_at(
+            "ticks",
+            RAYDIUM_CL,
+            include_bytes!("../fixtures/raydium_tick_array_sol_usdc.bin"),
+        );
+        let amm_config = account_at(
+            "9bJCbHNaWC2xaGAw2kYobvisixnkpyCEMBndHADX9jm5",
+            RAYDIUM_CL,
+            include_bytes!("../fixtures/raydium_amm_config_4.bin"),
+        );
+        tracker.apply_account(&mut calculator, &raydium).unwrap();
+        tracker.apply_account(&mut calculator, &raydium_ticks).unwrap();
+        assert!(tracker.quote_pool(raydium_address).is_none());
+        tracker.apply_account(&mut calculator, &amm_config).unwrap();
+        let pool = tracker.quote_pool(raydium_address).unwrap();
+        let quote = calculator.quote_exact_out(&pool, 1_000_000_000, Side::QuoteToBase).unwrap();
+        assert_eq!(quote.amount_out, 1_000_000_000);
+        assert!(quote.effective_price > 150.25);
+
+        // Both pools are now routable
+        let route_pool = tracker.route_pool(raydium_address).unwrap();
+        assert_eq!(route_pool.program_id, RAYDIUM_CL);
+        assert_eq!((route_pool.base_mint.as_str(), route_pool.quote_mint.as_str()), (SOL_MINT, USDC_MINT));
+        assert_eq!(tracker.route_pools().len(), 2);
+    }
+}
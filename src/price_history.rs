@@ -0,0 +1,140 @@
+//! Per-pool price history for percentage change over a time window.
+
+use std::collections::{HashMap, VecDeque};
+use crate::fixed::PriceX64;
+use crate::volume::{ONE_DAY_MS, ONE_MINUTE_MS};
+
+#[derive(Debug, Clone)]
+struct PoolPrices {
+    /// At most one sample per `resolution_ms`, oldest first: the first
+    /// price seen in each interval.
+    samples: VecDeque<(i64, PriceX64)>,
+    latest: (i64, PriceX64),
+}
+
+/// Prices of every pool seen on the stream, kept for `retention_ms` at
+/// `resolution_ms`. Changes over windows shorter than the resolution are
+/// measured from the start of the interval instead.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    retention_ms: i64,
+    resolution_ms: i64,
+    pools: HashMap<String, PoolPrices>,
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self::new(ONE_DAY_MS, ONE_MINUTE_MS)
+    }
+}
+
+impl PriceHistory {
+    pub fn new(retention_ms: i64, resolution_ms: i64) -> Self {
+        Self {
+            retention_ms,
+            resolution_ms: resolution_ms.max(1),
+            pools: HashMap::new(),
+        }
+    }
+
+    /// Record `price` for `pool_address` at `timestamp` (ms). Zero prices
+    /// and updates older than the pool's latest are ignored.
+    pub fn record(&mut self, pool_address: &str, timestamp: i64, price: PriceX64) {
+        if price.is_zero() {
+            return;
+        }
+        let Some(pool) = self.pools.get_mut(pool_address) else {
+            self.pools.insert(
+                pool_address.to_string(),
+                PoolPrices {
+                    samples: VecDeque::from([(timestamp, price)]),
+                    latest: (timestamp, price),
+                },
+            );
+            return;
+        };
+        if timestamp < pool.latest.0 {
+            return;
+        }
+
+        pool.latest = (timestamp, price);
+        if pool.samples.back().is_none_or(|&(last, _)| timestamp >= last + self.resolution_ms) {
+            pool.samples.push_back((timestamp, price));
+        }
+        // Keep the newest sample at or before the retention boundary, so a
+        // change over the full retention still has a starting point.
+        let boundary = timestamp - self.retention_ms;
+        while pool.samples.get(1).is_some_and(|&(second, _)| second <= boundary) {
+            pool.samples.pop_front();
+        }
+    }
+
+    /// Latest price of `pool_address`.
+    pub fn latest(&self, pool_address: &str) -> Option<PriceX64> {
+        self.pools.get(pool_address).map(|pool| pool.latest.1)
+    }
+
+    /// Percentage change of the pool's price over the last `window_ms` of
+    /// stream time (5.0 = up 5%). `None` for a pool seen for less than
+    /// `window_ms`, whose history doesn't reach back that far.
+    pub fn change(&self, pool_address: &str, window_ms: i64) -> Option<f64> {
+        let pool = self.pools.get(pool_address)?;
+        let (now, current) = pool.latest;
+        if pool.samples.front().is_none_or(|&(first, _)| first > now - window_ms) {
+            return None;
+        }
+        let start = pool.samples.partition_point(|&(timestamp, _)| timestamp <= now - window_ms);
+        let (_, reference) = pool.samples[start.saturating_sub(1)];
+        Some((current.x64() as f64 / reference.x64() as f64 - 1.0) * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed::ONE_X64;
+    use crate::volume::ONE_HOUR_MS;
+
+    fn price(value: u128) -> PriceX64 {
+        PriceX64::from_x64(value * ONE_X64)
+    }
+
+    #[test]
+    fn test_change_over_windows() {
+        let mut history = PriceHistory::default();
+        history.record("pool", 0, price(100));
+        history.record("pool", ONE_HOUR_MS, price(110));
+        history.record("pool", 2 * ONE_HOUR_MS, price(121));
+
+        assert!((history.change("pool", ONE_HOUR_MS).unwrap() - 10.0).abs() < 1e-9);
+        assert!((history.change("pool", 2 * ONE_HOUR_MS).unwrap() - 21.0).abs() < 1e-9);
+        // Younger than the window: no change rather than the one since first seen
+        assert_eq!(history.change("pool", ONE_DAY_MS), None);
+        assert_eq!(history.change("other", ONE_HOUR_MS), None);
+
+        // Late and zero prices don't move anything
+        history.record("pool", ONE_HOUR_MS / 2, price(50));
+        history.record("pool", 3 * ONE_HOUR_MS, PriceX64::ZERO);
+        assert_eq!(history.latest("pool"), Some(price(121)));
+    }
+
+    #[test]
+    fn test_resolution_and_retention() {
+        let mut history = PriceHistory::new(ONE_HOUR_MS, ONE_MINUTE_MS);
+        // Updates within a minute of the last sample only move the latest price
+        history.record("pool", 0, price(100));
+        history.record("pool", 30_000, price(200));
+        assert_eq!(history.pools["pool"].samples.len(), 1);
+        assert!((history.change("pool", 1_000).unwrap() - 100.0).abs() < 1e-9);
+
+        for minute in 1..=180 {
+            history.record("pool", minute * ONE_MINUTE_MS, price(100 + minute as u128));
+        }
+        // Two hours past retention, one sample at the boundary survives
+        let samples = &history.pools["pool"].samples;
+        assert_eq!(samples.len(), 61);
+        assert_eq!(samples[0].0, 120 * ONE_MINUTE_MS);
+        let expected = (280.0 / 220.0 - 1.0) * 100.0;
+        assert!((history.change("pool", ONE_HOUR_MS).unwrap() - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,900 @@
+//! Swap quotes against decoded pool state.
+//!
+//! Each curve follows its program's integer math and rounding, so a quote is
+//! what the swap instruction would do against the same account state:
+//!
+//! * Pump.fun AMM: constant product on the vault balances, with the LP,
+//!   protocol and creator fees each rounded up.
+//! * Whirlpool and Raydium CLMM: Uniswap V3 style swap steps between
+//!   initialized ticks. Prices at tick boundaries come from the Uniswap V3
+//!   tick table truncated to Q64.64; the programs use their own 64-bit
+//!   tables, which can differ in the last bits, so an amount that crosses a
+//!   tick can differ by a unit. Within a tick range the math is exact.
+//! * Meteora DLMM: bin by bin from the active bin, with the variable fee
+//!   recomputed for every bin visited.
+//!
+//! Amounts are in raw token units.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::decoders::meteora_dlmm::{bin_array_index, BinArrayState, LbPairState, FEE_PRECISION};
+use crate::decoders::pump_amm::PumpFees;
+use crate::fixed::{PriceX64, U256};
+
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+/// Lowest sqrt price either CLMM program allows, in Q64.64.
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+/// Highest sqrt price either CLMM program allows, in Q64.64.
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_515_401_279_992_447_579_055;
+
+/// Denominator of CLMM fee rates.
+const CLMM_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Which way a swap goes. The base token is token A/0/X of the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Sell the base token for the quote token.
+    BaseToQuote,
+    /// Buy the base token with the quote token.
+    QuoteToBase,
+}
+
+/// Which of the swap's tokens a fee is charged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeSide {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Total fee charged, in the token given by `fee_side`.
+    pub fee: u64,
+    pub fee_side: FeeSide,
+    /// Quote tokens per base token actually paid or received, fees included,
+    /// adjusted for decimals.
+    pub effective_price: f64,
+    /// How far the execution price before fees is from the spot price, as a
+    /// fraction. Positive means worse than spot for the trader.
+    pub price_impact: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuoteError {
+    /// The requested amount, or the amount it quotes to, is zero.
+    ZeroAmount,
+    /// The pool can't fill the requested amount.
+    InsufficientLiquidity,
+    /// The swap would cross a tick array or bin array we haven't seen.
+    MissingLiquidityData,
+    /// An intermediate or final amount doesn't fit the program's integer types.
+    Overflow,
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuoteError::ZeroAmount => write!(f, "swap amount rounds to zero"),
+            QuoteError::InsufficientLiquidity => write!(f, "not enough liquidity in the pool"),
+            QuoteError::MissingLiquidityData => write!(f, "swap crosses tick or bin arrays that haven't been seen"),
+            QuoteError::Overflow => write!(f, "swap amount overflows"),
+        }
+    }
+}
+
+impl std::error::Error for QuoteError {}
+
+/// Everything needed to quote a swap on one pool.
+#[derive(Debug, Clone)]
+pub struct QuotePool {
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub curve: Curve,
+}
+
+#[derive(Debug, Clone)]
+pub enum Curve {
+    /// Pump.fun AMM pool priced from its vault balances.
+    ConstantProduct { base_reserve: u64, quote_reserve: u64, fees: PumpFees },
+    /// Whirlpool or Raydium CLMM pool.
+    Clmm(ClmmCurve),
+    /// Meteora DLMM pair with the bin arrays seen for it.
+    Dlmm { pair: LbPairState, bin_arrays: BTreeMap<i64, BinArrayState> },
+}
+
+/// The CLMM programs round the token A amount of a price range differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClmmProgram {
+    Whirlpool,
+    RaydiumClmm,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClmmCurve {
+    pub program: ClmmProgram,
+    pub sqrt_price_x64: u128,
+    pub liquidity: u128,
+    pub tick_current: i32,
+    /// Swap fee out of 1,000,000.
+    pub fee_rate: u32,
+    pub ticks: TickMap,
+}
+
+/// Initialized ticks of a CLMM pool, from the tick arrays seen so far.
+#[derive(Debug, Clone, Default)]
+pub struct TickMap {
+    /// `liquidity_net` by tick index.
+    ticks: BTreeMap<i32, i128>,
+    /// Tick ranges covered by a tick array, end exclusive, by start.
+    arrays: BTreeMap<i32, i32>,
+}
+
+impl TickMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the tick array covering `[start, end)`, replacing whatever was
+    /// known about that range.
+    pub fn insert_array(&mut self, start: i32, end: i32, initialized: impl IntoIterator<Item = (i32, i128)>) {
+        let stale: Vec<i32> = self.ticks.range(start..end).map(|(tick, _)| *tick).collect();
+        for tick in stale {
+            self.ticks.remove(&tick);
+        }
+        self.ticks.extend(initialized);
+        self.arrays.insert(start, end);
+    }
+
+    /// The contiguous run of tick arrays containing `tick`, as `[low, high)`.
+    fn covered_range(&self, tick: i32) -> Option<(i32, i32)> {
+        let (&start, &end) = self.arrays.range(..=tick).next_back()?;
+        if tick >= end {
+            return None;
+        }
+        let (mut low, mut high) = (start, end);
+        while let Some((&start, _)) = self.arrays.range(..low).next_back().filter(|(_, &end)| end == low) {
+            low = start;
+        }
+        while let Some(&end) = self.arrays.get(&high) {
+            high = end;
+        }
+        Some((low, high))
+    }
+
+    /// The next tick a swap from `tick` stops at: the next initialized tick
+    /// in the swap direction, or the edge of the known tick arrays. The flag
+    /// says whether the tick is initialized.
+    fn next_boundary(&self, tick: i32, zero_for_one: bool) -> Option<(i32, bool)> {
+        let (low, high) = self.covered_range(tick)?;
+        let next = if zero_for_one {
+            self.ticks.range(low..=tick).next_back()
+        } else {
+            self.ticks.range(tick + 1..high).next()
+        };
+        Some(match next {
+            Some((&next, _)) => (next, true),
+            None if zero_for_one => (low, false),
+            None => (high, false),
+        })
+    }
+}
+
+/// Quote swapping exactly `amount_in` on `pool`. `now` is the unix time in
+/// seconds, which only matters for DLMM fees.
+pub fn quote_exact_in(pool: &QuotePool, amount_in: u64, side: Side, now: i64) -> Result<SwapQuote, QuoteError> {
+    quote(pool, amount_in, side, true, now)
+}
+
+/// Quote receiving exactly `amount_out` from `pool`.
+pub fn quote_exact_out(pool: &QuotePool, amount_out: u64, side: Side, now: i64) -> Result<SwapQuote, QuoteError> {
+    quote(pool, amount_out, side, false, now)
+}
+
+fn quote(pool: &QuotePool, amount: u64, side: Side, exact_in: bool, now: i64) -> Result<SwapQuote, QuoteError> {
+    if amount == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    let (amount_in, amount_out, fee, fee_side) = match &pool.curve {
+        Curve::ConstantProduct { base_reserve, quote_reserve, fees } => {
+            constant_product_swap(*base_reserve, *quote_reserve, fees, amount, side, exact_in)?
+        }
+        Curve::Clmm(curve) => {
+            let (amount_in, amount_out, fee) = clmm_swap(curve, amount, side == Side::BaseToQuote, exact_in)?;
+            (amount_in, amount_out, fee, FeeSide::Input)
+        }
+        Curve::Dlmm { pair, bin_arrays } => {
+            let (amount_in, amount_out, fee) =
+                dlmm_swap(pair, bin_arrays, amount, side == Side::BaseToQuote, exact_in, now)?;
+            (amount_in, amount_out, fee, FeeSide::Input)
+        }
+    };
+    if amount_in == 0 || amount_out == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+
+    let base_scale = 10f64.powi(pool.base_decimals as i32);
+    let quote_scale = 10f64.powi(pool.quote_decimals as i32);
+    // Amounts before fees, to separate price impact from the fee
+    let (in_before_fee, out_before_fee) = match fee_side {
+        FeeSide::Input => (amount_in - fee, amount_out),
+        FeeSide::Output => (amount_in, amount_out + fee),
+    };
+    let (effective_price, price_before_fee) = match side {
+        Side::BaseToQuote => (
+            (amount_out as f64 / quote_scale) / (amount_in as f64 / base_scale),
+            (out_before_fee as f64 / quote_scale) / (in_before_fee as f64 / base_scale),
+        ),
+        Side::QuoteToBase => (
+            (amount_in as f64 / quote_scale) / (amount_out as f64 / base_scale),
+            (in_before_fee as f64 / quote_scale) / (out_before_fee as f64 / base_scale),
+        ),
+    };
+    let spot = spot_price(pool);
+    let price_impact = match side {
+        Side::BaseToQuote => (spot - price_before_fee) / spot,
+        Side::QuoteToBase => (price_before_fee - spot) / spot,
+    };
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        fee,
+        fee_side,
+        effective_price,
+        price_impact,
+    })
+}
+
+/// Quote tokens per base token at the current price, adjusted for decimals.
+pub fn spot_price(pool: &QuotePool) -> f64 {
+    let price = match &pool.curve {
+        Curve::ConstantProduct { base_reserve, quote_reserve, .. } => {
+            PriceX64::from_amounts(*base_reserve as u128, *quote_reserve as u128)
+        }
+        Curve::Clmm(curve) => PriceX64::from_sqrt_price_x64(curve.sqrt_price_x64),
+        Curve::Dlmm { pair, .. } => PriceX64::from_x64(pair.bin_price(pair.active_id).unwrap_or(0)),
+    };
+    price.to_f64(pool.base_decimals, pool.quote_decimals)
+}
+
+fn to_u64(value: u128) -> Result<u64, QuoteError> {
+    u64::try_from(value).map_err(|_| QuoteError::Overflow)
+}
+
+fn u256_to_u128(value: U256) -> Result<u128, QuoteError> {
+    if value.bits() > 128 {
+        return Err(QuoteError::Overflow);
+    }
+    Ok(value.as_u128())
+}
+
+fn div_ceil_u256(numerator: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    }
+}
+
+/// Fee of `bps` on `amount`, rounded up.
+fn bps_fee(amount: u128, bps: u64) -> u128 {
+    (amount * bps as u128).div_ceil(BPS_DENOMINATOR)
+}
+
+/// Pump.fun AMM swap. Fees are always charged in the quote token: taken from
+/// the proceeds of a sell and added on top of the cost of a buy.
+fn constant_product_swap(
+    base_reserve: u64,
+    quote_reserve: u64,
+    fees: &PumpFees,
+    amount: u64,
+    side: Side,
+    exact_in: bool,
+) -> Result<(u64, u64, u64, FeeSide), QuoteError> {
+    let (base_reserve, quote_reserve, amount) = (base_reserve as u128, quote_reserve as u128, amount as u128);
+    let total_fee = |quote: u128| {
+        bps_fee(quote, fees.lp_fee_bps) + bps_fee(quote, fees.protocol_fee_bps) + bps_fee(quote, fees.creator_fee_bps)
+    };
+
+    match (side, exact_in) {
+        (Side::BaseToQuote, true) => {
+            let quote_out = quote_reserve * amount / (base_reserve + amount);
+            let fee = total_fee(quote_out);
+            let amount_out = quote_out.checked_sub(fee).ok_or(QuoteError::ZeroAmount)?;
+            Ok((to_u64(amount)?, to_u64(amount_out)?, to_u64(fee)?, FeeSide::Output))
+        }
+        (Side::BaseToQuote, false) => {
+            // Gross up the proceeds so they cover the fee, then solve for the base in
+            let fee_bps = fees.total_bps() as u128;
+            if fee_bps >= BPS_DENOMINATOR {
+                return Err(QuoteError::InsufficientLiquidity);
+            }
+            let quote_out = (amount * BPS_DENOMINATOR).div_ceil(BPS_DENOMINATOR - fee_bps);
+            if quote_out >= quote_reserve {
+                return Err(QuoteError::InsufficientLiquidity);
+            }
+            let base_in = (base_reserve * quote_out).div_ceil(quote_reserve - quote_out);
+            Ok((to_u64(base_in)?, to_u64(amount)?, to_u64(quote_out - amount)?, FeeSide::Output))
+        }
+        (Side::QuoteToBase, true) => {
+            let fee_bps = fees.total_bps() as u128;
+            let quote_in = amount * BPS_DENOMINATOR / (BPS_DENOMINATOR + fee_bps);
+            let base_out = base_reserve * quote_in / (quote_reserve + quote_in);
+            Ok((to_u64(amount)?, to_u64(base_out)?, to_u64(amount - quote_in)?, FeeSide::Input))
+        }
+        (Side::QuoteToBase, false) => {
+            if amount >= base_reserve {
+                return Err(QuoteError::InsufficientLiquidity);
+            }
+            let quote_in = (quote_reserve * amount).div_ceil(base_reserve - amount);
+            let fee = total_fee(quote_in);
+            Ok((to_u64(quote_in + fee)?, to_u64(amount)?, to_u64(fee)?, FeeSide::Input))
+        }
+    }
+}
+
+/// Q64.64 sqrt price at `tick`.
+pub fn sqrt_price_at_tick(tick: i32) -> u128 {
+    // sqrt(1.0001)^-(2^i) as Q128.128, from Uniswap V3's TickMath
+    const RATIOS: [u128; 20] = [
+        0xfffcb933bd6fad37aa2d162d1a594001,
+        0xfff97272373d413259a46990580e213a,
+        0xfff2e50f5f656932ef12357cf3c7fdcc,
+        0xffe5caca7e10e4e61c3624eaa0941cd0,
+        0xffcb9843d60f6159c9db58835c926644,
+        0xff973b41fa98c081472e6896dfb254c0,
+        0xff2ea16466c96a3843ec78b326b52861,
+        0xfe5dee046a99a2a811c461f1969c3053,
+        0xfcbe86c7900a88aedcffc83b479aa3a4,
+        0xf987a7253ac413176f2b074cf7815e54,
+        0xf3392b0822b70005940c7a398e4b70f3,
+        0xe7159475a2c29b7443b29c7fa6e889d9,
+        0xd097f3bdfd2022b8845ad8f792aa5825,
+        0xa9f746462d870fdf8a65dc1f90e061e5,
+        0x70d869a156d2a1b890bb3df62baf32f7,
+        0x31be135f97d08fd981231505542fcfa6,
+        0x9aa508b5b7a84e1c677de54f3e99bc9,
+        0x5d6af8dedb81196699c329225ee604,
+        0x2216e584f5fa1ea926041bedfe98,
+        0x48a170391f7dc42444e8fa2,
+    ];
+
+    let tick = tick.clamp(MIN_TICK, MAX_TICK);
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 1 != 0 { U256::from(RATIOS[0]) } else { U256::one() << 128 };
+    for (bit, multiplier) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*multiplier)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    (ratio >> 64).as_u128().clamp(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64)
+}
+
+/// Token A needed to move the price between two sqrt prices.
+fn amount_a_delta(
+    program: ClmmProgram,
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64, QuoteError> {
+    let (lower, upper) = (sqrt_price_0.min(sqrt_price_1), sqrt_price_0.max(sqrt_price_1));
+    let numerator = (U256::from(liquidity) << 64) * U256::from(upper - lower);
+    let amount = match program {
+        // One division by the product of both prices
+        ClmmProgram::Whirlpool => {
+            let denominator = U256::from(upper) * U256::from(lower);
+            if round_up {
+                div_ceil_u256(numerator, denominator)
+            } else {
+                numerator / denominator
+            }
+        }
+        // Divided by each price in turn, as in Uniswap V3
+        ClmmProgram::RaydiumClmm => {
+            if round_up {
+                div_ceil_u256(div_ceil_u256(numerator, U256::from(upper)), U256::from(lower))
+            } else {
+                numerator / U256::from(upper) / U256::from(lower)
+            }
+        }
+    };
+    to_u64(u256_to_u128(amount)?)
+}
+
+/// Token B needed to move the price between two sqrt prices.
+fn amount_b_delta(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Result<u64, QuoteError> {
+    let diff = sqrt_price_0.abs_diff(sqrt_price_1);
+    let product = U256::from(liquidity) * U256::from(diff);
+    let amount = if round_up {
+        div_ceil_u256(product, U256::one() << 64)
+    } else {
+        product >> 64
+    };
+    to_u64(u256_to_u128(amount)?)
+}
+
+/// Sqrt price after adding (`add`) or removing `amount` of token A, rounded up.
+fn next_sqrt_price_from_a(sqrt_price: u128, liquidity: u128, amount: u64, add: bool) -> Result<u128, QuoteError> {
+    let liquidity_x64 = U256::from(liquidity) << 64;
+    let product = U256::from(amount) * U256::from(sqrt_price);
+    let denominator = if add {
+        liquidity_x64 + product
+    } else if liquidity_x64 > product {
+        liquidity_x64 - product
+    } else {
+        return Err(QuoteError::InsufficientLiquidity);
+    };
+    u256_to_u128(div_ceil_u256(liquidity_x64 * U256::from(sqrt_price), denominator))
+}
+
+/// Sqrt price after adding (`add`) or removing `amount` of token B, rounded down.
+fn next_sqrt_price_from_b(sqrt_price: u128, liquidity: u128, amount: u64, add: bool) -> Result<u128, QuoteError> {
+    let amount_x64 = U256::from(amount) << 64;
+    if add {
+        let delta = u256_to_u128(amount_x64 / U256::from(liquidity))?;
+        sqrt_price.checked_add(delta).ok_or(QuoteError::Overflow)
+    } else {
+        let delta = u256_to_u128(div_ceil_u256(amount_x64, U256::from(liquidity)))?;
+        sqrt_price.checked_sub(delta).ok_or(QuoteError::InsufficientLiquidity)
+    }
+}
+
+struct SwapStep {
+    sqrt_price_next: u128,
+    amount_in: u64,
+    amount_out: u64,
+    fee: u64,
+}
+
+/// One swap step within a tick range, as in Uniswap V3's `computeSwapStep`.
+/// `amount_remaining` is the input left for exact-in swaps and the output
+/// left for exact-out swaps.
+fn compute_swap_step(
+    program: ClmmProgram,
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    exact_in: bool,
+) -> Result<SwapStep, QuoteError> {
+    let zero_for_one = sqrt_price_current >= sqrt_price_target;
+    let fee_rate = fee_rate as u64;
+    let amount_in_to_target = |round_up| {
+        if zero_for_one {
+            amount_a_delta(program, sqrt_price_target, sqrt_price_current, liquidity, round_up)
+        } else {
+            amount_b_delta(sqrt_price_current, sqrt_price_target, liquidity, round_up)
+        }
+    };
+    let amount_out_to_target = || {
+        if zero_for_one {
+            amount_b_delta(sqrt_price_target, sqrt_price_current, liquidity, false)
+        } else {
+            amount_a_delta(program, sqrt_price_current, sqrt_price_target, liquidity, false)
+        }
+    };
+
+    let sqrt_price_next = if exact_in {
+        let remaining_less_fee = (amount_remaining as u128 * (CLMM_FEE_RATE_DENOMINATOR - fee_rate) as u128
+            / CLMM_FEE_RATE_DENOMINATOR as u128) as u64;
+        if remaining_less_fee >= amount_in_to_target(true)? {
+            sqrt_price_target
+        } else if zero_for_one {
+            next_sqrt_price_from_a(sqrt_price_current, liquidity, remaining_less_fee, true)?
+        } else {
+            next_sqrt_price_from_b(sqrt_price_current, liquidity, remaining_less_fee, true)?
+        }
+    } else if amount_remaining >= amount_out_to_target()? {
+        sqrt_price_target
+    } else if zero_for_one {
+        next_sqrt_price_from_b(sqrt_price_current, liquidity, amount_remaining, false)?
+    } else {
+        next_sqrt_price_from_a(sqrt_price_current, liquidity, amount_remaining, false)?
+    };
+
+    let (amount_in, mut amount_out) = if zero_for_one {
+        (
+            amount_a_delta(program, sqrt_price_next, sqrt_price_current, liquidity, true)?,
+            amount_b_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?,
+        )
+    } else {
+        (
+            amount_b_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?,
+            amount_a_delta(program, sqrt_price_current, sqrt_price_next, liquidity, false)?,
+        )
+    };
+    if !exact_in {
+        amount_out = amount_out.min(amount_remaining);
+    }
+
+    let fee = if exact_in && sqrt_price_next != sqrt_price_target {
+        // The step uses up the input; whatever isn't swapped is fee
+        amount_remaining - amount_in
+    } else {
+        to_u64((amount_in as u128 * fee_rate as u128).div_ceil((CLMM_FEE_RATE_DENOMINATOR - fee_rate) as u128))?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee,
+    })
+}
+
+/// Walk a CLMM pool tick by tick. Returns `(amount_in, amount_out, fee)`,
+/// with the fee in the input token and included in `amount_in`.
+fn clmm_swap(curve: &ClmmCurve, amount: u64, zero_for_one: bool, exact_in: bool) -> Result<(u64, u64, u64), QuoteError> {
+    let mut sqrt_price = curve.sqrt_price_x64;
+    let mut liquidity = curve.liquidity;
+    let mut tick = curve.tick_current;
+    let mut remaining = amount;
+    let (mut total_in, mut total_out, mut total_fee) = (0u64, 0u64, 0u64);
+
+    while remaining > 0 {
+        let (next_tick, initialized) = curve
+            .ticks
+            .next_boundary(tick, zero_for_one)
+            .ok_or(QuoteError::MissingLiquidityData)?;
+        let sqrt_price_target = sqrt_price_at_tick(next_tick);
+
+        let step = compute_swap_step(
+            curve.program,
+            sqrt_price,
+            sqrt_price_target,
+            liquidity,
+            remaining,
+            curve.fee_rate,
+            exact_in,
+        )?;
+        sqrt_price = step.sqrt_price_next;
+        let step_in = step.amount_in.checked_add(step.fee).ok_or(QuoteError::Overflow)?;
+        remaining -= if exact_in { step_in } else { step.amount_out };
+        total_in = total_in.checked_add(step_in).ok_or(QuoteError::Overflow)?;
+        total_out += step.amount_out;
+        total_fee += step.fee;
+
+        if sqrt_price != sqrt_price_target {
+            break;
+        }
+        if initialized {
+            let liquidity_net = curve.ticks.ticks[&next_tick];
+            let liquidity_net = if zero_for_one { -liquidity_net } else { liquidity_net };
+            liquidity = liquidity.checked_add_signed(liquidity_net).ok_or(QuoteError::Overflow)?;
+        }
+        tick = if zero_for_one { next_tick - 1 } else { next_tick };
+    }
+
+    Ok((total_in, total_out, total_fee))
+}
+
+/// Q64.64 price of a bin, preferring the one the program stored in it.
+fn bin_price_x64(pair: &LbPairState, stored: u128, bin_id: i32) -> Result<u128, QuoteError> {
+    if stored != 0 {
+        Ok(stored)
+    } else {
+        pair.bin_price(bin_id).ok_or(QuoteError::Overflow)
+    }
+}
+
+/// `amount * price >> 64`, rounded up or down.
+fn mul_shr(amount: u64, price: u128, round_up: bool) -> Result<u64, QuoteError> {
+    let product = U256::from(amount) * U256::from(price);
+    let shifted = if round_up {
+        div_ceil_u256(product, U256::one() << 64)
+    } else {
+        product >> 64
+    };
+    to_u64(u256_to_u128(shifted)?)
+}
+
+/// `(amount << 64) / price`, rounded up or down.
+fn shl_div(amount: u64, price: u128, round_up: bool) -> Result<u64, QuoteError> {
+    let numerator = U256::from(amount) << 64;
+    let quotient = if round_up {
+        div_ceil_u256(numerator, U256::from(price))
+    } else {
+        numerator / U256::from(price)
+    };
+    to_u64(u256_to_u128(quotient)?)
+}
+
+/// Walk a DLMM pair bin by bin from the active bin. Returns
+/// `(amount_in, amount_out, fee)`, with the fee in the input token and
+/// included in `amount_in`.
+fn dlmm_swap(
+    pair: &LbPairState,
+    bin_arrays: &BTreeMap<i64, BinArrayState>,
+    amount: u64,
+    swap_for_y: bool,
+    exact_in: bool,
+    now: i64,
+) -> Result<(u64, u64, u64), QuoteError> {
+    let mut pair = pair.clone();
+    pair.update_references(now);
+    let mut remaining = amount;
+    let (mut total_in, mut total_out, mut total_fee) = (0u64, 0u64, 0u64);
+
+    while remaining > 0 {
+        let bin_array = bin_arrays
+            .get(&bin_array_index(pair.active_id))
+            .ok_or(QuoteError::MissingLiquidityData)?;
+        let offset = (pair.active_id as i64 - bin_array.lower_bin_id()) as usize;
+        let bin = &bin_array.bins[offset];
+
+        pair.update_volatility_accumulator();
+        let fee_rate = pair.total_fee_rate();
+        // Fee on top of an amount, and fee out of an amount that includes it
+        let fee_on = |amount: u64| {
+            to_u64((amount as u128 * fee_rate).div_ceil(FEE_PRECISION - fee_rate))
+        };
+        let fee_from = |amount: u64| to_u64((amount as u128 * fee_rate).div_ceil(FEE_PRECISION));
+
+        let max_amount_out = if swap_for_y { bin.amount_y } else { bin.amount_x };
+        if max_amount_out > 0 {
+            let price = bin_price_x64(&pair, bin.price, pair.active_id)?;
+            let amount_in_for = |amount_out: u64| {
+                if swap_for_y {
+                    shl_div(amount_out, price, true)
+                } else {
+                    mul_shr(amount_out, price, true)
+                }
+            };
+            let max_amount_in = amount_in_for(max_amount_out)?;
+            let max_fee = fee_on(max_amount_in)?;
+            let max_amount_in_with_fee = max_amount_in.checked_add(max_fee).ok_or(QuoteError::Overflow)?;
+
+            let (step_in, step_out, step_fee) = if exact_in {
+                if remaining > max_amount_in_with_fee {
+                    (max_amount_in_with_fee, max_amount_out, max_fee)
+                } else {
+                    let fee = fee_from(remaining)?;
+                    let amount_in_after_fee = remaining - fee;
+                    let amount_out = if swap_for_y {
+                        mul_shr(amount_in_after_fee, price, false)?
+                    } else {
+                        shl_div(amount_in_after_fee, price, false)?
+                    };
+                    (remaining, amount_out.min(max_amount_out), fee)
+                }
+            } else if remaining >= max_amount_out {
+                (max_amount_in_with_fee, max_amount_out, max_fee)
+            } else {
+                let amount_in = amount_in_for(remaining)?;
+                let fee = fee_on(amount_in)?;
+                (amount_in.checked_add(fee).ok_or(QuoteError::Overflow)?, remaining, fee)
+            };
+
+            remaining -= if exact_in { step_in } else { step_out };
+            total_in = total_in.checked_add(step_in).ok_or(QuoteError::Overflow)?;
+            total_out += step_out;
+            total_fee += step_fee;
+        }
+
+        if remaining > 0 {
+            pair.active_id += if swap_for_y { -1 } else { 1 };
+        }
+    }
+
+    Ok((total_in, total_out, total_fee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pump_pool() -> QuotePool {
+        QuotePool {
+            base_decimals: 9,
+            quote_decimals: 6,
+            curve: Curve::ConstantProduct {
+                base_reserve: 1_000_000_000_000,
+                quote_reserve: 150_500_000_000,
+                fees: PumpFees { lp_fee_bps: 20, protocol_fee_bps: 5, creator_fee_bps: 5 },
+            },
+        }
+    }
+
+    fn whirlpool() -> QuotePool {
+        let pool = crate::decoders::whirlpool::WhirlpoolState::decode(include_bytes!(
+            "../fixtures/whirlpool_sol_usdc.bin"
+        ))
+        .unwrap();
+        let tick_array = crate::decoders::whirlpool::WhirlpoolTickArray::decode(include_bytes!(
+            "../fixtures/whirlpool_tick_array_sol_usdc.bin"
+        ))
+        .unwrap();
+        let mut ticks = TickMap::new();
+        ticks.insert_array(
+            tick_array.start_tick_index,
+            tick_array.end_tick_index(pool.tick_spacing),
+            tick_array.initialized_ticks(pool.tick_spacing),
+        );
+        QuotePool {
+            base_decimals: 9,
+            quote_decimals: 6,
+            curve: Curve::Clmm(ClmmCurve {
+                program: ClmmProgram::Whirlpool,
+                sqrt_price_x64: pool.sqrt_price,
+                liquidity: pool.liquidity,
+                tick_current: pool.tick_current_index,
+                fee_rate: pool.fee_rate as u32,
+                ticks,
+            }),
+        }
+    }
+
+    fn dlmm_pool() -> QuotePool {
+        let pair = LbPairState::decode(include_bytes!("../fixtures/meteora_lb_pair_sol_usdc.bin")).unwrap();
+        let bin_array = BinArrayState::decode(include_bytes!("../fixtures/meteora_bin_array_sol_usdc.bin")).unwrap();
+        QuotePool {
+            base_decimals: 9,
+            quote_decimals: 6,
+            curve: Curve::Dlmm {
+                pair,
+                bin_arrays: BTreeMap::from([(bin_array.index, bin_array)]),
+            },
+        }
+    }
+
+    #[test]
+    fn test_sqrt_price_at_tick() {
+        assert_eq!(sqrt_price_at_tick(0), 1 << 64);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK), MAX_SQRT_PRICE_X64);
+        // 1.0001^(1/2) and 1.0001^(-1/2)
+        assert_eq!(sqrt_price_at_tick(1), 18_447_666_387_855_959_850);
+        assert_eq!(sqrt_price_at_tick(-1), 18_445_821_805_675_392_311);
+        // The fixture pool sits just above tick -18,973
+        let sqrt_price = 7_144_393_258_922_745_604;
+        assert!(sqrt_price_at_tick(-18_973) <= sqrt_price && sqrt_price < sqrt_price_at_tick(-18_972));
+    }
+
+    #[test]
+    fn test_constant_product_rounding() {
+        let pool = pump_pool();
+
+        // Sell 1 SOL: 150_500_000_000 * 1e9 / 1_001e9 = 150_349_650 before fees,
+        // then 300_700 + 75_175 + 75_175 in fees, each rounded up
+        let sell = quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote, 0).unwrap();
+        assert_eq!(sell.amount_out, 150_349_650 - 451_050);
+        assert_eq!((sell.fee, sell.fee_side), (451_050, FeeSide::Output));
+        assert!((sell.price_impact - 0.000_999).abs() < 1e-6);
+
+        // Buy exactly 1 SOL: 150_650_651 before fees, rounded up
+        let buy = quote_exact_out(&pool, 1_000_000_000, Side::QuoteToBase, 0).unwrap();
+        assert_eq!(buy.amount_in, 150_650_651 + 451_954);
+        assert_eq!((buy.fee, buy.fee_side), (451_954, FeeSide::Input));
+        assert!(buy.effective_price > 150.5 * 1.003);
+
+        // Spending what that buy costs gets about the same SOL back; the
+        // exact-in path takes its fee as one ratio rather than three round-ups
+        let spend = quote_exact_in(&pool, buy.amount_in, Side::QuoteToBase, 0).unwrap();
+        assert_eq!(spend.amount_out, 1_000_000_015);
+
+        // Receiving what the sell paid out costs at most the SOL sold
+        let receive = quote_exact_out(&pool, sell.amount_out, Side::BaseToQuote, 0).unwrap();
+        assert!(receive.amount_in <= 1_000_000_001 && receive.amount_in > 999_990_000);
+
+        assert_eq!(
+            quote_exact_out(&pool, 1_000_000_000_000, Side::QuoteToBase, 0),
+            Err(QuoteError::InsufficientLiquidity)
+        );
+        assert_eq!(quote_exact_in(&pool, 0, Side::BaseToQuote, 0), Err(QuoteError::ZeroAmount));
+    }
+
+    #[test]
+    fn test_clmm_step_within_one_tick_range() {
+        let pool = whirlpool();
+        let Curve::Clmm(curve) = &pool.curve else { unreachable!() };
+
+        // 1 SOL is far from the next initialized tick at -19,000
+        let sell = quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote, 0).unwrap();
+        assert_eq!(sell.fee, 400_000);
+        let remaining = 1_000_000_000 - 400_000;
+        let sqrt_next = next_sqrt_price_from_a(curve.sqrt_price_x64, curve.liquidity, remaining, true).unwrap();
+        let expected_out = amount_b_delta(sqrt_next, curve.sqrt_price_x64, curve.liquidity, false).unwrap();
+        assert_eq!(sell.amount_out, expected_out);
+        // About 7,746 SOL of virtual reserves at this liquidity
+        assert!((sell.price_impact - 0.000_129).abs() < 1e-6);
+        assert!((sell.effective_price - 149.920_653).abs() < 1e-9);
+        assert!(sell.price_impact > 0.0 && sell.price_impact < 0.001);
+
+        // Exact out at the same size agrees to within rounding
+        let receive = quote_exact_out(&pool, sell.amount_out, Side::BaseToQuote, 0).unwrap();
+        assert_eq!(receive.amount_out, sell.amount_out);
+        assert!(receive.amount_in.abs_diff(1_000_000_000) <= 1);
+    }
+
+    #[test]
+    fn test_clmm_crosses_initialized_ticks() {
+        let pool = whirlpool();
+
+        // Selling 5 SOL runs past the tick at -18,980 and loses a third of the
+        // liquidity, so it moves the price more than 5x a 1 SOL sale
+        let small = quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote, 0).unwrap();
+        let large = quote_exact_in(&pool, 5_000_000_000, Side::BaseToQuote, 0).unwrap();
+        assert!(large.price_impact > 5.0 * small.price_impact);
+
+        // Buying 35 SOL walks up through -18,900 the same way
+        let buy = quote_exact_out(&pool, 35_000_000_000, Side::QuoteToBase, 0).unwrap();
+        assert_eq!(buy.amount_out, 35_000_000_000);
+        assert!(buy.effective_price > 150.0);
+
+        // Past the end of the tick array the quote needs more data
+        assert_eq!(
+            quote_exact_in(&pool, 1_000_000_000_000, Side::BaseToQuote, 0),
+            Err(QuoteError::MissingLiquidityData)
+        );
+    }
+
+    #[test]
+    fn test_raydium_rounds_token_a_in_two_steps() {
+        // Same range and liquidity, different rounding of the A amount
+        let (lower, upper, liquidity) = (7_144_089_069_857_984_833, 7_150_344_441_344_836_650, 5_250_000_000_000);
+        let whirlpool = amount_a_delta(ClmmProgram::Whirlpool, lower, upper, liquidity, true).unwrap();
+        let raydium = amount_a_delta(ClmmProgram::RaydiumClmm, lower, upper, liquidity, true).unwrap();
+        assert!(whirlpool.abs_diff(raydium) <= 1);
+        assert!(amount_a_delta(ClmmProgram::RaydiumClmm, lower, upper, liquidity, false).unwrap() <= raydium);
+    }
+
+    #[test]
+    fn test_dlmm_fills_bin_by_bin() {
+        let pool = dlmm_pool();
+        let Curve::Dlmm { pair, .. } = &pool.curve else { unreachable!() };
+
+        // Long enough after the last swap that volatility has decayed to zero
+        let now = pair.last_update_timestamp + 1_000;
+
+        // Selling 1 SOL fits in the active bin's 750 USDC and pays the base fee
+        let sell = quote_exact_in(&pool, 1_000_000_000, Side::BaseToQuote, now).unwrap();
+        assert_eq!(sell.fee, 1_000_000);
+        assert_eq!(sell.amount_out, mul_shr(1_000_000_000 - 1_000_000, 2_769_967_950_966_767_054, false).unwrap());
+        assert!(sell.price_impact.abs() < 1e-6);
+
+        // Selling 10 SOL empties it and moves down into the USDC-only bins
+        let large = quote_exact_in(&pool, 10_000_000_000, Side::BaseToQuote, now).unwrap();
+        assert!(large.amount_out > 750_000_000 && large.price_impact > 0.0);
+        assert!(large.fee > 10 * sell.fee, "variable fee grows as the swap crosses bins");
+
+        // Buying 6 SOL takes the active bin's 5 SOL and one more from the next bin up
+        let buy = quote_exact_out(&pool, 6_000_000_000, Side::QuoteToBase, now).unwrap();
+        assert_eq!(buy.amount_out, 6_000_000_000);
+        assert!(buy.effective_price > 150.16);
+
+        // The fixture has one bin array, so a large buy runs off its end
+        assert_eq!(
+            quote_exact_out(&pool, 66_000_000_000, Side::QuoteToBase, now),
+            Err(QuoteError::MissingLiquidityData)
+        );
+    }
+
+    #[test]
+    fn test_tick_map_coverage() {
+        let mut ticks = TickMap::new();
+        ticks.insert_array(0, 60, [(10, 5)]);
+        ticks.insert_array(60, 120, [(90, -5)]);
+        ticks.insert_array(-120, -60, []);
+
+        assert_eq!(ticks.covered_range(30), Some((0, 120)));
+        assert_eq!(ticks.next_boundary(30, false), Some((90, true)));
+        assert_eq!(ticks.next_boundary(30, true), Some((10, true)));
+        assert_eq!(ticks.next_boundary(5, true), Some((0, false)));
+        assert_eq!(ticks.next_boundary(-30, true), None);
+
+        // A newer copy of an array replaces its ticks
+        ticks.insert_array(60, 120, []);
+        assert_eq!(ticks.next_boundary(30, false), Some((120, false)));
+    }
+}
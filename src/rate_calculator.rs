@@ -1,28 +1,87 @@
-use crate::amm_types::{MarketRate, PoolLiquidity, TokenPair, FilterConfig};
+use crate::amm_types::{
+    MarketRate, PoolLiquidity, PriceUpdate, QuotePreference, TokenPair, FilterConfig, METEORA_DLMM, PUMP_FUN_AMM, RAYDIUM_CL, WHIRLPOOLS,
+};
+use crate::decoders::meteora_dlmm::{BinArrayState, LbPairState};
+use crate::decoders::pump_amm::{PumpAmmPool, PumpFees};
+use crate::decoders::raydium_clmm::{RaydiumAmmConfig, RaydiumClmmPoolState};
+use crate::decoders::whirlpool::WhirlpoolState;
+use crate::fixed::{from_ui_amount, saturating_u128, PriceX64, Q64_SHIFT, U256};
+use crate::price_history::PriceHistory;
+use crate::quote::{self, QuoteError, QuotePool, Side, SwapQuote};
+use crate::reference_prices::ReferencePrices;
+use crate::router::{self, Route, RouteConfig, RouteError, RoutePool};
+use crate::volume::{VolumeTracker, ONE_DAY_MS, ONE_HOUR_MS};
+use std::borrow::Cow;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+/// Raw token amounts that would be needed to provide `liquidity` at the
+/// current price with a full-range position. Concentrated liquidity pools
+/// don't expose their real reserves, so this is what we report as pool depth.
+/// Saturates at `u64::MAX`.
+pub fn virtual_reserves(liquidity: u128, sqrt_price_x64: u128) -> (u64, u64) {
+    if sqrt_price_x64 == 0 {
+        return (0, 0);
+    }
+    let liquidity = U256::from(liquidity);
+    let sqrt_price = U256::from(sqrt_price_x64);
+    let base = (liquidity << Q64_SHIFT) / sqrt_price;
+    let quote = (liquidity * sqrt_price) >> Q64_SHIFT;
+    (saturating_u64(saturating_u128(base)), saturating_u64(saturating_u128(quote)))
+}
+
+fn saturating_u64(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// Liquidity for raw reserves, valued in the quote token at `price`.
+fn pool_liquidity(price: PriceX64, base_reserve: u64, quote_reserve: u64) -> PoolLiquidity {
+    PoolLiquidity {
+        base_reserve,
+        quote_reserve,
+        total_liquidity_quote: price.quote_value(base_reserve as u128).saturating_add(quote_reserve as u128),
+        volume_24h: 0, // Would come from real data
+        volume_1h: 0,  // Would come from real data
+    }
+}
+
 pub struct RateCalculator {
     filter_config: FilterConfig,
+    /// Orientation pools are compared in; `Denomination::Quote` thresholds
+    /// are in the canonical quote token.
+    quote_preference: QuotePreference,
+    reference_prices: ReferencePrices,
+    /// Trade volume over `filter_config.volume_timeframe_ms`.
+    volume: VolumeTracker,
+    price_history: PriceHistory,
     performance_log: Vec<(String, Instant)>,
 }
 
 impl RateCalculator {
     pub fn new(filter_config: FilterConfig) -> Self {
         Self {
+            volume: VolumeTracker::new(filter_config.volume_timeframe_ms),
             filter_config,
+            quote_preference: QuotePreference::default(),
+            reference_prices: ReferencePrices::new(),
+            price_history: PriceHistory::default(),
             performance_log: Vec::new(),
         }
     }
 
-    pub fn calculate_rate(&mut self, base_amount: f64, quote_amount: f64) -> f64 {
+    pub fn with_quote_preference(mut self, quote_preference: QuotePreference) -> Self {
+        self.quote_preference = quote_preference;
+        self
+    }
+
+    pub fn quote_preference(&self) -> &QuotePreference {
+        &self.quote_preference
+    }
+
+    /// Quote / Base for raw reserves (USDC per SOL).
+    pub fn calculate_rate(&mut self, base_amount: u64, quote_amount: u64) -> PriceX64 {
         let start_time = Instant::now();
         
-        // Rate = Quote / Base (USDC per SOL)
-        let rate = if base_amount > 0.0 {
-            quote_amount / base_amount
-        } else {
-            0.0
-        };
+        let rate = PriceX64::from_amounts(base_amount as u128, quote_amount as u128);
 
         // Log performance
         let elapsed = start_time.elapsed();
@@ -34,50 +93,126 @@ impl RateCalculator {
         rate
     }
 
-    pub fn apply_filters(&self, market_rate: &MarketRate) -> (bool, bool) {
-        let meets_liquidity = market_rate.liquidity.total_liquidity_usd >= self.filter_config.min_liquidity_sol;
-        let meets_volume = market_rate.liquidity.volume_1h >= self.filter_config.min_volume_sol;
+    /// Take what the stream tells us about the market from `market_rate`
+    /// without filtering it. [`RateCalculator::apply_filters`] does this
+    /// itself; call it directly to prime the calculator with rates that are
+    /// filtered later.
+    pub fn observe(&mut self, market_rate: &MarketRate) {
+        self.reference_prices.observe(market_rate);
+        self.volume.observe(market_rate);
+        self.price_history
+            .record(&market_rate.pool_address, market_rate.timestamp, market_rate.price);
+    }
+
+    /// Filter `market_rate` and attach the pool's 1h and 24h price change.
+    pub fn price_update(&mut self, market_rate: MarketRate) -> PriceUpdate {
+        let start_time = Instant::now();
+
+        let (meets_liquidity_filter, meets_volume_filter) = self.apply_filters(&market_rate);
+        let pool_address = &market_rate.pool_address;
+        let price_change_1h = self.price_change(pool_address, ONE_HOUR_MS);
+        let price_change_24h = self.price_change(pool_address, ONE_DAY_MS);
+        let volume = self.volume.volume(pool_address);
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("Price update: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        PriceUpdate {
+            market_rate,
+            price_change_24h,
+            price_change_1h,
+            volume,
+            meets_liquidity_filter,
+            meets_volume_filter,
+        }
+    }
+
+    /// Percentage change of the pool's price over the last `window_ms`, see
+    /// [`PriceHistory::change`]. `None` for a pool not seen for that long.
+    pub fn price_change(&self, pool_address: &str, window_ms: i64) -> Option<f64> {
+        self.price_history.change(pool_address, window_ms)
+    }
+
+    /// Whether the pool's liquidity and its traded volume over the
+    /// configured timeframe, converted to the configured denomination, meet
+    /// the thresholds. A pool whose quote token has no reference price yet
+    /// fails both.
+    pub fn apply_filters(&mut self, market_rate: &MarketRate) -> (bool, bool) {
+        self.observe(market_rate);
+        let volume = self.volume.volume(&market_rate.pool_address);
+        self.check_filters(market_rate, volume)
+    }
+
+    /// [`RateCalculator::apply_filters`] with volume from `volume` instead of
+    /// this calculator's own window, for callers that track volume across
+    /// calculators.
+    pub fn apply_filters_with_volume(&mut self, market_rate: &MarketRate, volume: &mut VolumeTracker) -> (bool, bool) {
+        self.reference_prices.observe(market_rate);
+        let volume = volume.volume(&market_rate.pool_address);
+        self.check_filters(market_rate, volume)
+    }
+
+    fn check_filters(&self, market_rate: &MarketRate, volume: u128) -> (bool, bool) {
+        // Value everything in the canonical quote token, so a pool is
+        // measured the same whichever way round it lists its pair
+        let (market_rate, volume) = if self.quote_preference.is_reversed(&market_rate.token_pair) {
+            (Cow::Owned(market_rate.inverse()), market_rate.price.inverse().quote_value(volume))
+        } else {
+            (Cow::Borrowed(market_rate), volume)
+        };
+
+        let denomination = self.filter_config.denomination;
+        let quote_mint = &market_rate.token_pair.quote_mint;
+        let decimals = denomination.decimals(market_rate.token_pair.quote_decimals);
+        let meets = |raw_quote: u128, threshold: f64| {
+            self.reference_prices
+                .convert(quote_mint, raw_quote, denomination)
+                .is_some_and(|value| value >= from_ui_amount(threshold, decimals))
+        };
+        let meets_liquidity = meets(market_rate.liquidity.total_liquidity_quote, self.filter_config.min_liquidity);
+        let meets_volume = meets(volume, self.filter_config.min_volume);
         
         (meets_liquidity, meets_volume)
     }
 
+    pub fn reference_prices(&self) -> &ReferencePrices {
+        &self.reference_prices
+    }
+
+    /// Quote volume traded on `pool_address` over the filter's timeframe.
+    pub fn rolling_volume(&mut self, pool_address: &str) -> u128 {
+        self.volume.volume(pool_address)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_market_rate(
         &mut self,
         program_id: String,
         pool_address: String,
         token_pair: TokenPair,
-        base_liquidity: f64,
-        quote_liquidity: f64,
+        base_reserve: u64,
+        quote_reserve: u64,
         swap_fee: f64,
         transaction_signature: String,
     ) -> MarketRate {
         let start_time = Instant::now();
         
         // Calculate rate
-        let rate = self.calculate_rate(base_liquidity, quote_liquidity);
-        
-        // Create liquidity info
-        let liquidity = PoolLiquidity {
-            base_liquidity,
-            quote_liquidity,
-            total_liquidity_usd: base_liquidity + quote_liquidity, // Simplified
-            volume_24h: 0.0, // Would come from real data
-            volume_1h: 0.0,  // Would come from real data
-        };
+        let price = self.calculate_rate(base_reserve, quote_reserve);
 
         // Get timestamp
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
+        let timestamp = current_timestamp_ms();
 
         let market_rate = MarketRate {
             program_id,
             pool_address,
             token_pair,
-            rate,
+            price,
             swap_fee,
-            liquidity,
+            liquidity: pool_liquidity(price, base_reserve, quote_reserve),
             timestamp,
             transaction_signature,
         };
@@ -92,6 +227,225 @@ impl RateCalculator {
         market_rate
     }
 
+    /// Build a `MarketRate` from a decoded Whirlpool account. Token A is the
+    /// base and token B the quote; decimals come from `token_pair`, while the
+    /// mints are taken from the account itself.
+    pub fn whirlpool_market_rate(
+        &mut self,
+        pool_address: String,
+        pool: &WhirlpoolState,
+        mut token_pair: TokenPair,
+        transaction_signature: String,
+    ) -> MarketRate {
+        token_pair.base_mint = pool.token_mint_a.clone();
+        token_pair.quote_mint = pool.token_mint_b.clone();
+
+        self.clmm_market_rate(
+            WHIRLPOOLS,
+            pool_address,
+            token_pair,
+            pool.sqrt_price,
+            pool.liquidity,
+            pool.swap_fee(),
+            transaction_signature,
+        )
+    }
+
+    /// Build a `MarketRate` from a decoded Raydium CLMM pool. Token 0 is the
+    /// base and token 1 the quote; mints and decimals are taken from the
+    /// account. The fee comes from the pool's `AmmConfig`.
+    pub fn raydium_clmm_market_rate(
+        &mut self,
+        pool_address: String,
+        pool: &RaydiumClmmPoolState,
+        amm_config: &RaydiumAmmConfig,
+        mut token_pair: TokenPair,
+        transaction_signature: String,
+    ) -> MarketRate {
+        token_pair.base_mint = pool.token_mint_0.clone();
+        token_pair.quote_mint = pool.token_mint_1.clone();
+        token_pair.base_decimals = pool.mint_decimals_0;
+        token_pair.quote_decimals = pool.mint_decimals_1;
+
+        self.clmm_market_rate(
+            RAYDIUM_CL,
+            pool_address,
+            token_pair,
+            pool.sqrt_price_x64,
+            pool.liquidity,
+            amm_config.swap_fee(),
+            transaction_signature,
+        )
+    }
+
+    /// Build a `MarketRate` from a decoded DLMM pair. Token X is the base and
+    /// token Y the quote; decimals come from `token_pair`. The price is the
+    /// active bin's price, liquidity is the sum of the reserves in
+    /// `bin_arrays`, and the fee is the pair's current base + variable fee.
+    pub fn meteora_dlmm_market_rate<'a>(
+        &mut self,
+        pool_address: String,
+        pair: &LbPairState,
+        bin_arrays: impl IntoIterator<Item = &'a BinArrayState>,
+        mut token_pair: TokenPair,
+        transaction_signature: String,
+    ) -> MarketRate {
+        let start_time = Instant::now();
+
+        token_pair.base_mint = pair.token_x_mint.clone();
+        token_pair.quote_mint = pair.token_y_mint.clone();
+        let price = PriceX64::from_x64(pair.bin_price(pair.active_id).unwrap_or(0));
+
+        let (amount_x, amount_y) = bin_arrays.into_iter().fold((0u128, 0u128), |(x, y), bin_array| {
+            let (array_x, array_y) = bin_array.total_amounts();
+            (x + array_x, y + array_y)
+        });
+
+        let market_rate = MarketRate {
+            program_id: METEORA_DLMM.to_string(),
+            pool_address,
+            token_pair,
+            price,
+            swap_fee: pair.swap_fee(),
+            liquidity: pool_liquidity(price, saturating_u64(amount_x), saturating_u64(amount_y)),
+            timestamp: current_timestamp_ms(),
+            transaction_signature,
+        };
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("DLMM rate: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        market_rate
+    }
+
+    /// Build a `MarketRate` for a Pump.fun AMM pool from its vault balances.
+    /// The price is the constant-product spot price quote/base, and the fee is
+    /// the sum of the LP, protocol and creator fees in `fees`.
+    pub fn pump_amm_market_rate(
+        &mut self,
+        pool_address: String,
+        pool: &PumpAmmPool,
+        (base_reserve, quote_reserve): (u64, u64),
+        fees: PumpFees,
+        mut token_pair: TokenPair,
+        transaction_signature: String,
+    ) -> MarketRate {
+        let start_time = Instant::now();
+
+        token_pair.base_mint = pool.base_mint.clone();
+        token_pair.quote_mint = pool.quote_mint.clone();
+        let price = self.calculate_rate(base_reserve, quote_reserve);
+
+        let market_rate = MarketRate {
+            program_id: PUMP_FUN_AMM.to_string(),
+            pool_address,
+            token_pair,
+            price,
+            swap_fee: fees.swap_fee(),
+            liquidity: pool_liquidity(price, base_reserve, quote_reserve),
+            timestamp: current_timestamp_ms(),
+            transaction_signature,
+        };
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("Pump AMM rate: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        market_rate
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn clmm_market_rate(
+        &mut self,
+        program_id: &str,
+        pool_address: String,
+        token_pair: TokenPair,
+        sqrt_price_x64: u128,
+        liquidity: u128,
+        swap_fee: f64,
+        transaction_signature: String,
+    ) -> MarketRate {
+        let start_time = Instant::now();
+
+        let price = PriceX64::from_sqrt_price_x64(sqrt_price_x64);
+        let (base_reserve, quote_reserve) = virtual_reserves(liquidity, sqrt_price_x64);
+
+        let market_rate = MarketRate {
+            program_id: program_id.to_string(),
+            pool_address,
+            token_pair,
+            price,
+            swap_fee,
+            liquidity: pool_liquidity(price, base_reserve, quote_reserve),
+            timestamp: current_timestamp_ms(),
+            transaction_signature,
+        };
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("CLMM rate: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        market_rate
+    }
+
+    /// Quote swapping exactly `amount_in` raw units of the input token on
+    /// `pool`, with the pool's own rounding.
+    pub fn quote_exact_in(&mut self, pool: &QuotePool, amount_in: u64, side: Side) -> Result<SwapQuote, QuoteError> {
+        let start_time = Instant::now();
+        let swap_quote = quote::quote_exact_in(pool, amount_in, side, current_timestamp_ms() / 1000);
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("Exact-in quote: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        swap_quote
+    }
+
+    /// Quote receiving exactly `amount_out` raw units of the output token
+    /// from `pool`, with the pool's own rounding.
+    pub fn quote_exact_out(&mut self, pool: &QuotePool, amount_out: u64, side: Side) -> Result<SwapQuote, QuoteError> {
+        let start_time = Instant::now();
+        let swap_quote = quote::quote_exact_out(pool, amount_out, side, current_timestamp_ms() / 1000);
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("Exact-out quote: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        swap_quote
+    }
+
+    /// Best route for `amount_in` raw units of `input_mint` across `pools`.
+    pub fn best_route(
+        &mut self,
+        pools: &[RoutePool],
+        input_mint: &str,
+        output_mint: &str,
+        amount_in: u64,
+        config: &RouteConfig,
+    ) -> Result<Route, RouteError> {
+        let start_time = Instant::now();
+        let route = router::best_route(pools, input_mint, output_mint, amount_in, config, current_timestamp_ms() / 1000);
+
+        let elapsed = start_time.elapsed();
+        self.performance_log.push((
+            format!("Route search: {}μs", elapsed.as_micros()),
+            start_time,
+        ));
+
+        route
+    }
+
     pub fn get_performance_log(&self) -> &Vec<(String, Instant)> {
         &self.performance_log
     }
@@ -107,72 +461,355 @@ impl RateCalculator {
     pub fn log_rate_output(&mut self, market_rate: &MarketRate) {
         let start_time = Instant::now();
         self.performance_log.push((
-            format!("Rate output: {} -> {}", market_rate.token_pair.base_token, market_rate.rate),
+            format!("Rate output: {} -> {}", market_rate.token_pair.base_token, market_rate.rate()),
             start_time,
         ));
     }
 
+    /// Start timing a new update: drop the previous update's entries so the
+    /// checks below measure this one alone.
+    pub fn clear_performance_log(&mut self) {
+        self.performance_log.clear();
+    }
+
+    /// The first logged operation more than 1ms ago, with its age in ms.
+    pub fn slow_operation(&self) -> Option<(&str, u128)> {
+        self.performance_log.iter().find_map(|(log_entry, start_time)| {
+            let elapsed = start_time.elapsed().as_millis();
+            (elapsed > 1).then_some((log_entry.as_str(), elapsed))
+        })
+    }
+
     pub fn check_1ms_performance(&self) -> bool {
         // Check if any operation took more than 1ms
-        for (log_entry, start_time) in &self.performance_log {
-            let elapsed = start_time.elapsed();
-            if elapsed.as_millis() > 1 {
-                println!("Performance warning: {} took {}ms", log_entry, elapsed.as_millis());
-                return false;
+        match self.slow_operation() {
+            Some((log_entry, elapsed_ms)) => {
+                eprintln!("Performance warning: {} took {}ms", log_entry, elapsed_ms);
+                false
             }
+            None => true,
         }
-        true
     }
 }
 
+fn current_timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amm_types::{Denomination, SOL_MINT, USDC_MINT};
 
     #[test]
     fn test_rate_calculation() {
         let config = FilterConfig::default();
         let mut calculator = RateCalculator::new(config);
         
-        let rate = calculator.calculate_rate(100.0, 200.0);
-        assert_eq!(rate, 2.0);
+        let rate = calculator.calculate_rate(100, 200);
+        assert_eq!(rate, PriceX64::from_x64(2 << 64));
+        assert_eq!(calculator.calculate_rate(0, 200), PriceX64::ZERO);
+    }
+
+    #[test]
+    fn test_performance_log_is_per_update() {
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        calculator.log_transaction_detection("first");
+        std::thread::sleep(std::time::Duration::from_millis(3));
+        assert_eq!(calculator.slow_operation().map(|(entry, _)| entry), Some("Transaction detected: first"));
+
+        // A new update isn't charged for the time since the last one
+        calculator.clear_performance_log();
+        calculator.log_transaction_detection("second");
+        assert!(calculator.check_1ms_performance());
     }
 
     #[test]
     fn test_filters() {
         let config = FilterConfig::default();
-        let calculator = RateCalculator::new(config);
+        let mut calculator = RateCalculator::new(config);
         
         let token_pair = TokenPair {
             base_token: "SOL".to_string(),
             quote_token: "USDC".to_string(),
-            base_mint: "".to_string(),
-            quote_mint: "".to_string(),
+            base_mint: SOL_MINT.to_string(),
+            quote_mint: USDC_MINT.to_string(),
             base_decimals: 9,
             quote_decimals: 6,
         };
 
+        // 125 USDC/SOL, so 1.25M USDC of liquidity is 10,000 SOL
         let liquidity = PoolLiquidity {
-            base_liquidity: 5000.0,
-            quote_liquidity: 5000.0,
-            total_liquidity_usd: 10000.0,
-            volume_24h: 100.0,
-            volume_1h: 60.0,
+            base_reserve: 5_000_000_000_000,
+            quote_reserve: 625_000_000_000,
+            total_liquidity_quote: 1_250_000_000_000,
+            volume_24h: 0,
+            volume_1h: 0,
         };
 
         let market_rate = MarketRate {
             program_id: "test".to_string(),
             pool_address: "test".to_string(),
             token_pair,
-            rate: 1.0,
+            price: PriceX64::from_amounts(5_000_000_000_000, 625_000_000_000),
             swap_fee: 0.003,
             liquidity,
             timestamp: 0,
             transaction_signature: "test".to_string(),
         };
 
+        // Deep enough, but nothing traded yet
+        assert_eq!(calculator.apply_filters(&market_rate), (true, false));
+
+        // Someone buys SOL with 6,250 USDC (50 SOL) within the second
+        let mut market_rate = market_rate;
+        market_rate.liquidity.base_reserve -= 50_000_000_000;
+        market_rate.liquidity.quote_reserve += 6_250_000_000;
+        market_rate.timestamp = 500;
         let (meets_liquidity, meets_volume) = calculator.apply_filters(&market_rate);
         assert!(meets_liquidity);
         assert!(meets_volume);
+        assert_eq!(calculator.rolling_volume("test"), 6_250_000_000);
+
+        // Thresholds are compared in raw units, one below is a miss
+        market_rate.liquidity.total_liquidity_quote -= 1;
+        assert_eq!(calculator.apply_filters(&market_rate), (false, true));
+
+        // The trade leaves the 1s window
+        market_rate.timestamp = 1_600;
+        assert_eq!(calculator.apply_filters(&market_rate), (false, false));
+
+        // The same thresholds in USDC are much easier to meet
+        let mut calculator = RateCalculator::new(FilterConfig {
+            denomination: Denomination::Quote,
+            ..FilterConfig::default()
+        });
+        market_rate.liquidity.total_liquidity_quote = 10_000_000_000;
+        calculator.observe(&market_rate);
+        market_rate.liquidity.quote_reserve -= 50_000_000;
+        market_rate.liquidity.base_reserve += 1;
+        assert_eq!(calculator.apply_filters(&market_rate), (true, true));
+
+        // Without a reference price the pool can't be measured in SOL
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        market_rate.token_pair.base_mint = "unknown".to_string();
+        market_rate.token_pair.quote_mint = "unknown quote".to_string();
+        market_rate.liquidity.total_liquidity_quote = u128::MAX;
+        assert_eq!(calculator.apply_filters(&market_rate), (false, false));
+    }
+
+    #[test]
+    fn test_quote_denomination_uses_canonical_quote() {
+        // A pool listing USDC/SOL: 1.25M USDC against 10,000 SOL, with its
+        // liquidity valued in SOL
+        let market_rate = MarketRate {
+            program_id: "test".to_string(),
+            pool_address: "test".to_string(),
+            token_pair: TokenPair {
+                base_token: "USDC".to_string(),
+                quote_token: "SOL".to_string(),
+                base_mint: USDC_MINT.to_string(),
+                quote_mint: SOL_MINT.to_string(),
+                base_decimals: 6,
+                quote_decimals: 9,
+            },
+            price: PriceX64::from_amounts(1_250_000_000_000, 10_000_000_000_000),
+            swap_fee: 0.003,
+            liquidity: PoolLiquidity {
+                base_reserve: 1_250_000_000_000,
+                quote_reserve: 10_000_000_000_000,
+                total_liquidity_quote: 20_000_000_000_000,
+                ..PoolLiquidity::default()
+            },
+            timestamp: 0,
+            transaction_signature: "test".to_string(),
+        };
+        let config = |min_liquidity| FilterConfig {
+            min_liquidity,
+            min_volume: 0.0,
+            denomination: Denomination::Quote,
+            ..FilterConfig::default()
+        };
+
+        // Thresholds are in USDC, like every other SOL/USDC pool
+        let mut calculator = RateCalculator::new(config(2_500_000.0));
+        assert_eq!(calculator.apply_filters(&market_rate), (true, true));
+        let mut calculator = RateCalculator::new(config(2_500_001.0));
+        assert_eq!(calculator.apply_filters(&market_rate), (false, true));
+
+        // ...unless SOL is preferred as the quote
+        let mut calculator = RateCalculator::new(config(20_000.0))
+            .with_quote_preference(QuotePreference::new([SOL_MINT, USDC_MINT]));
+        assert_eq!(calculator.apply_filters(&market_rate), (true, true));
+    }
+
+    #[test]
+    fn test_price_update_carries_price_changes() {
+        let pool = PumpAmmPool::decode(include_bytes!("../fixtures/pump_amm_pool_sol_usdc.bin")).unwrap();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+        let token_pair = TokenPair {
+            base_token: "SOL".to_string(),
+            quote_token: "USDC".to_string(),
+            base_mint: "".to_string(),
+            quote_mint: "".to_string(),
+            base_decimals: 9,
+            quote_decimals: 6,
+        };
+        let mut rate_at = |timestamp: i64, quote_reserve: u64| {
+            let mut market_rate = calculator.pump_amm_market_rate(
+                "pool".to_string(),
+                &pool,
+                (1_000_000_000_000, quote_reserve),
+                PumpFees::default(),
+                token_pair.clone(),
+                "sig".to_string(),
+            );
+            market_rate.timestamp = timestamp;
+            market_rate
+        };
+        let day_ago = rate_at(0, 100_000_000_000);
+        let hour_ago = rate_at(23 * 3_600_000, 160_000_000_000);
+        let now = rate_at(24 * 3_600_000, 150_000_000_000);
+
+        calculator.price_update(day_ago);
+        calculator.price_update(hour_ago);
+        let update = calculator.price_update(now);
+        assert!((update.market_rate.rate() - 150.0).abs() < 1e-9);
+        assert!((update.price_change_1h.unwrap() - -6.25).abs() < 1e-9);
+        assert!((update.price_change_24h.unwrap() - 50.0).abs() < 1e-9);
+        // A millisecond ago the price was still the one from an hour ago
+        assert!((calculator.price_change("pool", 1).unwrap() - -6.25).abs() < 1e-9);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_whirlpool_market_rate_from_fixture() {
+        let data = include_bytes!("../fixtures/whirlpool_sol_usdc.bin");
+        let pool = WhirlpoolState::decode(data).unwrap();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+
+        let token_pair = TokenPair {
+            base_token: "SOL".to_string(),
+            quote_token: "USDC".to_string(),
+            base_mint: "".to_string(),
+            quote_mint: "".to_string(),
+            base_decimals: 9,
+            quote_decimals: 6,
+        };
+        let market_rate = calculator.whirlpool_market_rate("pool".to_string(), &pool, token_pair, "sig".to_string());
+
+        assert_eq!(market_rate.program_id, WHIRLPOOLS);
+        assert!((market_rate.rate() - 150.0).abs() < 1e-6);
+        assert!((market_rate.swap_fee - 0.0004).abs() < 1e-12);
+        assert_eq!(market_rate.token_pair.base_mint, pool.token_mint_a);
+        assert_eq!(market_rate.token_pair.quote_mint, pool.token_mint_b);
+
+        // L = sqrt(x * y) and y / x = price for the virtual reserves
+        let liquidity = &market_rate.liquidity;
+        let (base, quote) = (liquidity.base_reserve as u128, liquidity.quote_reserve as u128);
+        assert!((base * quote).isqrt().abs_diff(3_000_000_000_000) <= 1);
+        assert!(PriceX64::from_amounts(base, quote).x64().abs_diff(market_rate.price.x64()) < 1 << 32);
+        assert_eq!(liquidity.total_liquidity_quote, market_rate.price.quote_value(base) + quote);
+    }
+
+    #[test]
+    fn test_virtual_reserves() {
+        // sqrt_price of exactly 2.0: x = L / 2, y = L * 2
+        assert_eq!(virtual_reserves(1_000, 2 << 64), (500, 2_000));
+        assert_eq!(virtual_reserves(1_000, 0), (0, 0));
+        assert_eq!(virtual_reserves(u128::MAX, 1 << 64), (u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_raydium_clmm_market_rate_from_fixture() {
+        let pool = RaydiumClmmPoolState::decode(include_bytes!("../fixtures/raydium_clmm_sol_usdc.bin")).unwrap();
+        let config = RaydiumAmmConfig::decode(include_bytes!("../fixtures/raydium_amm_config_4.bin")).unwrap();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+
+        // Decimals in the account win over whatever the caller passes.
+        let token_pair = TokenPair {
+            base_token: "SOL".to_string(),
+            quote_token: "USDC".to_string(),
+            base_mint: "".to_string(),
+            quote_mint: "".to_string(),
+            base_decimals: 0,
+            quote_decimals: 0,
+        };
+        let market_rate = calculator.raydium_clmm_market_rate(
+            "pool".to_string(),
+            &pool,
+            &config,
+            token_pair,
+            "sig".to_string(),
+        );
+
+        assert_eq!(market_rate.program_id, RAYDIUM_CL);
+        assert!((market_rate.rate() - 150.25).abs() < 1e-6);
+        assert!((market_rate.swap_fee - 0.0004).abs() < 1e-12);
+        assert_eq!((market_rate.token_pair.base_decimals, market_rate.token_pair.quote_decimals), (9, 6));
+        assert_eq!(market_rate.token_pair.base_mint, pool.token_mint_0);
+        let liquidity = &market_rate.liquidity;
+        let virtual_price = PriceX64::from_amounts(liquidity.base_reserve as u128, liquidity.quote_reserve as u128);
+        assert!((virtual_price.to_f64(9, 6) - 150.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_meteora_dlmm_market_rate_from_fixtures() {
+        let pair = LbPairState::decode(include_bytes!("../fixtures/meteora_lb_pair_sol_usdc.bin")).unwrap();
+        let bin_array = BinArrayState::decode(include_bytes!("../fixtures/meteora_bin_array_sol_usdc.bin")).unwrap();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+
+        let token_pair = TokenPair {
+            base_token: "SOL".to_string(),
+            quote_token: "USDC".to_string(),
+            base_mint: "".to_string(),
+            quote_mint: "".to_string(),
+            base_decimals: 9,
+            quote_decimals: 6,
+        };
+        let market_rate =
+            calculator.meteora_dlmm_market_rate("pair".to_string(), &pair, [&bin_array], token_pair, "sig".to_string());
+
+        assert_eq!(market_rate.program_id, METEORA_DLMM);
+        assert!((market_rate.rate() - 150.160_263_5).abs() < 1e-6);
+        assert!((market_rate.swap_fee - 0.001016).abs() < 1e-12);
+        assert_eq!(market_rate.price.x64(), pair.bin_price(pair.active_id).unwrap());
+        assert_eq!(market_rate.liquidity.base_reserve, 65_000_000_000);
+        assert_eq!(market_rate.liquidity.quote_reserve, 3_750_000_000);
+        assert_eq!(market_rate.token_pair.base_mint, pair.token_x_mint);
+    }
+
+    #[test]
+    fn test_pump_amm_market_rate_from_vaults() {
+        let pool = PumpAmmPool::decode(include_bytes!("../fixtures/pump_amm_pool_sol_usdc.bin")).unwrap();
+        let mut calculator = RateCalculator::new(FilterConfig::default());
+
+        let token_pair = TokenPair {
+            base_token: "SOL".to_string(),
+            quote_token: "USDC".to_string(),
+            base_mint: "".to_string(),
+            quote_mint: "".to_string(),
+            base_decimals: 9,
+            quote_decimals: 6,
+        };
+        let fees = PumpFees { lp_fee_bps: 20, protocol_fee_bps: 5, creator_fee_bps: 5 };
+        let market_rate = calculator.pump_amm_market_rate(
+            "pool".to_string(),
+            &pool,
+            (1_000_000_000_000, 150_500_000_000),
+            fees,
+            token_pair,
+            "sig".to_string(),
+        );
+
+        assert_eq!(market_rate.program_id, PUMP_FUN_AMM);
+        assert!((market_rate.rate() - 150.5).abs() < 1e-9);
+        assert!((market_rate.swap_fee - 0.003).abs() < 1e-12);
+        assert_eq!(market_rate.liquidity.base_reserve, 1_000_000_000_000);
+        // 1,000 SOL at 150.5 plus 150,500 USDC, less a unit of rounding
+        assert_eq!(market_rate.liquidity.total_liquidity_quote, 300_999_999_999);
+        assert_eq!(market_rate.token_pair.base_mint, pool.base_mint);
+    }
+}
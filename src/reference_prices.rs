@@ -0,0 +1,176 @@
+//! Live prices of tokens in SOL and USD, taken from the rate stream itself.
+//!
+//! Every pool that pairs a token with SOL (or with USDC/USDT) is a reference
+//! price for that token; where several do, the deepest one, by its reserve of
+//! the reference token, sets the price, so a thin pool can't skew it. Tokens
+//! without a direct reference pool are converted through SOL's own USD price,
+//! so a USDC-quoted pool can be measured in SOL and vice versa.
+
+use std::collections::HashMap;
+use crate::amm_types::{Denomination, MarketRate, SOL_MINT, USDC_MINT, USDT_MINT};
+use crate::fixed::{PriceX64, ONE_X64};
+
+/// Stablecoins that count as one dollar. Both have 6 decimals, so a raw unit
+/// is a micro-dollar.
+fn is_usd_stable(mint: &str) -> bool {
+    mint == USDC_MINT || mint == USDT_MINT
+}
+
+fn is_sol(mint: &str) -> bool {
+    mint == SOL_MINT
+}
+
+/// A token's price from the deepest pool seen pairing it with a reference
+/// token.
+#[derive(Debug, Clone)]
+struct Reference {
+    pool_address: String,
+    /// The pool's reserve of the reference token, in raw units.
+    depth: u64,
+    price: PriceX64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReferencePrices {
+    /// Lamports per raw unit of each token.
+    sol: HashMap<String, Reference>,
+    /// Micro-dollars per raw unit of each token.
+    usd: HashMap<String, Reference>,
+}
+
+impl ReferencePrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `market_rate` as the reference price for its tokens, if it
+    /// pairs one of them with SOL or a USD stablecoin and is the deepest such
+    /// pool, or the one already used.
+    pub fn observe(&mut self, market_rate: &MarketRate) {
+        if market_rate.price.is_zero() {
+            return;
+        }
+        record(&mut self.sol, market_rate, is_sol);
+        record(&mut self.usd, market_rate, is_usd_stable);
+    }
+
+    /// Lamports per raw unit of `mint`.
+    pub fn sol_price(&self, mint: &str) -> Option<PriceX64> {
+        if is_sol(mint) {
+            return Some(PriceX64::from_x64(ONE_X64));
+        }
+        self.sol.get(mint).map(|reference| reference.price).or_else(|| {
+            let sol_usd = self.usd.get(SOL_MINT)?.price;
+            Some(self.usd_price_direct(mint)? * sol_usd.inverse())
+        })
+    }
+
+    /// Micro-dollars per raw unit of `mint`.
+    pub fn usd_price(&self, mint: &str) -> Option<PriceX64> {
+        self.usd_price_direct(mint).or_else(|| {
+            let sol_usd = self.usd.get(SOL_MINT)?.price;
+            Some(self.sol.get(mint)?.price * sol_usd)
+        })
+    }
+
+    fn usd_price_direct(&self, mint: &str) -> Option<PriceX64> {
+        if is_usd_stable(mint) {
+            return Some(PriceX64::from_x64(ONE_X64));
+        }
+        self.usd.get(mint).map(|reference| reference.price)
+    }
+
+    /// `raw_amount` of `mint` in raw units of `denomination` (lamports,
+    /// micro-dollars, or `mint` itself). `None` until a reference price for
+    /// `mint` has been seen.
+    pub fn convert(&self, mint: &str, raw_amount: u128, denomination: Denomination) -> Option<u128> {
+        let price = match denomination {
+            Denomination::Quote => return Some(raw_amount),
+            Denomination::Sol => self.sol_price(mint)?,
+            Denomination::Usd => self.usd_price(mint)?,
+        };
+        Some(price.quote_value(raw_amount))
+    }
+}
+
+/// Store the price of the non-reference side of `market_rate` in reference
+/// units, unless a deeper pool already prices it. Pools with a reference
+/// token on both sides (or neither) are skipped.
+fn record(prices: &mut HashMap<String, Reference>, market_rate: &MarketRate, is_reference: fn(&str) -> bool) {
+    let pair = &market_rate.token_pair;
+    let liquidity = &market_rate.liquidity;
+    let (mint, depth, price) = match (is_reference(&pair.base_mint), is_reference(&pair.quote_mint)) {
+        (false, true) => (&pair.base_mint, liquidity.quote_reserve, market_rate.price),
+        (true, false) => (&pair.quote_mint, liquidity.base_reserve, market_rate.price.inverse()),
+        _ => return,
+    };
+    if let Some(current) = prices.get(mint) {
+        // The current pool's own updates always apply, even as it drains
+        if current.pool_address != market_rate.pool_address && current.depth > depth {
+            return;
+        }
+    }
+    let reference = Reference {
+        pool_address: market_rate.pool_address.clone(),
+        depth,
+        price,
+    };
+    prices.insert(mint.clone(), reference);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, token_pair};
+
+    const BONK_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn market_rate(base: (&str, u8), quote: (&str, u8), base_reserve: u64, quote_reserve: u64) -> MarketRate {
+        MarketRate {
+            token_pair: token_pair(base, quote),
+            ..test_utils::market_rate("test", base_reserve, quote_reserve)
+        }
+    }
+
+    #[test]
+    fn test_converts_through_sol() {
+        let mut prices = ReferencePrices::new();
+        // 1 BONK = 0.00000016 SOL: 1e5 raw BONK per 160 lamports
+        prices.observe(&market_rate((BONK_MINT, 5), (SOL_MINT, 9), 100_000_000_000_000, 160_000_000_000));
+        // 0.0016 isn't exact in binary and conversions round down
+        assert_eq!(prices.convert(BONK_MINT, 100_000, Denomination::Sol), Some(159));
+        assert_eq!(prices.convert(BONK_MINT, 100_000, Denomination::Usd), None);
+        assert_eq!(prices.convert(BONK_MINT, 100_000, Denomination::Quote), Some(100_000));
+
+        // Listed as USDC/SOL: 1 SOL = 125 USDC
+        prices.observe(&market_rate((USDC_MINT, 6), (SOL_MINT, 9), 125_000_000, 1_000_000_000));
+        assert_eq!(prices.convert(SOL_MINT, 1_000_000_000, Denomination::Usd), Some(125_000_000));
+        assert_eq!(prices.convert(USDC_MINT, 125_000_000, Denomination::Sol), Some(1_000_000_000));
+        assert_eq!(prices.convert(USDT_MINT, 1_000_000, Denomination::Usd), Some(1_000_000));
+
+        // 1e9 BONK is 160 SOL, or 20,000 USD
+        let usd = prices.convert(BONK_MINT, 100_000_000_000_000, Denomination::Usd).unwrap();
+        assert!(usd.abs_diff(20_000_000_000) <= 1, "{}", usd);
+    }
+
+    #[test]
+    fn test_deepest_pool_sets_the_price() {
+        let mut prices = ReferencePrices::new();
+        let mut pool = |address: &str, usdc_reserve: u64, usdc_per_sol: u64| {
+            let mut rate = market_rate((SOL_MINT, 9), (USDC_MINT, 6), 1_000_000_000, usdc_per_sol * 1_000_000);
+            rate.pool_address = address.to_string();
+            rate.liquidity.quote_reserve = usdc_reserve;
+            prices.observe(&rate);
+            // Conversions round down
+            prices.convert(SOL_MINT, 1_000_000_000, Denomination::Usd).unwrap().div_ceil(1_000_000)
+        };
+
+        assert_eq!(pool("deep", 1_000_000_000_000, 150), 150);
+        // A thin pool trading far off doesn't move it
+        assert_eq!(pool("thin", 1_000_000, 300), 150);
+        // The deep pool's own updates do, even if it drains below the thin one
+        assert_eq!(pool("deep", 100, 151), 151);
+        // Then any deeper pool takes over
+        assert_eq!(pool("thin", 1_000_000, 300), 300);
+    }
+}
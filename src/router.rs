@@ -0,0 +1,436 @@
+//! Best-route search across every pool with quotable state.
+//!
+//! Candidate routes are the paths from the input mint to the output mint of
+//! up to `max_hops` swaps that don't visit a mint or pool twice, searched
+//! breadth first through mints the output can still be reached from, with
+//! the number of paths kept at each step capped. Each is quoted exactly with
+//! [`crate::quote`], leg by leg. The amount can also be
+//! split across up to `max_splits` routes that share no pool: it is handed
+//! out in `split_parts` equal parts, each to the route where it adds the
+//! most output. Whichever of the best single route and the best split pays
+//! out more wins.
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::quote::{quote_exact_in, FeeSide, QuotePool, Side};
+
+/// Upper bound on candidate paths, so a densely connected mint can't make
+/// one request quote thousands of routes.
+const MAX_CANDIDATE_PATHS: usize = 256;
+
+/// Upper bound on partial paths still being extended, which otherwise grow
+/// with the fan-out of every hop.
+const MAX_FRONTIER: usize = 4096;
+
+/// A tracked pool and the mints it trades.
+#[derive(Debug, Clone)]
+pub struct RoutePool {
+    pub address: String,
+    pub program_id: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub pool: QuotePool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteConfig {
+    /// Most swaps in one route.
+    pub max_hops: usize,
+    /// Most routes the amount is split across; 1 disables splitting.
+    pub max_splits: usize,
+    /// Number of equal parts the amount is split in.
+    pub split_parts: u64,
+}
+
+impl Default for RouteConfig {
+    fn default() -> Self {
+        Self {
+            max_hops: 3,
+            max_splits: 3,
+            split_parts: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteLeg {
+    pub pool_address: String,
+    pub program_id: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Fee charged, in raw units of `fee_mint`.
+    pub fee: u64,
+    pub fee_mint: String,
+    /// Price impact of this leg as a fraction; positive is worse than spot.
+    pub price_impact: f64,
+}
+
+/// One path the input takes, and how much of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteSplit {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub legs: Vec<RouteLeg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub splits: Vec<RouteSplit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteError {
+    ZeroAmount,
+    /// Input and output are the same mint.
+    SameMint,
+    /// No path connects the mints, or none could be quoted for the amount.
+    NoRoute,
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::ZeroAmount => write!(f, "amount in is zero"),
+            RouteError::SameMint => write!(f, "input and output mint are the same"),
+            RouteError::NoRoute => write!(f, "no route between the mints can fill the amount"),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// One swap of a candidate path: a pool and which way through it.
+#[derive(Debug, Clone, Copy)]
+struct Hop {
+    pool: usize,
+    side: Side,
+}
+
+/// Find the route paying out the most `output_mint` for `amount_in` raw
+/// units of `input_mint`. `now` is the unix time in seconds, for DLMM fees.
+/// `pools` may be owned or shared, e.g. a snapshot of `Arc`s.
+pub fn best_route<P: Borrow<RoutePool>>(
+    pools: &[P],
+    input_mint: &str,
+    output_mint: &str,
+    amount_in: u64,
+    config: &RouteConfig,
+    now: i64,
+) -> Result<Route, RouteError> {
+    if amount_in == 0 {
+        return Err(RouteError::ZeroAmount);
+    }
+    if input_mint == output_mint {
+        return Err(RouteError::SameMint);
+    }
+
+    let paths = candidate_paths(pools, input_mint, output_mint, config.max_hops.max(1));
+    let single = paths
+        .iter()
+        .filter_map(|path| quote_path(pools, path, amount_in, now).map(|split| vec![split]))
+        .max_by_key(|splits| splits[0].amount_out);
+    let split = if config.max_splits > 1 && paths.len() > 1 {
+        split_route(pools, &paths, amount_in, config, now)
+    } else {
+        None
+    };
+
+    let splits = match (single, split) {
+        (Some(single), Some(split)) => {
+            if total_out(&split) > total_out(&single) {
+                split
+            } else {
+                single
+            }
+        }
+        (Some(splits), None) | (None, Some(splits)) => splits,
+        (None, None) => return Err(RouteError::NoRoute),
+    };
+    Ok(Route {
+        input_mint: input_mint.to_string(),
+        output_mint: output_mint.to_string(),
+        amount_in,
+        amount_out: total_out(&splits),
+        splits,
+    })
+}
+
+fn total_out(splits: &[RouteSplit]) -> u64 {
+    splits.iter().map(|split| split.amount_out).sum()
+}
+
+/// Paths from `input_mint` to `output_mint`, shortest first. Partial paths
+/// are only extended to mints the output can still be reached from.
+fn candidate_paths<P: Borrow<RoutePool>>(
+    pools: &[P],
+    input_mint: &str,
+    output_mint: &str,
+    max_hops: usize,
+) -> Vec<Vec<Hop>> {
+    let mut edges: HashMap<&str, Vec<(Hop, &str)>> = HashMap::new();
+    for (index, pool) in pools.iter().enumerate() {
+        let pool = pool.borrow();
+        edges
+            .entry(&pool.base_mint)
+            .or_default()
+            .push((Hop { pool: index, side: Side::BaseToQuote }, &pool.quote_mint));
+        edges
+            .entry(&pool.quote_mint)
+            .or_default()
+            .push((Hop { pool: index, side: Side::QuoteToBase }, &pool.base_mint));
+    }
+
+    // Hops from each mint to the output; every pool trades both ways
+    let mut distances: HashMap<&str, usize> = HashMap::from([(output_mint, 0)]);
+    let mut queue = VecDeque::from([output_mint]);
+    while let Some(mint) = queue.pop_front() {
+        let distance = distances[mint] + 1;
+        if distance >= max_hops {
+            continue;
+        }
+        for &(_, next_mint) in edges.get(mint).into_iter().flatten() {
+            if !distances.contains_key(next_mint) {
+                distances.insert(next_mint, distance);
+                queue.push_back(next_mint);
+            }
+        }
+    }
+
+    // Breadth first, so the caps keep the shortest paths
+    let mut paths = Vec::new();
+    let mut frontier: Vec<(Vec<Hop>, Vec<&str>)> = vec![(Vec::new(), vec![input_mint])];
+    for hops in 1..=max_hops {
+        let mut next_frontier = Vec::new();
+        for (path, visited) in frontier {
+            let mint = visited[visited.len() - 1];
+            for &(hop, next_mint) in edges.get(mint).into_iter().flatten() {
+                if visited.contains(&next_mint) {
+                    continue;
+                }
+                if next_mint != output_mint
+                    && (next_frontier.len() == MAX_FRONTIER
+                        || distances.get(next_mint).is_none_or(|distance| hops + distance > max_hops))
+                {
+                    continue;
+                }
+                let mut path = path.clone();
+                path.push(hop);
+                if next_mint == output_mint {
+                    paths.push(path);
+                    if paths.len() == MAX_CANDIDATE_PATHS {
+                        return paths;
+                    }
+                } else {
+                    let mut visited = visited.clone();
+                    visited.push(next_mint);
+                    next_frontier.push((path, visited));
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    paths
+}
+
+/// Quote `amount_in` through every hop of `path`. `None` if any leg can't be
+/// quoted.
+fn quote_path<P: Borrow<RoutePool>>(pools: &[P], path: &[Hop], amount_in: u64, now: i64) -> Option<RouteSplit> {
+    let mut amount = amount_in;
+    let mut legs = Vec::with_capacity(path.len());
+    for hop in path {
+        let pool = pools[hop.pool].borrow();
+        let quote = quote_exact_in(&pool.pool, amount, hop.side, now).ok()?;
+        let (input_mint, output_mint) = match hop.side {
+            Side::BaseToQuote => (&pool.base_mint, &pool.quote_mint),
+            Side::QuoteToBase => (&pool.quote_mint, &pool.base_mint),
+        };
+        legs.push(RouteLeg {
+            pool_address: pool.address.clone(),
+            program_id: pool.program_id.clone(),
+            input_mint: input_mint.clone(),
+            output_mint: output_mint.clone(),
+            amount_in: quote.amount_in,
+            amount_out: quote.amount_out,
+            fee: quote.fee,
+            fee_mint: match quote.fee_side {
+                FeeSide::Input => input_mint.clone(),
+                FeeSide::Output => output_mint.clone(),
+            },
+            price_impact: quote.price_impact,
+        });
+        amount = quote.amount_out;
+    }
+    Some(RouteSplit {
+        amount_in,
+        amount_out: amount,
+        legs,
+    })
+}
+
+/// Hand out `amount_in` part by part to whichever pool-disjoint path adds
+/// the most output.
+fn split_route<P: Borrow<RoutePool>>(
+    pools: &[P],
+    paths: &[Vec<Hop>],
+    amount_in: u64,
+    config: &RouteConfig,
+    now: i64,
+) -> Option<Vec<RouteSplit>> {
+    let parts = config.split_parts.clamp(1, amount_in);
+    let part = amount_in / parts;
+    let mut allocated: Vec<Option<RouteSplit>> = vec![None; paths.len()];
+    let mut used_pools: HashSet<usize> = HashSet::new();
+    let mut used_paths = 0;
+
+    for index in 0..parts {
+        // The remainder goes with the last part
+        let amount = if index == parts - 1 { amount_in - part * (parts - 1) } else { part };
+        let mut best: Option<(usize, u64, RouteSplit)> = None;
+        for (path_index, path) in paths.iter().enumerate() {
+            let current = allocated[path_index].as_ref();
+            if current.is_none()
+                && (used_paths == config.max_splits || path.iter().any(|hop| used_pools.contains(&hop.pool)))
+            {
+                continue;
+            }
+            let current_in = current.map_or(0, |split| split.amount_in);
+            let current_out = current.map_or(0, |split| split.amount_out);
+            let Some(split) = quote_path(pools, path, current_in + amount, now) else {
+                continue;
+            };
+            let gain = split.amount_out.saturating_sub(current_out);
+            if best.as_ref().is_none_or(|(_, best_gain, _)| gain > *best_gain) {
+                best = Some((path_index, gain, split));
+            }
+        }
+
+        let (path_index, _, split) = best?;
+        if allocated[path_index].is_none() {
+            used_paths += 1;
+            used_pools.extend(paths[path_index].iter().map(|hop| hop.pool));
+        }
+        allocated[path_index] = Some(split);
+    }
+
+    let mut splits: Vec<RouteSplit> = allocated.into_iter().flatten().collect();
+    splits.sort_by_key(|split| std::cmp::Reverse(split.amount_in));
+    Some(splits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::pump_amm::PumpFees;
+    use crate::quote::Curve;
+
+    const FEES: PumpFees = PumpFees {
+        lp_fee_bps: 20,
+        protocol_fee_bps: 5,
+        creator_fee_bps: 5,
+    };
+
+    fn pool(address: &str, base_mint: &str, quote_mint: &str, base_reserve: u64, quote_reserve: u64) -> RoutePool {
+        RoutePool {
+            address: address.to_string(),
+            program_id: "pump".to_string(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            pool: QuotePool {
+                base_decimals: 9,
+                quote_decimals: 6,
+                curve: Curve::ConstantProduct {
+                    base_reserve,
+                    quote_reserve,
+                    fees: FEES,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_picks_best_multi_hop_route() {
+        let pools = vec![
+            // Direct SOL/USDC at 150
+            pool("direct", "sol", "usdc", 1_000_000_000_000, 150_000_000_000),
+            // SOL -> BONK -> USDC, listed both ways round, at 153
+            pool("sol_bonk", "bonk", "sol", 100_000_000_000_000_000, 1_000_000_000_000),
+            pool("bonk_usdc", "bonk", "usdc", 100_000_000_000_000_000, 153_000_000_000),
+        ];
+        let config = RouteConfig { max_splits: 1, ..RouteConfig::default() };
+        let route = best_route(&pools, "sol", "usdc", 1_000_000_000, &config, 0).unwrap();
+
+        assert_eq!(route.splits.len(), 1);
+        let legs = &route.splits[0].legs;
+        let hops: Vec<(&str, &str, &str)> = legs
+            .iter()
+            .map(|leg| (leg.pool_address.as_str(), leg.input_mint.as_str(), leg.output_mint.as_str()))
+            .collect();
+        assert_eq!(hops, [("sol_bonk", "sol", "bonk"), ("bonk_usdc", "bonk", "usdc")]);
+        assert_eq!(legs[0].amount_out, legs[1].amount_in);
+        assert_eq!(route.amount_out, legs[1].amount_out);
+        // Two 0.3% fees still beat the direct pool
+        assert!(route.amount_out > 151_500_000 && route.amount_out < 153_000_000);
+        assert_eq!((legs[0].fee_mint.as_str(), legs[1].fee_mint.as_str()), ("sol", "usdc"));
+        assert!(legs.iter().all(|leg| leg.price_impact > 0.0 && leg.price_impact < 0.002));
+
+        // One hop can only use the direct pool
+        let config = RouteConfig { max_hops: 1, ..config };
+        let route = best_route(&pools, "sol", "usdc", 1_000_000_000, &config, 0).unwrap();
+        assert_eq!(route.splits[0].legs[0].pool_address, "direct");
+    }
+
+    #[test]
+    fn test_splits_large_orders_across_pools() {
+        // Two identical shallow pools: halving the order halves the impact
+        let pools = vec![
+            pool("a", "sol", "usdc", 1_000_000_000_000, 150_000_000_000),
+            pool("b", "sol", "usdc", 1_000_000_000_000, 150_000_000_000),
+        ];
+        let amount_in = 100_000_000_000;
+        let single = best_route(&pools, "sol", "usdc", amount_in, &RouteConfig { max_splits: 1, ..RouteConfig::default() }, 0)
+            .unwrap();
+        let split = best_route(&pools, "sol", "usdc", amount_in, &RouteConfig::default(), 0).unwrap();
+
+        assert_eq!(single.splits.len(), 1);
+        assert_eq!(split.splits.len(), 2);
+        assert_eq!(split.splits.iter().map(|split| split.amount_in).sum::<u64>(), amount_in);
+        assert_eq!(split.splits[0].amount_in, amount_in / 2);
+        assert!(split.amount_out > single.amount_out);
+    }
+
+    #[test]
+    fn test_dead_ends_do_not_crowd_out_routes() {
+        // More mints hang off SOL than the frontier holds, none of which
+        // lead to USDC
+        let mut pools: Vec<RoutePool> = (0..MAX_FRONTIER + 100)
+            .map(|index| pool(&format!("dead{}", index), &format!("mint{}", index), "sol", 1_000_000, 1_000_000))
+            .collect();
+        pools.push(pool("sol_bonk", "bonk", "sol", 100_000_000_000_000_000, 1_000_000_000_000));
+        pools.push(pool("bonk_usdc", "bonk", "usdc", 100_000_000_000_000_000, 153_000_000_000));
+
+        let paths = candidate_paths(&pools, "sol", "usdc", 3);
+        assert_eq!(paths.len(), 1);
+        let config = RouteConfig { max_splits: 1, ..RouteConfig::default() };
+        let route = best_route(&pools, "sol", "usdc", 1_000_000_000, &config, 0).unwrap();
+        assert_eq!(route.splits[0].legs.len(), 2);
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let pools = vec![pool("a", "sol", "usdc", 1_000_000_000_000, 150_000_000_000)];
+        let config = RouteConfig::default();
+        assert_eq!(best_route(&pools, "sol", "usdc", 0, &config, 0), Err(RouteError::ZeroAmount));
+        assert_eq!(best_route(&pools, "sol", "sol", 1, &config, 0), Err(RouteError::SameMint));
+        assert_eq!(best_route(&pools, "sol", "bonk", 1_000, &config, 0), Err(RouteError::NoRoute));
+        // Too small to get anything out after fees
+        assert_eq!(best_route(&pools, "sol", "usdc", 1, &config, 0), Err(RouteError::NoRoute));
+    }
+}
@@ -0,0 +1,156 @@
+//! Just enough Solana JSON-RPC to read accounts once.
+//!
+//! A Geyser stream only sends an account when it changes, so accounts that
+//! rarely do, like the configs pool fees come from, are read over RPC at
+//! startup and fed through the same path as streamed ones.
+
+use base64::Engine;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use crate::client::ClientError;
+use crate::geyser::AccountUpdate;
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: RpcAccount,
+}
+
+#[derive(Deserialize)]
+struct RpcAccount {
+    lamports: u64,
+    owner: String,
+    /// Base64 data and its encoding.
+    data: (String, String),
+}
+
+pub struct RpcClient {
+    endpoint: String,
+    client: Client<HttpConnector>,
+}
+
+impl RpcClient {
+    /// `endpoint` is a plain `http://` URL, e.g. `http://127.0.0.1:8899`.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> Result<T, ClientError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.endpoint)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))?;
+        let response = self.client.request(request).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let response: RpcResponse<T> = serde_json::from_slice(&body)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(format!("{} failed: {}", method, error).into()),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(format!("{} returned no result", method).into()),
+        }
+    }
+
+    /// Every account owned by `program_id` whose data starts with
+    /// `discriminator`, as if streamed without a transaction.
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &str,
+        discriminator: &[u8],
+    ) -> Result<Vec<AccountUpdate>, ClientError> {
+        let filter = json!({ "memcmp": { "offset": 0, "bytes": bs58::encode(discriminator).into_string() } });
+        let params = json!([program_id, { "encoding": "base64", "filters": [filter] }]);
+        let accounts: Vec<KeyedAccount> = self.call("getProgramAccounts", params).await?;
+        accounts
+            .into_iter()
+            .map(|keyed| {
+                Ok(AccountUpdate {
+                    pubkey: keyed.pubkey,
+                    owner: keyed.account.owner,
+                    lamports: keyed.account.lamports,
+                    data: base64::engine::general_purpose::STANDARD.decode(&keyed.account.data.0)?,
+                    slot: 0,
+                    write_version: 0,
+                    txn_signature: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::PUMP_FUN_AMM;
+    use crate::decoders::pump_amm::GLOBAL_CONFIG_DISCRIMINATOR;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer one HTTP request with `body`, returning the request.
+    async fn serve_once(listener: TcpListener, body: String) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(headers_end) = text.find("\r\n\r\n") {
+                let length = text
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(str::to_string))
+                    .and_then(|length| length.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if request.len() >= headers_end + 4 + length {
+                    break;
+                }
+            }
+        }
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8(request).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_program_accounts_decodes_base64_data() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let data = base64::engine::general_purpose::STANDARD.encode([1u8, 2, 3]);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": [{
+                "pubkey": "config",
+                "account": { "lamports": 5, "owner": PUMP_FUN_AMM, "data": [data, "base64"], "executable": false }
+            }]
+        });
+        let server = tokio::spawn(serve_once(listener, body.to_string()));
+
+        let rpc = RpcClient::new(&endpoint);
+        let accounts = rpc.get_program_accounts(PUMP_FUN_AMM, &GLOBAL_CONFIG_DISCRIMINATOR).await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!((accounts[0].pubkey.as_str(), accounts[0].owner.as_str()), ("config", PUMP_FUN_AMM));
+        assert_eq!(accounts[0].data, vec![1, 2, 3]);
+
+        let request = server.await.unwrap();
+        assert!(request.contains("getProgramAccounts"));
+        assert!(request.contains(&bs58::encode(GLOBAL_CONFIG_DISCRIMINATOR).into_string()));
+    }
+}
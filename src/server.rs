@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use crate::amm_types::{Denomination, FilterConfig, MarketRate, PriceUpdate};
+use crate::proto::solana::amm;
+use crate::proto::solana::amm::amm_service_server::{AmmService, AmmServiceServer};
+use crate::fixed::to_ui_amount;
+use crate::price_history::PriceHistory;
+use crate::rate_calculator::RateCalculator;
+use crate::reference_prices::ReferencePrices;
+use crate::router::{best_route, Route, RouteConfig, RouteError, RoutePool};
+use crate::volume::{VolumeTracker, ONE_DAY_MS, ONE_HOUR_MS, ONE_MINUTE_MS, ONE_SECOND_MS};
+
+/// Most hops a `GetBestRoute` request may ask for.
+const MAX_ROUTE_HOPS: u32 = 4;
+
+/// Most routes a `GetBestRoute` request may split across.
+const MAX_ROUTE_SPLITS: u32 = 4;
+
+/// Volume timeframes the hub tracks, and so the only ones clients may filter
+/// on.
+pub const VOLUME_TIMEFRAMES_MS: [i64; 4] = [ONE_SECOND_MS, ONE_MINUTE_MS, ONE_HOUR_MS, ONE_DAY_MS];
+
+type ResponseStream<T> = Pin<Box<dyn tokio_stream::Stream<Item = Result<T, Status>> + Send>>;
+
+/// `rate` for the wire, with its liquidity also valued in USD through
+/// `prices`.
+fn market_rate_message(rate: &MarketRate, prices: &ReferencePrices) -> amm::MarketRate {
+    let pair = &rate.token_pair;
+    let total_liquidity_usd = prices
+        .convert(&pair.quote_mint, rate.liquidity.total_liquidity_quote, Denomination::Usd)
+        .map_or(0.0, |usd| to_ui_amount(usd, Denomination::Usd.decimals(pair.quote_decimals)));
+    amm::MarketRate {
+        program_id: rate.program_id.clone(),
+        pool_address: rate.pool_address.clone(),
+        token_pair: Some(amm::TokenPair {
+            base_token: pair.base_token.clone(),
+            quote_token: pair.quote_token.clone(),
+            base_mint: pair.base_mint.clone(),
+            quote_mint: pair.quote_mint.clone(),
+            base_decimals: pair.base_decimals as f64,
+            quote_decimals: pair.quote_decimals as f64,
+        }),
+        rate: rate.rate(),
+        swap_fee: rate.swap_fee,
+        liquidity: Some(amm::PoolLiquidity {
+            base_liquidity: pair.base_ui_amount(rate.liquidity.base_reserve as u128),
+            quote_liquidity: pair.quote_ui_amount(rate.liquidity.quote_reserve as u128),
+            total_liquidity_usd,
+            volume_24h: pair.quote_ui_amount(rate.liquidity.volume_24h),
+            volume_1h: pair.quote_ui_amount(rate.liquidity.volume_1h),
+            total_liquidity_quote: pair.quote_ui_amount(rate.liquidity.total_liquidity_quote),
+        }),
+        timestamp: rate.timestamp,
+        transaction_signature: rate.transaction_signature.clone(),
+    }
+}
+
+fn price_update_message(update: &PriceUpdate, prices: &ReferencePrices) -> amm::PriceUpdate {
+    amm::PriceUpdate {
+        market_rate: Some(market_rate_message(&update.market_rate, prices)),
+        price_change_24h: update.price_change_24h.unwrap_or(0.0),
+        price_change_1h: update.price_change_1h.unwrap_or(0.0),
+        meets_liquidity_filter: update.meets_liquidity_filter,
+        meets_volume_filter: update.meets_volume_filter,
+        has_price_change_24h: update.price_change_24h.is_some(),
+        has_price_change_1h: update.price_change_1h.is_some(),
+    }
+}
+
+impl From<&Route> for amm::RouteResponse {
+    fn from(route: &Route) -> Self {
+        Self {
+            input_mint: route.input_mint.clone(),
+            output_mint: route.output_mint.clone(),
+            amount_in: route.amount_in,
+            amount_out: route.amount_out,
+            splits: route
+                .splits
+                .iter()
+                .map(|split| amm::RouteSplit {
+                    amount_in: split.amount_in,
+                    amount_out: split.amount_out,
+                    legs: split
+                        .legs
+                        .iter()
+                        .map(|leg| amm::RouteLeg {
+                            pool_address: leg.pool_address.clone(),
+                            program_id: leg.program_id.clone(),
+                            input_mint: leg.input_mint.clone(),
+                            output_mint: leg.output_mint.clone(),
+                            amount_in: leg.amount_in,
+                            amount_out: leg.amount_out,
+                            fee: leg.fee,
+                            fee_mint: leg.fee_mint.clone(),
+                            price_impact: leg.price_impact,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<amm::FilterConfig> for FilterConfig {
+    fn from(config: amm::FilterConfig) -> Self {
+        Self {
+            min_liquidity: config.min_liquidity_sol,
+            min_volume: config.min_volume_sol,
+            volume_timeframe_ms: config.volume_timeframe_ms,
+            denomination: match config.denomination() {
+                amm::Denomination::Sol => Denomination::Sol,
+                amm::Denomination::Usd => Denomination::Usd,
+                amm::Denomination::Quote => Denomination::Quote,
+            },
+        }
+    }
+}
+
+/// Fan-out hub that re-publishes processed market rates to downstream clients.
+///
+/// Upstream rates are fed in through [`AmmServer::publish`]; every downstream
+/// subscriber gets its own `RateCalculator` built from the `FilterConfig` it
+/// sent, so each client only sees the pools that pass its own thresholds.
+/// Price changes are computed once, as rates are published, from the hub's
+/// own price history, so every client gets the same changes however
+/// recently it connected.
+///
+/// Snapshots and streams are both filtered on volume the hub has been
+/// tracking since it started, so a client sees the same pools in either
+/// however recently it connected. Volume is tracked for the common timeframes in [`VOLUME_TIMEFRAMES_MS`] (1s, 1m,
+/// 1h, 24h). Requests for any other timeframe are rejected, so clients can't
+/// make the hub track an unbounded number of them.
+///
+/// Routes are searched over the pool state fed in through
+/// [`AmmServer::publish_pool`]; a hub that only sees rates has none.
+#[derive(Clone)]
+pub struct AmmServer {
+    latest: Arc<RwLock<HashMap<String, MarketRate>>>,
+    /// Shared so route searches can snapshot them without copying.
+    route_pools: Arc<RwLock<HashMap<String, Arc<RoutePool>>>>,
+    /// Volume trackers by timeframe in ms.
+    volume: Arc<Mutex<HashMap<i64, VolumeTracker>>>,
+    history: Arc<Mutex<PriceHistory>>,
+    /// Published rates with their price changes, not yet filtered.
+    updates: broadcast::Sender<PriceUpdate>,
+}
+
+impl AmmServer {
+    pub fn new(capacity: usize) -> Self {
+        let (updates, _) = broadcast::channel(capacity);
+        let volume = VOLUME_TIMEFRAMES_MS
+            .into_iter()
+            .map(|timeframe_ms| (timeframe_ms, VolumeTracker::new(timeframe_ms)))
+            .collect();
+        Self {
+            latest: Arc::new(RwLock::new(HashMap::new())),
+            route_pools: Arc::new(RwLock::new(HashMap::new())),
+            volume: Arc::new(Mutex::new(volume)),
+            history: Arc::new(Mutex::new(PriceHistory::default())),
+            updates,
+        }
+    }
+
+    /// Record the latest rate for its pool and forward it, with its price
+    /// changes, to all subscribers.
+    pub fn publish(&self, market_rate: MarketRate) {
+        for tracker in self.volume.lock().unwrap().values_mut() {
+            tracker.observe(&market_rate);
+        }
+        let (price_change_1h, price_change_24h) = {
+            let mut history = self.history.lock().unwrap();
+            let pool_address = &market_rate.pool_address;
+            history.record(pool_address, market_rate.timestamp, market_rate.price);
+            (history.change(pool_address, ONE_HOUR_MS), history.change(pool_address, ONE_DAY_MS))
+        };
+        self.latest
+            .write()
+            .unwrap()
+            .insert(market_rate.pool_address.clone(), market_rate.clone());
+        // Subscribers fill in volume and filter results
+        let update = PriceUpdate {
+            market_rate,
+            price_change_24h,
+            price_change_1h,
+            volume: 0,
+            meets_liquidity_filter: false,
+            meets_volume_filter: false,
+        };
+        // No subscribers is not an error for a hub.
+        let _ = self.updates.send(update);
+    }
+
+    /// Record the latest quotable state of a pool for `GetBestRoute`.
+    pub fn publish_pool(&self, pool: RoutePool) {
+        self.route_pools.write().unwrap().insert(pool.address.clone(), Arc::new(pool));
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.updates.receiver_count()
+    }
+
+    pub fn into_service(self) -> AmmServiceServer<Self> {
+        AmmServiceServer::new(self)
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        eprintln!("Serving AmmService on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(self.into_service())
+            .serve(addr)
+            .await
+    }
+}
+
+/// The filter a client sent, if its volume timeframe is one the hub tracks.
+fn filter_config(config: amm::FilterConfig) -> Result<FilterConfig, String> {
+    let filter_config = FilterConfig::from(config);
+    if !VOLUME_TIMEFRAMES_MS.contains(&filter_config.volume_timeframe_ms) {
+        return Err(format!(
+            "Unsupported volume timeframe {}ms (expected one of {:?})",
+            filter_config.volume_timeframe_ms, VOLUME_TIMEFRAMES_MS
+        ));
+    }
+    Ok(filter_config)
+}
+
+#[tonic::async_trait]
+impl AmmService for AmmServer {
+    type SubscribePriceUpdatesStream = ResponseStream<amm::PriceUpdate>;
+    type GetMarketRatesStream = ResponseStream<amm::MarketRate>;
+
+    async fn subscribe_price_updates(
+        &self,
+        request: Request<amm::FilterConfig>,
+    ) -> Result<Response<Self::SubscribePriceUpdatesStream>, Status> {
+        let filter_config = filter_config(request.into_inner()).map_err(Status::invalid_argument)?;
+        let timeframe_ms = filter_config.volume_timeframe_ms;
+        let mut calculator = RateCalculator::new(filter_config);
+        let volume = self.volume.clone();
+        let mut updates = self.updates.subscribe();
+        let (tx, rx) = mpsc::channel(256);
+
+        tokio::spawn(async move {
+            loop {
+                let mut update = match updates.recv().await {
+                    Ok(update) => update,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        eprintln!("WARNING: Slow subscriber skipped {} updates", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                let (meets_liquidity, meets_volume, pool_volume) = {
+                    let mut volume = volume.lock().unwrap();
+                    let volume = volume.get_mut(&timeframe_ms).expect("tracked timeframe");
+                    let (meets_liquidity, meets_volume) = calculator.apply_filters_with_volume(&update.market_rate, volume);
+                    (meets_liquidity, meets_volume, volume.volume(&update.market_rate.pool_address))
+                };
+                if !(meets_liquidity && meets_volume) {
+                    continue;
+                }
+                update.volume = pool_volume;
+                update.meets_liquidity_filter = true;
+                update.meets_volume_filter = true;
+
+                let message = price_update_message(&update, calculator.reference_prices());
+                if tx.send(Ok(message)).await.is_err() {
+                    // Downstream client went away
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn get_market_rates(
+        &self,
+        request: Request<amm::FilterConfig>,
+    ) -> Result<Response<Self::GetMarketRatesStream>, Status> {
+        let filter_config = filter_config(request.into_inner()).map_err(Status::invalid_argument)?;
+        let timeframe_ms = filter_config.volume_timeframe_ms;
+        let mut calculator = RateCalculator::new(filter_config);
+        let mut volume = self.volume.lock().unwrap();
+        let volume = volume.get_mut(&timeframe_ms).expect("tracked timeframe");
+        let latest = self.latest.read().unwrap();
+        // Learn reference prices from the whole snapshot before filtering any of it
+        for market_rate in latest.values() {
+            calculator.observe(market_rate);
+        }
+        let passing: Vec<&MarketRate> = latest
+            .values()
+            .filter(|market_rate| {
+                let (meets_liquidity, meets_volume) = calculator.apply_filters_with_volume(market_rate, volume);
+                meets_liquidity && meets_volume
+            })
+            .collect();
+        let snapshot: Vec<amm::MarketRate> = passing
+            .into_iter()
+            .map(|market_rate| market_rate_message(market_rate, calculator.reference_prices()))
+            .collect();
+
+        Ok(Response::new(Box::pin(tokio_stream::iter(snapshot.into_iter().map(Ok)))))
+    }
+
+    async fn ping(&self, _request: Request<amm::PingRequest>) -> Result<Response<amm::PingResponse>, Status> {
+        Ok(Response::new(amm::PingResponse {}))
+    }
+
+    async fn get_best_route(&self, request: Request<amm::RouteRequest>) -> Result<Response<amm::RouteResponse>, Status> {
+        let request = request.into_inner();
+        let defaults = RouteConfig::default();
+        let config = RouteConfig {
+            max_hops: match request.max_hops {
+                0 => defaults.max_hops,
+                hops => hops.min(MAX_ROUTE_HOPS) as usize,
+            },
+            max_splits: match request.max_splits {
+                0 => defaults.max_splits,
+                splits => splits.min(MAX_ROUTE_SPLITS) as usize,
+            },
+            ..defaults
+        };
+        let pools: Vec<Arc<RoutePool>> = self.route_pools.read().unwrap().values().cloned().collect();
+        let now = chrono::Utc::now().timestamp();
+
+        // Quoting every candidate is CPU-bound; keep it off the runtime
+        let route = tokio::task::spawn_blocking(move || {
+            best_route(&pools, &request.input_mint, &request.output_mint, request.amount_in, &config, now)
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+        match route {
+            Ok(route) => Ok(Response::new((&route).into())),
+            Err(e @ (RouteError::ZeroAmount | RouteError::SameMint)) => Err(Status::invalid_argument(e.to_string())),
+            Err(e @ RouteError::NoRoute) => Err(Status::not_found(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::{AmmProgram, SOL_MINT, USDC_MINT};
+    use crate::client::GrpcClient;
+    use crate::decoders::pump_amm::PumpFees;
+    use crate::quote::{Curve, QuotePool};
+    use crate::fixed::{from_ui_amount, PriceX64};
+    use crate::test_utils;
+    use std::time::Duration;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    /// A SOL/USDC pool at 150 USDC/SOL, with liquidity in USDC.
+    fn market_rate(pool_address: &str, total_liquidity_usdc: f64) -> MarketRate {
+        let mut market_rate = test_utils::market_rate(pool_address, 1_000_000_000_000_000, 150_000_000_000_000);
+        market_rate.liquidity.total_liquidity_quote = from_ui_amount(total_liquidity_usdc, 6);
+        market_rate
+    }
+
+    /// `market_rate` after someone buys SOL with `usdc`.
+    fn after_buy(market_rate: &MarketRate, usdc: f64) -> MarketRate {
+        let mut market_rate = market_rate.clone();
+        market_rate.liquidity.quote_reserve += from_ui_amount(usdc, 6) as u64;
+        market_rate.liquidity.base_reserve -= from_ui_amount(usdc / 150.0, 9) as u64;
+        market_rate.price = PriceX64::from_amounts(
+            market_rate.liquidity.base_reserve as u128,
+            market_rate.liquidity.quote_reserve as u128,
+        );
+        market_rate
+    }
+
+    async fn spawn_server(server: AmmServer) -> AmmProgram {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(server.into_service())
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        AmmProgram {
+            id: "test".to_string(),
+            name: "Hub".to_string(),
+            endpoint: format!("http://{}", addr),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_get_their_own_filters() {
+        let server = AmmServer::new(16);
+        let program = spawn_server(server.clone()).await;
+
+        let mut strict = GrpcClient::new(program.clone()).await.unwrap();
+        let mut strict_stream = strict.subscribe_price_updates(FilterConfig::default()).await.unwrap();
+        let mut loose = GrpcClient::new(program).await.unwrap();
+        let loose_filter = FilterConfig {
+            min_liquidity: 0.0,
+            min_volume: 0.0,
+            volume_timeframe_ms: 1_000,
+            denomination: Denomination::Sol,
+        };
+        let mut loose_stream = loose.subscribe_price_updates(loose_filter).await.unwrap();
+
+        while server.subscriber_count() < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        server.publish(market_rate("shallow", 100.0));
+        // 33,333 SOL of liquidity, but no volume until someone trades 500 SOL
+        let deep = market_rate("deep", 5_000_000.0);
+        server.publish(deep.clone());
+        server.publish(after_buy(&deep, 75_000.0));
+
+        // Volume is the hub's as the subscriber reads it, which by then
+        // includes the trade, so both deep rates pass
+        let first = strict_stream.message().await.unwrap().unwrap();
+        let first_rate = first.market_rate.unwrap();
+        assert_eq!(first_rate.pool_address, "deep");
+        assert_eq!(first_rate.liquidity.unwrap().quote_liquidity, 150_000_000.0);
+        assert!(first.meets_liquidity_filter && first.meets_volume_filter);
+        // The pool is younger than an hour, so it has no change yet
+        assert!(!first.has_price_change_1h && !first.has_price_change_24h);
+        let second = strict_stream.message().await.unwrap().unwrap();
+        assert_eq!(second.market_rate.unwrap().liquidity.unwrap().quote_liquidity, 150_075_000.0);
+
+        let first = loose_stream.message().await.unwrap().unwrap();
+        let second = loose_stream.message().await.unwrap().unwrap();
+        assert_eq!(first.market_rate.unwrap().pool_address, "shallow");
+        assert_eq!(second.market_rate.unwrap().pool_address, "deep");
+    }
+
+    #[tokio::test]
+    async fn test_price_changes_come_from_hub_history() {
+        let server = AmmServer::new(16);
+        // Published before anyone subscribed
+        server.publish(market_rate("deep", 5_000_000.0));
+        let program = spawn_server(server.clone()).await;
+
+        let mut client = GrpcClient::new(program).await.unwrap();
+        let loose_filter = FilterConfig { min_liquidity: 0.0, min_volume: 0.0, ..FilterConfig::default() };
+        let mut stream = client.subscribe_price_updates(loose_filter).await.unwrap();
+        while server.subscriber_count() < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let mut later = after_buy(&market_rate("deep", 5_000_000.0), 75_000.0);
+        later.timestamp = ONE_HOUR_MS;
+        server.publish(later);
+
+        let update = stream.message().await.unwrap().unwrap();
+        assert!(update.has_price_change_1h && !update.has_price_change_24h);
+        assert!((update.price_change_1h - 0.1).abs() < 1e-3, "{}", update.price_change_1h);
+    }
+
+    #[tokio::test]
+    async fn test_late_subscribers_see_volume_traded_before_they_connected() {
+        let server = AmmServer::new(16);
+        // 500 SOL traded before anyone subscribed
+        let deep = after_buy(&market_rate("deep", 5_000_000.0), 75_000.0);
+        server.publish(market_rate("deep", 5_000_000.0));
+        server.publish(deep.clone());
+        let program = spawn_server(server.clone()).await;
+
+        let mut client = GrpcClient::new(program).await.unwrap();
+        let mut stream = client.subscribe_price_updates(FilterConfig::default()).await.unwrap();
+        while server.subscriber_count() < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // No trade of its own, but still within the hub's volume window
+        let quiet = MarketRate { timestamp: 500, ..deep };
+        server.publish(quiet);
+
+        let update = stream.message().await.unwrap().unwrap();
+        assert_eq!(update.market_rate.unwrap().pool_address, "deep");
+        assert!(update.meets_liquidity_filter && update.meets_volume_filter);
+    }
+
+    #[tokio::test]
+    async fn test_get_market_rates_returns_filtered_snapshot() {
+        let server = AmmServer::new(16);
+        server.publish(market_rate("shallow", 100.0));
+        server.publish(market_rate("deep", 5_000_000.0));
+        server.publish(after_buy(&market_rate("deep", 6_000_000.0), 15_000.0));
+        let program = spawn_server(server).await;
+
+        let mut client = GrpcClient::new(program.clone()).await.unwrap();
+        client.ping().await.unwrap();
+
+        let request = tonic::Request::new(amm::FilterConfig {
+            min_liquidity_sol: 10_000.0,
+            min_volume_sol: 50.0,
+            volume_timeframe_ms: 1_000,
+            denomination: amm::Denomination::Sol as i32,
+        });
+        let mut raw = amm::amm_service_client::AmmServiceClient::connect(program.endpoint)
+            .await
+            .unwrap();
+        let mut stream = raw.get_market_rates(request).await.unwrap().into_inner();
+
+        let mut pools = Vec::new();
+        while let Some(rate) = stream.message().await.unwrap() {
+            pools.push((rate.pool_address, rate.liquidity.unwrap().total_liquidity_usd));
+        }
+        assert_eq!(pools, vec![("deep".to_string(), 6_000_000.0)]);
+
+        // Untracked timeframes are refused rather than tracked from now on
+        let request = tonic::Request::new(amm::FilterConfig { volume_timeframe_ms: 1_234, ..Default::default() });
+        let status = raw.get_market_rates(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_liquidity_is_labeled_with_its_unit() {
+        let mut prices = ReferencePrices::new();
+        prices.observe(&market_rate("sol_usdc", 0.0));
+        // A BONK/SOL pool with 100 SOL of liquidity
+        let mut bonk_sol = test_utils::market_rate("bonk_sol", 1_000_000_000, 1_000_000_000);
+        bonk_sol.token_pair = test_utils::token_pair(("bonk", 5), (SOL_MINT, 9));
+        bonk_sol.liquidity.total_liquidity_quote = 100_000_000_000;
+
+        let liquidity = market_rate_message(&bonk_sol, &prices).liquidity.unwrap();
+        assert_eq!(liquidity.total_liquidity_quote, 100.0);
+        assert!((liquidity.total_liquidity_usd - 15_000.0).abs() < 1e-3, "{}", liquidity.total_liquidity_usd);
+        // Without a USD price for SOL there's no USD value
+        let liquidity = market_rate_message(&bonk_sol, &ReferencePrices::new()).liquidity.unwrap();
+        assert_eq!(liquidity.total_liquidity_usd, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_best_route_searches_published_pools() {
+        let server = AmmServer::new(16);
+        let fees = PumpFees {
+            lp_fee_bps: 20,
+            protocol_fee_bps: 5,
+            creator_fee_bps: 5,
+        };
+        for (address, quote_reserve) in [("cheap", 149_000_000_000), ("dear", 151_000_000_000)] {
+            server.publish_pool(RoutePool {
+                address: address.to_string(),
+                program_id: "pump".to_string(),
+                base_mint: SOL_MINT.to_string(),
+                quote_mint: USDC_MINT.to_string(),
+                pool: QuotePool {
+                    base_decimals: 9,
+                    quote_decimals: 6,
+                    curve: Curve::ConstantProduct { base_reserve: 1_000_000_000_000, quote_reserve, fees },
+                },
+            });
+        }
+        let program = spawn_server(server).await;
+        let mut raw = amm::amm_service_client::AmmServiceClient::connect(program.endpoint)
+            .await
+            .unwrap();
+
+        let request = amm::RouteRequest {
+            input_mint: SOL_MINT.to_string(),
+            output_mint: USDC_MINT.to_string(),
+            amount_in: 1_000_000_000,
+            max_hops: 0,
+            max_splits: 1,
+        };
+        let route = raw.get_best_route(request.clone()).await.unwrap().into_inner();
+        assert_eq!(route.splits.len(), 1);
+        let leg = &route.splits[0].legs[0];
+        assert_eq!((leg.pool_address.as_str(), leg.fee_mint.as_str()), ("dear", USDC_MINT));
+        assert_eq!(leg.amount_out, route.amount_out);
+
+        let unknown = amm::RouteRequest { output_mint: "unknown".to_string(), ..request.clone() };
+        assert_eq!(raw.get_best_route(unknown).await.unwrap_err().code(), tonic::Code::NotFound);
+        let empty = amm::RouteRequest { amount_in: 0, ..request };
+        assert_eq!(raw.get_best_route(empty).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+}
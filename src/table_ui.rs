@@ -1,50 +1,1278 @@
-use crate::amm_types::MarketRate;
-use std::collections::VecDeque;
+//! The rate table: one row per pool with its latest update.
+//!
+//! [`TableUI`] holds the rows and the view settings (sort column, filter,
+//! pause, selection) and draws them either full-screen with ratatui, through
+//! [`Tui`], or as a plain text table for non-interactive output. Each row
+//! keeps its recent rates and liquidity for the trend columns, and the
+//! selected pool's candles can be charted in a detail pane.
+//!
+//! The binary shares one [`TableUI`] between its ingest loop and
+//! [`run_display`], which redraws it at a fixed frame rate on its own task.
 
+use crate::amm_types::{get_amm_programs, short_address, AmmProgram, PriceUpdate, QuotePreference};
+use crate::candles::{Candle, SeriesKey, SharedCandleAggregator};
+use crate::client::{SubscriptionEvent, SubscriptionState};
+use crate::fixed::PriceX64;
+use crate::volume::ONE_MINUTE_MS;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{Frame, Terminal};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{Stdout, Write};
+#[cfg(unix)]
+use std::io::{BufRead, BufReader};
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+
+/// Frames drawn per second unless configured otherwise.
+pub const DEFAULT_FRAME_RATE: u32 = 10;
+
+/// The table shared by the ingest loop, which adds updates to it, and
+/// [`run_display`], which draws it.
+pub type SharedTableUI = Arc<Mutex<TableUI>>;
+
+/// Messages kept for the message pane.
+const MAX_MESSAGES: usize = 5;
+
+/// Rates kept per pool for the sparkline, by default.
+const SPARKLINE_LEN: usize = 16;
+
+/// Closed candles to keep per pool for the detail pane.
+pub const DETAIL_CANDLES: usize = 120;
+
+/// Height of the detail pane, borders included.
+const DETAIL_HEIGHT: u16 = 14;
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Direction of a pool's last rate change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tick {
+    Up,
+    Down,
+    Unchanged,
+}
+
+/// Latest state of one pool.
+#[derive(Debug, Clone)]
+pub struct PoolRow {
+    pub update: PriceUpdate,
+    pub tick: Tick,
+    /// Updates seen for the pool.
+    pub updates: u64,
+    /// Recent prices and total liquidity (raw quote units), oldest first.
+    pub history: VecDeque<(PriceX64, u128)>,
+}
+
+impl PoolRow {
+    fn pair(&self) -> String {
+        let token_pair = &self.update.market_rate.token_pair;
+        format!("{}/{}", token_pair.base_token, token_pair.quote_token)
+    }
+
+    fn rates(&self) -> impl Iterator<Item = f64> + '_ {
+        let token_pair = &self.update.market_rate.token_pair;
+        self.history
+            .iter()
+            .map(|(price, _)| price.to_f64(token_pair.base_decimals, token_pair.quote_decimals))
+    }
+
+    /// Percentage change of the rate over the kept history.
+    pub fn rate_trend(&self) -> f64 {
+        percent_change(self.rates().next(), self.rates().last())
+    }
+
+    /// Percentage change of total liquidity over the kept history.
+    pub fn liquidity_trend(&self) -> f64 {
+        let liquidity = |entry: Option<&(PriceX64, u128)>| entry.map(|(_, liquidity)| *liquidity as f64);
+        percent_change(liquidity(self.history.front()), liquidity(self.history.back()))
+    }
+
+    /// The kept rates as block characters, lowest to highest.
+    pub fn sparkline(&self) -> String {
+        let (low, high) = self
+            .rates()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), rate| (low.min(rate), high.max(rate)));
+        let top = (SPARK_LEVELS.len() - 1) as f64;
+        self.rates()
+            .map(|rate| {
+                let level = if high > low { ((rate - low) / (high - low) * top).round() as usize } else { 0 };
+                SPARK_LEVELS[level.min(SPARK_LEVELS.len() - 1)]
+            })
+            .collect()
+    }
+}
+
+fn percent_change(first: Option<f64>, last: Option<f64>) -> f64 {
+    match (first, last) {
+        (Some(first), Some(last)) if first > 0.0 => (last - first) / first * 100.0,
+        _ => 0.0,
+    }
+}
+
+/// Columns of the table. Any of them can be shown, in any order, and
+/// sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Program,
+    Pool,
+    Pair,
+    Rate,
+    /// Sparkline of the recent rates.
+    RateTrend,
+    Change1h,
+    Change24h,
+    SwapFee,
+    Liquidity,
+    /// Direction and size of the recent liquidity change.
+    LiquidityTrend,
+    Volume,
+    Updated,
+}
+
+impl Column {
+    pub const ALL: [Column; 12] = [
+        Column::Program,
+        Column::Pool,
+        Column::Pair,
+        Column::Rate,
+        Column::RateTrend,
+        Column::Change1h,
+        Column::Change24h,
+        Column::SwapFee,
+        Column::Liquidity,
+        Column::LiquidityTrend,
+        Column::Volume,
+        Column::Updated,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Program => "Program",
+            Column::Pool => "Pool",
+            Column::Pair => "Base/Quote",
+            Column::Rate => "Rate",
+            Column::RateTrend => "Trend",
+            Column::Change1h => "1h",
+            Column::Change24h => "24h",
+            Column::SwapFee => "Swap Fee",
+            Column::Liquidity => "Liquidity",
+            Column::LiquidityTrend => "Liq Trend",
+            Column::Volume => "Volume",
+            Column::Updated => "Timestamp",
+        }
+    }
+
+    /// Name of the column in `--columns` lists.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Program => "program",
+            Column::Pool => "pool",
+            Column::Pair => "pair",
+            Column::Rate => "rate",
+            Column::RateTrend => "spark",
+            Column::Change1h => "1h",
+            Column::Change24h => "24h",
+            Column::SwapFee => "fee",
+            Column::Liquidity => "liquidity",
+            Column::LiquidityTrend => "liq_trend",
+            Column::Volume => "volume",
+            Column::Updated => "time",
+        }
+    }
+
+    pub fn default_width(self) -> u16 {
+        match self {
+            Column::Program => 14,
+            Column::Pool => 10,
+            Column::Pair => 15,
+            Column::Rate | Column::RateTrend => 16,
+            Column::LiquidityTrend => 10,
+            Column::Change1h | Column::Change24h | Column::SwapFee | Column::Updated => 9,
+            Column::Liquidity | Column::Volume => 18,
+        }
+    }
+
+    /// Parse a column list like `program,pair:12,rate`, with optional widths.
+    pub fn parse_list(list: &str) -> Result<Vec<(Column, u16)>, String> {
+        list.split(',')
+            .map(|entry| {
+                let (name, width) = match entry.split_once(':') {
+                    Some((name, width)) => (name, Some(width)),
+                    None => (entry, None),
+                };
+                let column = Column::ALL
+                    .into_iter()
+                    .find(|column| column.name() == name)
+                    .ok_or_else(|| format!("Unknown column: {}", name))?;
+                let width = match width {
+                    Some(width) => width
+                        .parse()
+                        .ok()
+                        .filter(|width| *width > 0)
+                        .ok_or_else(|| format!("Invalid width for column {}: {}", name, width))?,
+                    None => column.default_width(),
+                };
+                Ok((column, width))
+            })
+            .collect()
+    }
+
+    fn compare(self, a: &PoolRow, b: &PoolRow) -> Ordering {
+        let (rate_a, rate_b) = (&a.update.market_rate, &b.update.market_rate);
+        match self {
+            Column::Program => rate_a.program_id.cmp(&rate_b.program_id),
+            Column::Pool => rate_a.pool_address.cmp(&rate_b.pool_address),
+            Column::Pair => a.pair().cmp(&b.pair()),
+            Column::Rate => rate_a.rate().total_cmp(&rate_b.rate()),
+            Column::RateTrend => a.rate_trend().total_cmp(&b.rate_trend()),
+            Column::LiquidityTrend => a.liquidity_trend().total_cmp(&b.liquidity_trend()),
+            // Pools without the history sort below any change
+            Column::Change1h => {
+                a.update.price_change_1h.partial_cmp(&b.update.price_change_1h).unwrap_or(Ordering::Equal)
+            }
+            Column::Change24h => {
+                a.update.price_change_24h.partial_cmp(&b.update.price_change_24h).unwrap_or(Ordering::Equal)
+            }
+            Column::SwapFee => rate_a.swap_fee.total_cmp(&rate_b.swap_fee),
+            Column::Liquidity => rate_a
+                .token_pair
+                .quote_ui_amount(rate_a.liquidity.total_liquidity_quote)
+                .total_cmp(&rate_b.token_pair.quote_ui_amount(rate_b.liquidity.total_liquidity_quote)),
+            Column::Volume => rate_a
+                .token_pair
+                .quote_ui_amount(a.update.volume)
+                .total_cmp(&rate_b.token_pair.quote_ui_amount(b.update.volume)),
+            Column::Updated => rate_a.timestamp.cmp(&rate_b.timestamp),
+        }
+    }
+}
+
+/// What the caller should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiAction {
+    None,
+    Quit,
+    /// Repaint the whole screen, e.g. after other output scribbled on it.
+    Redraw,
+}
+
+#[derive(Clone)]
 pub struct TableUI {
-    market_rates: VecDeque<MarketRate>,
+    rows: HashMap<String, PoolRow>,
+    /// Updates received while paused, latest per pool.
+    held: HashMap<String, PriceUpdate>,
     max_rows: usize,
+    quote_preference: QuotePreference,
+    /// Columns shown, with their widths.
+    columns: Vec<(Column, u16)>,
+    /// Display names by program ID.
+    program_names: HashMap<String, String>,
+    sort_column: Column,
+    descending: bool,
+    filter: String,
+    /// The filter being typed, while in filter input mode.
+    filter_input: Option<String>,
+    paused: bool,
+    selected: usize,
+    /// Subscription state by program name, with its endpoint.
+    connections: BTreeMap<String, (String, SubscriptionState)>,
+    messages: VecDeque<String>,
+    total_updates: u64,
+    sparkline_len: usize,
+    /// Per-pool candles for the detail pane, built by whoever shares them.
+    candles: Option<SharedCandleAggregator>,
+    candle_resolution_ms: i64,
+    show_detail: bool,
 }
 
 impl TableUI {
+    /// `max_rows` caps the plain text table; the full-screen table shows as
+    /// many rows as fit.
     pub fn new(max_rows: usize) -> Self {
         Self {
-            market_rates: VecDeque::new(),
+            rows: HashMap::new(),
+            held: HashMap::new(),
             max_rows,
+            quote_preference: QuotePreference::default(),
+            columns: Column::ALL.iter().map(|column| (*column, column.default_width())).collect(),
+            program_names: HashMap::new(),
+            sort_column: Column::Liquidity,
+            descending: true,
+            filter: String::new(),
+            filter_input: None,
+            paused: false,
+            selected: 0,
+            connections: BTreeMap::new(),
+            messages: VecDeque::new(),
+            total_updates: 0,
+            sparkline_len: SPARKLINE_LEN,
+            candles: None,
+            candle_resolution_ms: ONE_MINUTE_MS,
+            show_detail: false,
         }
+        .with_programs(&get_amm_programs())
+    }
+
+    /// Keep the last `sparkline_len` rates of each pool for the trend
+    /// columns.
+    pub fn with_sparkline_len(mut self, sparkline_len: usize) -> Self {
+        self.sparkline_len = sparkline_len.max(1);
+        self
+    }
+
+    /// Chart the detail pane from `candles` at `resolution_ms`, which should
+    /// keep [`DETAIL_CANDLES`] of history at that resolution. The table only
+    /// reads them; the aggregator is fed by its owner.
+    pub fn with_candles(mut self, candles: SharedCandleAggregator, resolution_ms: i64) -> Self {
+        self.candles = Some(candles);
+        self.candle_resolution_ms = resolution_ms;
+        self
     }
 
-    pub fn add_market_rate(&mut self, rate: MarketRate) {
-        self.market_rates.push_back(rate);
-        if self.market_rates.len() > self.max_rows {
-            self.market_rates.pop_front();
+    /// Name programs as in `programs` rather than by ID.
+    pub fn with_programs(mut self, programs: &[AmmProgram]) -> Self {
+        self.program_names = programs.iter().map(|program| (program.id.clone(), program.name.clone())).collect();
+        self
+    }
+
+    /// Show `columns`, in order, at the given widths.
+    pub fn with_columns(mut self, columns: Vec<(Column, u16)>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Show every pair in the orientation `quote_preference` picks.
+    pub fn with_quote_preference(mut self, quote_preference: QuotePreference) -> Self {
+        self.quote_preference = quote_preference;
+        self
+    }
+
+    /// Add `update`, turned to its pair's canonical orientation so rows of
+    /// the same market line up. While paused, it is held back from the table
+    /// until the view resumes.
+    pub fn add_price_update(&mut self, update: PriceUpdate) {
+        let update = update.normalized(&self.quote_preference);
+        self.total_updates += 1;
+        if self.paused {
+            self.held.insert(update.market_rate.pool_address.clone(), update);
+        } else {
+            self.apply(update);
         }
     }
 
-    pub fn display_table(&self) {
-        println!("\n{}", "=".repeat(120));
-        println!("SOLANA AMM MARKET RATES - REAL-TIME");
-        println!("{}", "=".repeat(120));
-        println!("{:<20} {:<15} {:<15} {:<12} {:<12} {:<15} {:<15}", 
-                 "Program", "Base/Quote", "Rate", "Swap Fee", "Liquidity", "Volume 1h", "Timestamp");
-        println!("{}", "-".repeat(120));
+    fn apply(&mut self, update: PriceUpdate) {
+        let point = (update.market_rate.price, update.market_rate.liquidity.total_liquidity_quote);
+        let row = match self.rows.get_mut(&update.market_rate.pool_address) {
+            Some(row) => {
+                row.tick = match update.market_rate.price.cmp(&row.update.market_rate.price) {
+                    Ordering::Greater => Tick::Up,
+                    Ordering::Less => Tick::Down,
+                    Ordering::Equal => row.tick,
+                };
+                row.updates += 1;
+                row.update = update;
+                row
+            }
+            None => self.rows.entry(update.market_rate.pool_address.clone()).or_insert(PoolRow {
+                update,
+                tick: Tick::Unchanged,
+                updates: 1,
+                history: VecDeque::new(),
+            }),
+        };
+        row.history.push_back(point);
+        while row.history.len() > self.sparkline_len {
+            row.history.pop_front();
+        }
+    }
+
+    /// Record a subscription's new state for the status bar.
+    pub fn set_connection_state(&mut self, event: SubscriptionEvent) {
+        self.connections.insert(event.program_name, (event.endpoint, event.state));
+    }
 
-        for rate in &self.market_rates {
-            let pair = format!("{}/{}", rate.token_pair.base_token, rate.token_pair.quote_token);
-            let liquidity = format!("{:.2} SOL", rate.liquidity.total_liquidity_usd);
-            let volume = format!("{:.2} SOL", rate.liquidity.volume_1h);
-            let timestamp = chrono::DateTime::from_timestamp_millis(rate.timestamp)
-                .unwrap_or_default()
-                .format("%H:%M:%S")
-                .to_string();
+    /// Show `message` in the message pane, e.g. an arbitrage alert.
+    pub fn push_message(&mut self, message: String) {
+        self.messages.push_back(message);
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-            println!("{:<20} {:<15} {:<15.6} {:<12.4} {:<12} {:<15} {:<15}", 
-                     rate.program_id[..20].to_string(), pair, rate.rate, rate.swap_fee, liquidity, volume, timestamp);
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            for (_, update) in std::mem::take(&mut self.held) {
+                self.apply(update);
+            }
         }
-        println!("{}", "=".repeat(120));
+    }
+
+    /// Sort by `column`, largest first if `descending`.
+    pub fn sort_by(&mut self, column: Column, descending: bool) {
+        self.sort_column = column;
+        self.descending = descending;
+    }
+
+    /// Only show pools whose program or pair contains `filter`, ignoring
+    /// case. Empty shows everything.
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_lowercase();
+        self.selected = 0;
+    }
+
+    fn matches_filter(&self, row: &PoolRow) -> bool {
+        self.filter.is_empty()
+            || row.update.market_rate.program_id.to_lowercase().contains(&self.filter)
+            || self.program_name(&row.update.market_rate.program_id).to_lowercase().contains(&self.filter)
+            || row.pair().to_lowercase().contains(&self.filter)
+    }
+
+    /// The program's name, or its abbreviated ID if it isn't known.
+    fn program_name(&self, program_id: &str) -> String {
+        self.program_names.get(program_id).cloned().unwrap_or_else(|| short_address(program_id))
+    }
+
+    /// The leading columns that fit in `width`, with a space between
+    /// columns. The first column is kept even if it has to be cut.
+    fn fitted_columns(&self, width: u16) -> &[(Column, u16)] {
+        let mut used = 0u16;
+        let count = self
+            .columns
+            .iter()
+            .take_while(|(_, column_width)| {
+                used = used.saturating_add(*column_width).saturating_add(if used == 0 { 0 } else { 1 });
+                used <= width
+            })
+            .count();
+        &self.columns[..count.max(1).min(self.columns.len())]
+    }
+
+    fn cell_text(&self, column: Column, row: &PoolRow) -> String {
+        let update = &row.update;
+        let rate = &update.market_rate;
+        // Liquidity and volume are valued in the pair's quote token
+        let quote = &rate.token_pair.quote_token;
+        match column {
+            Column::Program => self.program_name(&rate.program_id),
+            Column::Pool => short_address(&rate.pool_address),
+            Column::Pair => row.pair(),
+            Column::Rate => {
+                let arrow = match row.tick {
+                    Tick::Up => "▲",
+                    Tick::Down => "▼",
+                    Tick::Unchanged => " ",
+                };
+                format!("{} {:.6}", arrow, rate.rate())
+            }
+            Column::RateTrend => row.sparkline(),
+            Column::LiquidityTrend => {
+                let trend = row.liquidity_trend();
+                let arrow = match trend.partial_cmp(&0.0) {
+                    Some(Ordering::Greater) => "↑",
+                    Some(Ordering::Less) => "↓",
+                    _ => "→",
+                };
+                format!("{} {:+.2}%", arrow, trend)
+            }
+            Column::Change1h => format_change(update.price_change_1h),
+            Column::Change24h => format_change(update.price_change_24h),
+            Column::SwapFee => format!("{:.4}", rate.swap_fee),
+            Column::Liquidity => {
+                format!("{:.2} {}", rate.token_pair.quote_ui_amount(rate.liquidity.total_liquidity_quote), quote)
+            }
+            Column::Volume => format!("{:.2} {}", rate.token_pair.quote_ui_amount(update.volume), quote),
+            Column::Updated => format_time(rate.timestamp),
+        }
+    }
+
+    fn cell_style(column: Column, row: &PoolRow) -> Style {
+        match column {
+            Column::Rate => match row.tick {
+                Tick::Up => Style::default().fg(Color::Green),
+                Tick::Down => Style::default().fg(Color::Red),
+                Tick::Unchanged => Style::default(),
+            },
+            Column::RateTrend => change_style(row.rate_trend()),
+            Column::LiquidityTrend => change_style(row.liquidity_trend()),
+            Column::Change1h => change_style(row.update.price_change_1h.unwrap_or(0.0)),
+            Column::Change24h => change_style(row.update.price_change_24h.unwrap_or(0.0)),
+            _ => Style::default(),
+        }
+    }
+
+    /// The rows that pass the filter, in sort order. Ties keep a stable
+    /// order by pool address.
+    pub fn visible_rows(&self) -> Vec<&PoolRow> {
+        let mut rows: Vec<&PoolRow> = self.rows.values().filter(|row| self.matches_filter(row)).collect();
+        rows.sort_by(|a, b| {
+            let ordering = self.sort_column.compare(a, b);
+            let ordering = if self.descending { ordering.reverse() } else { ordering };
+            ordering.then_with(|| a.update.market_rate.pool_address.cmp(&b.update.market_rate.pool_address))
+        });
+        rows
+    }
+
+    /// Apply a key press. In filter input mode keys edit the filter;
+    /// otherwise `q` quits, `p`/space pauses, `s` changes the sort column,
+    /// `r` reverses it, `/` starts a filter and the arrows move the selection.
+    pub fn handle_key(&mut self, key: KeyEvent) -> UiAction {
+        if key.kind != KeyEventKind::Press {
+            return UiAction::None;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('c') => UiAction::Quit,
+                KeyCode::Char('l') => UiAction::Redraw,
+                _ => UiAction::None,
+            };
+        }
+        if let Some(input) = &mut self.filter_input {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let filter = std::mem::take(input);
+                    self.filter_input = None;
+                    self.set_filter(&filter);
+                }
+                KeyCode::Esc => {
+                    self.filter_input = None;
+                    self.set_filter("");
+                }
+                _ => {}
+            }
+            return UiAction::None;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return UiAction::Quit,
+            KeyCode::Char('p') | KeyCode::Char(' ') => self.set_paused(!self.paused),
+            KeyCode::Char('s') => self.sort_column = self.next_sort_column(),
+            KeyCode::Char('r') => self.descending = !self.descending,
+            KeyCode::Char('/') => self.filter_input = Some(self.filter.clone()),
+            KeyCode::Char('d') | KeyCode::Enter => self.show_detail = !self.show_detail,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                let rows = self.rows.values().filter(|row| self.matches_filter(row)).count();
+                self.selected = (self.selected + 1).min(rows.saturating_sub(1));
+            }
+            _ => {}
+        }
+        UiAction::None
+    }
+
+    /// The shown column after the current sort column.
+    fn next_sort_column(&self) -> Column {
+        let position = self.columns.iter().position(|(column, _)| *column == self.sort_column);
+        let next = position.map_or(0, |position| (position + 1) % self.columns.len());
+        self.columns.get(next).map_or(self.sort_column, |(column, _)| *column)
+    }
+
+    /// Updates added so far, held ones included.
+    pub fn total_updates(&self) -> u64 {
+        self.total_updates
+    }
+
+    /// Candles of `pool_address` for the detail pane, oldest first: the
+    /// kept closed ones and the open ones.
+    pub fn pool_candles(&self, pool_address: &str) -> Vec<Candle> {
+        let Some(candles) = &self.candles else {
+            return Vec::new();
+        };
+        let candles = candles.lock().unwrap();
+        let series = SeriesKey::Pool(pool_address.to_string());
+        let resolution_ms = self.candle_resolution_ms;
+        candles
+            .history(&series, resolution_ms)
+            .chain(candles.open_candles(&series, resolution_ms))
+            .cloned()
+            .collect()
+    }
+
+    /// Draw the full-screen view: the table, the detail pane when toggled
+    /// on, the message pane and the status bar.
+    pub fn render(&self, frame: &mut Frame) {
+        let rows = self.visible_rows();
+        let selected = rows.get(self.selected.min(rows.len().saturating_sub(1))).copied();
+        let detail_height = if self.show_detail && selected.is_some() { DETAIL_HEIGHT } else { 0 };
+        let message_height = if self.messages.is_empty() { 0 } else { self.messages.len() as u16 + 2 };
+        let [table_area, detail_area, message_area, status_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(detail_height),
+            Constraint::Length(message_height),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        // Inside the borders
+        let columns = self.fitted_columns(table_area.width.saturating_sub(2));
+        let header = Row::new(columns.iter().map(|(column, _)| {
+            let title = if *column == self.sort_column {
+                format!("{} {}", column.title(), if self.descending { "▼" } else { "▲" })
+            } else {
+                column.title().to_string()
+            };
+            Cell::from(title)
+        }))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let widths = columns.iter().map(|(_, width)| Constraint::Length(*width));
+        let title = format!(
+            " SOLANA AMM MARKET RATES - {} of {} pools{} ",
+            rows.len(),
+            self.rows.len(),
+            if self.filter.is_empty() { String::new() } else { format!(" matching \"{}\"", self.filter) },
+        );
+        let table_rows = rows.iter().map(|row| {
+            Row::new(columns.iter().map(|(column, _)| {
+                Cell::from(self.cell_text(*column, row)).style(Self::cell_style(*column, row))
+            }))
+        });
+        let table = Table::new(table_rows, widths)
+            .header(header)
+            .block(Block::bordered().title(title))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default().with_selected(Some(self.selected.min(rows.len().saturating_sub(1))));
+        frame.render_stateful_widget(table, table_area, &mut state);
+
+        if let Some(row) = selected.filter(|_| self.show_detail) {
+            self.render_detail(frame, detail_area, row);
+        }
+
+        if !self.messages.is_empty() {
+            let lines: Vec<Line> = self.messages.iter().map(|message| Line::from(message.as_str())).collect();
+            frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Messages ")), message_area);
+        }
+
+        frame.render_widget(Paragraph::new(vec![self.connection_line(), self.status_line()]), status_area);
+    }
+
+    /// Candlestick chart of `row`'s pool, with the price range on the left.
+    fn render_detail(&self, frame: &mut Frame, area: Rect, row: &PoolRow) {
+        let candles = self.pool_candles(&row.update.market_rate.pool_address);
+        let title = format!(
+            " {} {} - {} candles ",
+            self.program_name(&row.update.market_rate.program_id),
+            row.pair(),
+            format_resolution(self.candle_resolution_ms),
+        );
+        let block = Block::bordered().title(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if candles.is_empty() {
+            frame.render_widget(Paragraph::new("No candles yet"), inner);
+            return;
+        }
+        let [scale_area, chart_area] =
+            Layout::horizontal([Constraint::Length(12), Constraint::Min(1)]).areas(inner);
+        let (chart, high, low) = candle_chart(&candles, chart_area.width as usize, chart_area.height as usize);
+        let mut scale = vec![Line::from(format!("{:.6}", high))];
+        scale.resize(inner.height.saturating_sub(1) as usize, Line::default());
+        scale.push(Line::from(format!("{:.6}", low)));
+        frame.render_widget(Paragraph::new(scale), scale_area);
+        frame.render_widget(Paragraph::new(chart), chart_area);
+    }
+
+    /// Per-endpoint subscription state.
+    fn connection_line(&self) -> Line<'_> {
+        if self.connections.is_empty() {
+            return Line::from("No subscriptions reported yet");
+        }
+        let mut spans = Vec::new();
+        for (name, (endpoint, state)) in &self.connections {
+            let color = match state {
+                SubscriptionState::Streaming => Color::Green,
+                SubscriptionState::Connecting | SubscriptionState::BackingOff { .. } => Color::Yellow,
+                SubscriptionState::Failed => Color::Red,
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(Span::raw(format!("{} ({}): ", name, endpoint)));
+            spans.push(Span::styled(state.to_string(), Style::default().fg(color)));
+        }
+        Line::from(spans)
+    }
+
+    fn status_line(&self) -> Line<'_> {
+        let mut spans = Vec::new();
+        if self.paused {
+            spans.push(Span::styled(
+                format!(" PAUSED ({} pools held) ", self.held.len()),
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            ));
+            spans.push(Span::raw(" "));
+        }
+        match &self.filter_input {
+            Some(input) => spans.push(Span::raw(format!("Filter: {}█  (enter to apply, esc to clear)", input))),
+            None => spans.push(Span::raw(format!(
+                "{} updates | sort: {} | q quit, p pause, s sort, r reverse, / filter, d detail, ctrl-l redraw",
+                self.total_updates,
+                self.sort_column.title(),
+            ))),
+        }
+        Line::from(spans)
+    }
+
+    /// The rows as a plain text table, for output that isn't a terminal.
+    /// Cells are cut to their column's width.
+    pub fn plain_table(&self) -> String {
+        let width = self.columns.iter().map(|(_, width)| *width as usize + 1).sum::<usize>();
+        let line = |cells: Vec<String>| {
+            let cells: Vec<String> =
+                self.columns.iter().zip(cells).map(|((_, width), cell)| fit(&cell, *width as usize)).collect();
+            cells.join(" ")
+        };
+
+        let mut lines = vec![
+            String::new(),
+            "=".repeat(width),
+            "SOLANA AMM MARKET RATES - REAL-TIME".to_string(),
+            "=".repeat(width),
+            line(self.columns.iter().map(|(column, _)| column.title().to_string()).collect()),
+            "-".repeat(width),
+        ];
+        for row in self.visible_rows().into_iter().take(self.max_rows) {
+            lines.push(line(self.columns.iter().map(|(column, _)| self.cell_text(*column, row)).collect()));
+        }
+        lines.push("=".repeat(width));
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    pub fn display_table(&self) {
+        print!("{}", self.plain_table());
     }
 
     pub fn clear_screen() {
         print!("\x1B[2J\x1B[1;1H");
     }
-} 
\ No newline at end of file
+}
+
+fn format_time(timestamp_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .unwrap_or_default()
+        .format("%H:%M:%S")
+        .to_string()
+}
+
+/// A price change, or `-` for a pool without the history.
+fn format_change(change: Option<f64>) -> String {
+    change.map_or_else(|| "-".to_string(), |change| format!("{:+.2}%", change))
+}
+
+fn format_resolution(resolution_ms: i64) -> String {
+    if resolution_ms % ONE_MINUTE_MS == 0 {
+        format!("{}m", resolution_ms / ONE_MINUTE_MS)
+    } else {
+        format!("{}s", resolution_ms as f64 / 1000.0)
+    }
+}
+
+/// The last candles that fit in `width` columns, one column each, drawn as
+/// `height` lines scaled from the lowest low to the highest high of those
+/// candles, which are returned with the lines. Bodies are `█`, wicks `│`;
+/// rising candles green, falling ones red.
+fn candle_chart(candles: &[Candle], width: usize, height: usize) -> (Vec<Line<'static>>, f64, f64) {
+    let candles = &candles[candles.len().saturating_sub(width)..];
+    let ohlc: Vec<(f64, f64, f64, f64)> = candles.iter().map(Candle::ohlc).collect();
+    let high = ohlc.iter().map(|(_, high, _, _)| *high).fold(f64::NEG_INFINITY, f64::max);
+    let low = ohlc.iter().map(|(_, _, low, _)| *low).fold(f64::INFINITY, f64::min);
+    // Line of `price`, 0 at the top
+    let line_of = |price: f64| {
+        if high > low {
+            ((high - price) / (high - low) * (height.saturating_sub(1)) as f64).round() as usize
+        } else {
+            height / 2
+        }
+    };
+
+    let lines = (0..height)
+        .map(|line| {
+            Line::from(
+                ohlc.iter()
+                    .map(|&(open, candle_high, candle_low, close)| {
+                        let style = Style::default().fg(if close >= open { Color::Green } else { Color::Red });
+                        let body = line_of(open.max(close))..=line_of(open.min(close));
+                        let wick = line_of(candle_high)..=line_of(candle_low);
+                        if body.contains(&line) {
+                            Span::styled("█", style)
+                        } else if wick.contains(&line) {
+                            Span::styled("│", style)
+                        } else {
+                            Span::raw(" ")
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    (lines, high, low)
+}
+
+fn change_style(change: f64) -> Style {
+    match change.partial_cmp(&0.0) {
+        Some(Ordering::Greater) => Style::default().fg(Color::Green),
+        Some(Ordering::Less) => Style::default().fg(Color::Red),
+        _ => Style::default(),
+    }
+}
+
+/// `text` cut or padded to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() > width {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    } else {
+        format!("{:<width$}", text, width = width)
+    }
+}
+
+/// The terminal in full-screen mode. Raw mode and the alternate screen are
+/// left again on drop, and stderr is restored if it was captured.
+pub struct Tui {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    #[cfg(unix)]
+    stderr: Option<StderrCapture>,
+}
+
+impl Tui {
+    pub fn enter() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(std::io::stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+        Ok(Self {
+            terminal,
+            #[cfg(unix)]
+            stderr: None,
+        })
+    }
+
+    /// Show whatever is written to stderr, by this process or its background
+    /// tasks, in `table_ui`'s message pane instead of across the screen.
+    #[cfg(unix)]
+    pub fn capture_stderr(&mut self, table_ui: SharedTableUI) -> std::io::Result<()> {
+        self.stderr = Some(StderrCapture::start(table_ui)?);
+        Ok(())
+    }
+
+    pub fn draw(&mut self, table_ui: &TableUI) -> std::io::Result<()> {
+        self.terminal.draw(|frame| table_ui.render(frame))?;
+        Ok(())
+    }
+
+    /// Forget what is on screen so the next draw repaints everything.
+    pub fn clear(&mut self) -> std::io::Result<()> {
+        self.terminal.clear()
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        self.stderr.take();
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Stderr pointed at a pipe whose lines are pushed to the message pane. The
+/// original stderr is put back on drop, which also ends the reading thread.
+#[cfg(unix)]
+struct StderrCapture {
+    original: OwnedFd,
+}
+
+#[cfg(unix)]
+impl StderrCapture {
+    fn start(table_ui: SharedTableUI) -> std::io::Result<Self> {
+        let (reader, writer) = std::io::pipe()?;
+        // SAFETY: dup and dup2 only duplicate descriptors; the results are
+        // checked and the copy of stderr is owned from here on.
+        let original = unsafe { libc::dup(libc::STDERR_FILENO) };
+        if original < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let original = unsafe { OwnedFd::from_raw_fd(original) };
+        if unsafe { libc::dup2(writer.as_raw_fd(), libc::STDERR_FILENO) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        drop(writer);
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                match line {
+                    Ok(line) => table_ui.lock().unwrap().push_message(line),
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Self { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for StderrCapture {
+    fn drop(&mut self) {
+        // SAFETY: as above; closing the pipe's last writer ends the reader
+        unsafe { libc::dup2(self.original.as_raw_fd(), libc::STDERR_FILENO) };
+    }
+}
+
+/// Where [`run_display`] draws.
+pub enum Display {
+    /// The full-screen view.
+    Tui(Tui),
+    /// A plain table, written on frames with new updates.
+    Plain(Box<dyn Write + Send>),
+}
+
+/// Draw `table_ui` every `frame_interval` until quit is pressed, applying
+/// keys and subscription states as they arrive. The lock is only held to
+/// copy the table; sorting, rendering and writing to the terminal happen on
+/// the copy, so the ingest loop is never stuck behind a slow frame.
+pub async fn run_display(
+    table_ui: SharedTableUI,
+    mut display: Display,
+    frame_interval: Duration,
+    mut keys_rx: mpsc::UnboundedReceiver<KeyEvent>,
+    mut state_rx: mpsc::UnboundedReceiver<SubscriptionEvent>,
+) -> std::io::Result<()> {
+    let mut frames = tokio::time::interval(frame_interval);
+    frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut printed_updates = 0;
+    loop {
+        tokio::select! {
+            _ = frames.tick() => {}
+            Some(key) = keys_rx.recv() => {
+                // Redraw straight away so keys feel immediate
+                let action = table_ui.lock().unwrap().handle_key(key);
+                match action {
+                    UiAction::Quit => return Ok(()),
+                    UiAction::Redraw => {
+                        if let Display::Tui(tui) = &mut display {
+                            tui.clear()?;
+                        }
+                    }
+                    UiAction::None => {}
+                }
+            }
+            Some(event) = state_rx.recv() => {
+                table_ui.lock().unwrap().set_connection_state(event);
+                continue;
+            }
+        }
+        match &mut display {
+            Display::Tui(tui) => {
+                let view = table_ui.lock().unwrap().clone();
+                tui.draw(&view)?;
+            }
+            Display::Plain(writer) => {
+                let view = {
+                    let table_ui = table_ui.lock().unwrap();
+                    if table_ui.total_updates() == printed_updates {
+                        continue;
+                    }
+                    printed_updates = table_ui.total_updates();
+                    table_ui.clone()
+                };
+                writer.write_all(view.plain_table().as_bytes())?;
+                writer.flush()?;
+            }
+        }
+    }
+}
+
+/// Read key presses on a blocking thread, since terminal input has no async
+/// API without extra dependencies. The thread ends when `tx` is closed.
+pub fn spawn_key_reader(tx: mpsc::UnboundedSender<KeyEvent>) {
+    std::thread::spawn(move || loop {
+        match event::read() {
+            Ok(Event::Key(key)) => {
+                if tx.send(key).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_types::WHIRLPOOLS;
+    use crate::test_utils;
+    use crate::candles::CandleAggregator;
+    use ratatui::backend::TestBackend;
+
+    fn update(pool: &str, program_id: &str, base: &str, quote_reserve: u64, liquidity: u128) -> PriceUpdate {
+        let mut market_rate = test_utils::market_rate(pool, 1_000_000_000_000, quote_reserve);
+        market_rate.program_id = program_id.to_string();
+        market_rate.token_pair.base_token = base.to_string();
+        market_rate.liquidity.total_liquidity_quote = liquidity;
+        PriceUpdate {
+            market_rate,
+            price_change_24h: None,
+            price_change_1h: Some(1.5),
+            volume: 0,
+            meets_liquidity_filter: true,
+            meets_volume_filter: true,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn pools(table_ui: &TableUI) -> Vec<&str> {
+        table_ui.visible_rows().iter().map(|row| row.update.market_rate.pool_address.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rows_track_latest_state_per_pool() {
+        let mut table_ui = TableUI::new(20);
+        table_ui.add_price_update(update("a", "whirlpool", "SOL", 150_000_000_000, 3_000));
+        table_ui.add_price_update(update("b", "raydium", "SOL", 151_000_000_000, 2_000));
+        table_ui.add_price_update(update("a", "whirlpool", "SOL", 149_000_000_000, 1_000));
+
+        assert_eq!(pools(&table_ui), vec!["b", "a"]);
+        let rows = table_ui.visible_rows();
+        assert_eq!((rows[1].tick, rows[1].updates), (Tick::Down, 2));
+
+        // Paused updates are held back, latest per pool
+        table_ui.handle_key(key(KeyCode::Char('p')));
+        table_ui.add_price_update(update("a", "whirlpool", "SOL", 152_000_000_000, 1_000));
+        table_ui.add_price_update(update("c", "raydium", "SOL", 150_000_000_000, 500));
+        assert_eq!(pools(&table_ui), vec!["b", "a"]);
+        table_ui.handle_key(key(KeyCode::Char(' ')));
+        assert!(!table_ui.is_paused());
+        assert_eq!(pools(&table_ui), vec!["b", "a", "c"]);
+        assert_eq!(table_ui.visible_rows()[1].tick, Tick::Up);
+    }
+
+    #[test]
+    fn test_keys_sort_and_filter() {
+        let mut table_ui = TableUI::new(20);
+        table_ui.add_price_update(update("a", "whirlpool", "SOL", 150_000_000_000, 3_000));
+        table_ui.add_price_update(update("b", "raydium", "SOL", 151_000_000_000, 2_000));
+        table_ui.add_price_update(update("c", "raydium", "BONK", 149_000_000_000, 1_000));
+
+        // Liquidity -> Liq Trend -> Volume -> Timestamp -> Program, then ascending
+        for _ in 0..4 {
+            table_ui.handle_key(key(KeyCode::Char('s')));
+        }
+        table_ui.handle_key(key(KeyCode::Char('r')));
+        assert_eq!(pools(&table_ui), vec!["b", "c", "a"]);
+
+        for c in "/bonk".chars() {
+            table_ui.handle_key(key(KeyCode::Char(c)));
+        }
+        // Typing doesn't apply the filter, or quit on `q`, until enter
+        assert_eq!(pools(&table_ui).len(), 3);
+        table_ui.handle_key(key(KeyCode::Enter));
+        assert_eq!(pools(&table_ui), vec!["c"]);
+        table_ui.set_filter("RAYDIUM");
+        assert_eq!(pools(&table_ui), vec!["b", "c"]);
+        // The selection stops at the last shown row
+        for _ in 0..5 {
+            table_ui.handle_key(key(KeyCode::Down));
+        }
+        assert_eq!(table_ui.selected, 1);
+        table_ui.handle_key(key(KeyCode::Up));
+        assert_eq!(table_ui.selected, 0);
+        assert_eq!(table_ui.handle_key(key(KeyCode::Char('q'))), UiAction::Quit);
+    }
+
+    #[test]
+    fn test_render_shows_rows_and_connection_state() {
+        let mut table_ui = TableUI::new(20);
+        table_ui.add_price_update(update("a", "whirlpool", "SOL", 150_000_000_000, 3_000));
+        table_ui.set_connection_state(SubscriptionEvent {
+            program_name: "Whirlpools".to_string(),
+            endpoint: "http://localhost:50051".to_string(),
+            state: SubscriptionState::Streaming,
+        });
+        table_ui.push_message("ARBITRAGE: test".to_string());
+
+        let mut terminal = Terminal::new(TestBackend::new(160, 20)).unwrap();
+        terminal.draw(|frame| table_ui.render(frame)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("SOL/USDC"));
+        assert!(screen.contains("150.000000"));
+        assert!(screen.contains("+1.50%"));
+        assert!(screen.contains("ARBITRAGE: test"));
+        assert!(screen.contains("Whirlpools (http://localhost:50051): streaming"));
+    }
+
+    #[test]
+    fn test_programs_are_named_and_addresses_abbreviated() {
+        let mut table_ui = TableUI::new(20);
+        let pool = "HJPjoWUrhoZzkNfRpHuieeFk9WcZWjwy6PBjZ81ngndJ";
+        table_ui.add_price_update(update(pool, WHIRLPOOLS, "SOL", 150_000_000_000, 3_000));
+        table_ui.add_price_update(update("b", "", "SOL", 150_000_000_000, 2_000));
+        table_ui.add_price_update(update("c", "11111111111111111111111111111111", "SOL", 150_000_000_000, 1_000));
+
+        let rows = table_ui.visible_rows();
+        let cells: Vec<(String, String)> = rows
+            .iter()
+            .map(|row| (table_ui.cell_text(Column::Program, row), table_ui.cell_text(Column::Pool, row)))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                ("Whirlpools".to_string(), "HJPj…gndJ".to_string()),
+                (String::new(), "b".to_string()),
+                ("1111…1111".to_string(), "c".to_string()),
+            ]
+        );
+        table_ui.set_filter("whirl");
+        assert_eq!(table_ui.visible_rows().len(), 1);
+
+        // Short and empty program IDs print without panicking
+        table_ui.set_filter("");
+        table_ui.display_table();
+    }
+
+    #[test]
+    fn test_trends_and_candle_detail() {
+        let candles: SharedCandleAggregator = Arc::new(Mutex::new(
+            CandleAggregator::new(&[1_000]).with_pair_candles(false).with_history(1_000, DETAIL_CANDLES),
+        ));
+        let mut table_ui = TableUI::new(20).with_sparkline_len(4).with_candles(candles.clone(), 1_000);
+        for (second, (quote_reserve, liquidity)) in
+            [(150, 1_000), (152, 1_000), (151, 1_100), (153, 1_200), (149, 1_200)].into_iter().enumerate()
+        {
+            let mut price_update = update("a", WHIRLPOOLS, "SOL", quote_reserve * 1_000_000_000, liquidity);
+            price_update.market_rate.timestamp = second as i64 * 1_000;
+            candles.lock().unwrap().observe(&price_update.market_rate);
+            table_ui.add_price_update(price_update);
+        }
+
+        // The first rate has dropped out of the sparkline
+        let row = table_ui.visible_rows()[0];
+        assert_eq!(row.sparkline(), "▆▅█▁");
+        assert_eq!(table_ui.cell_text(Column::LiquidityTrend, row), "↑ +20.00%");
+        assert!(row.rate_trend() < 0.0);
+
+        // Four closed one-second candles and the open one
+        let candles = table_ui.pool_candles("a");
+        assert_eq!(candles.iter().map(|candle| candle.start_ms).collect::<Vec<_>>(), vec![0, 1_000, 2_000, 3_000, 4_000]);
+        // Scaled to the candles drawn, not all of them
+        let (chart, high, low) = candle_chart(&candles[1..3], 2, 3);
+        let chart: Vec<String> =
+            chart.iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect();
+        assert_eq!(chart, vec!["█ ", "  ", " █"]);
+        assert!((high - 152.0).abs() < 1e-9 && (low - 151.0).abs() < 1e-9);
+
+        table_ui.handle_key(key(KeyCode::Char('d')));
+        let mut terminal = Terminal::new(TestBackend::new(160, 30)).unwrap();
+        terminal.draw(|frame| table_ui.render(frame)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Whirlpools SOL/USDC - 1s candles"));
+        assert!(screen.contains("153.000000"));
+    }
+
+    /// Collects what the display task prints.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn tables(&self) -> usize {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).matches("SOLANA AMM MARKET RATES").count()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_display_task_applies_states_and_quits() {
+        let frame = Duration::from_millis(100);
+        let table_ui: SharedTableUI = Arc::new(Mutex::new(TableUI::new(20)));
+        table_ui.lock().unwrap().add_price_update(update("a", WHIRLPOOLS, "SOL", 150_000_000_000, 3_000));
+        let (keys_tx, keys_rx) = mpsc::unbounded_channel();
+        let (state_tx, state_rx) = mpsc::unbounded_channel();
+        let output = Captured::default();
+        let display = Display::Plain(Box::new(output.clone()));
+        let display = tokio::spawn(run_display(table_ui.clone(), display, frame, keys_rx, state_rx));
+
+        // The first frame is drawn straight away
+        tokio::task::yield_now().await;
+        assert_eq!(output.tables(), 1);
+        assert!(String::from_utf8_lossy(&output.0.lock().unwrap()).contains("SOL/USDC"));
+
+        // Frames without new updates print nothing
+        state_tx
+            .send(SubscriptionEvent {
+                program_name: "Whirlpools".to_string(),
+                endpoint: "http://localhost:50051".to_string(),
+                state: SubscriptionState::Streaming,
+            })
+            .unwrap();
+        tokio::time::advance(frame * 3).await;
+        tokio::task::yield_now().await;
+        assert_eq!(output.tables(), 1);
+        assert_eq!(table_ui.lock().unwrap().connections.len(), 1);
+
+        table_ui.lock().unwrap().add_price_update(update("b", WHIRLPOOLS, "SOL", 151_000_000_000, 3_000));
+        tokio::time::advance(frame).await;
+        tokio::task::yield_now().await;
+        assert_eq!(output.tables(), 2);
+
+        keys_tx.send(key(KeyCode::Char('q'))).unwrap();
+        display.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_columns_are_configurable_and_fit_the_terminal() {
+        assert_eq!(
+            Column::parse_list("pair:12,rate,1h").unwrap(),
+            vec![(Column::Pair, 12), (Column::Rate, 16), (Column::Change1h, 9)]
+        );
+        assert_eq!(Column::parse_list("pair,price").unwrap_err(), "Unknown column: price");
+        assert!(Column::parse_list("pair:0").is_err());
+
+        let mut table_ui = TableUI::new(20).with_columns(Column::parse_list("pair:10,rate:16,liquidity").unwrap());
+        table_ui.add_price_update(update("a", WHIRLPOOLS, "SOL", 150_000_000_000, 3_000_000_000));
+        assert_eq!(table_ui.fitted_columns(27).len(), 2);
+        assert_eq!(table_ui.fitted_columns(26).len(), 1);
+        assert_eq!(table_ui.fitted_columns(0).len(), 1);
+
+        for width in [20, 31, 80] {
+            let mut terminal = Terminal::new(TestBackend::new(width, 10)).unwrap();
+            terminal.draw(|frame| table_ui.render(frame)).unwrap();
+            let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+            assert!(screen.contains("SOL/USDC"));
+            assert_eq!(screen.contains("3000.00 USDC"), width == 80);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Symbols and decimals of token mints.
+//!
+//! The registry starts from the reference tokens and a local token list, and
+//! learns the rest from `Mint` accounts as they are decoded. Every
+//! [`TokenPair`] is filled from it before use, so rows read `BONK/SOL` rather
+//! than blanks, and checked against it, so a pair with the wrong decimals is
+//! rejected instead of being priced off by powers of ten.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use serde::{Deserialize, Serialize};
+use crate::amm_types::{short_address, TokenPair, SOL_MINT, USDC_MINT, USDT_MINT};
+use crate::decoders::spl_token::{Mint, MintExtension};
+
+/// A registry entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    #[serde(alias = "mint")]
+    pub address: String,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub name: String,
+    pub decimals: u8,
+    /// Latest `Mint` account state, once one has been decoded.
+    #[serde(skip)]
+    pub mint: Option<Mint>,
+}
+
+impl TokenInfo {
+    pub fn new(address: &str, symbol: &str, decimals: u8) -> Self {
+        Self {
+            address: address.to_string(),
+            symbol: symbol.to_string(),
+            name: String::new(),
+            decimals,
+            mint: None,
+        }
+    }
+
+    /// The symbol, or the abbreviated mint address if there is none.
+    pub fn display_symbol(&self) -> String {
+        if self.symbol.is_empty() {
+            short_address(&self.address)
+        } else {
+            self.symbol.clone()
+        }
+    }
+
+    /// Token-2022 extensions of the mint, if it has been seen.
+    pub fn extensions(&self) -> &[MintExtension] {
+        self.mint.as_ref().map(|mint| mint.extensions.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Token list files: either a bare array of tokens or the
+/// `{"tokens": [...]}` layout of the Solana token lists.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenList {
+    Tokens(Vec<TokenInfo>),
+    Wrapped { tokens: Vec<TokenInfo> },
+}
+
+/// Why a [`TokenPair`] was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenPairError {
+    /// The pair's decimals for `mint` disagree with the registry's.
+    DecimalsMismatch { mint: String, expected: u8, actual: u8 },
+    /// Both sides are the same mint.
+    SameMint(String),
+}
+
+impl fmt::Display for TokenPairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenPairError::DecimalsMismatch { mint, expected, actual } => {
+                write!(f, "{} has {} decimals, pair says {}", mint, expected, actual)
+            }
+            TokenPairError::SameMint(mint) => write!(f, "pair of {} with itself", mint),
+        }
+    }
+}
+
+impl std::error::Error for TokenPairError {}
+
+/// One registry shared by the task that learns mints from the stream and
+/// the ones that fill pairs from it.
+pub type SharedTokenRegistry = Arc<RwLock<TokenRegistry>>;
+
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    tokens: HashMap<String, TokenInfo>,
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenRegistry {
+    /// A registry of the reference tokens.
+    pub fn new() -> Self {
+        let mut registry = Self { tokens: HashMap::new() };
+        registry.add_token(SOL_MINT, "SOL", 9);
+        registry.add_token(USDC_MINT, "USDC", 6);
+        registry.add_token(USDT_MINT, "USDT", 6);
+        registry
+    }
+
+    /// The reference tokens plus those in a token list. List entries
+    /// override the defaults.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let tokens = match serde_json::from_str(json)? {
+            TokenList::Tokens(tokens) | TokenList::Wrapped { tokens } => tokens,
+        };
+        let mut registry = Self::new();
+        for token in tokens {
+            registry.insert(token);
+        }
+        Ok(registry)
+    }
+
+    /// [`TokenRegistry::from_json`] for a token list file.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(Self::from_json(&json)?)
+    }
+
+    pub fn insert(&mut self, token: TokenInfo) {
+        self.tokens.insert(token.address.clone(), token);
+    }
+
+    pub fn add_token(&mut self, mint: &str, symbol: &str, decimals: u8) {
+        self.insert(TokenInfo::new(mint, symbol, decimals));
+    }
+
+    pub fn get(&self, mint: &str) -> Option<&TokenInfo> {
+        self.tokens.get(mint)
+    }
+
+    pub fn contains(&self, mint: &str) -> bool {
+        self.tokens.contains_key(mint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Record a decoded `Mint` account. The account is authoritative for
+    /// decimals; the symbol and name come from the token list if it had
+    /// them, otherwise from Token-2022 metadata.
+    pub fn apply_mint(&mut self, address: &str, mint: Mint) {
+        let token = self.tokens.entry(address.to_string()).or_insert_with(|| TokenInfo::new(address, "", mint.decimals));
+        token.decimals = mint.decimals;
+        for extension in &mint.extensions {
+            if let MintExtension::TokenMetadata { name, symbol, .. } = extension {
+                if token.symbol.is_empty() {
+                    token.symbol = symbol.clone();
+                }
+                if token.name.is_empty() {
+                    token.name = name.clone();
+                }
+            }
+        }
+        token.mint = Some(mint);
+    }
+
+    /// Display symbol for `mint`, abbreviating unknown mints.
+    pub fn symbol(&self, mint: &str) -> String {
+        self.tokens.get(mint).map(TokenInfo::display_symbol).unwrap_or_else(|| short_address(mint))
+    }
+
+    /// The pair of two known mints.
+    pub fn token_pair(&self, base_mint: &str, quote_mint: &str) -> Option<TokenPair> {
+        let base = self.tokens.get(base_mint)?;
+        let quote = self.tokens.get(quote_mint)?;
+        Some(TokenPair {
+            base_token: base.display_symbol(),
+            quote_token: quote.display_symbol(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            base_decimals: base.decimals,
+            quote_decimals: quote.decimals,
+        })
+    }
+
+    /// Fill in blank symbols of `token_pair` and check its decimals. Zero
+    /// decimals on a known mint with more are taken as "not supplied" and
+    /// filled in too; any other disagreement is an error. Pairs without
+    /// mints are left alone.
+    pub fn fill(&self, token_pair: &mut TokenPair) -> Result<(), TokenPairError> {
+        if !token_pair.base_mint.is_empty() && token_pair.base_mint == token_pair.quote_mint {
+            return Err(TokenPairError::SameMint(token_pair.base_mint.clone()));
+        }
+        self.fill_side(&token_pair.base_mint, &mut token_pair.base_token, &mut token_pair.base_decimals)?;
+        self.fill_side(&token_pair.quote_mint, &mut token_pair.quote_token, &mut token_pair.quote_decimals)
+    }
+
+    fn fill_side(&self, mint: &str, symbol: &mut String, decimals: &mut u8) -> Result<(), TokenPairError> {
+        if mint.is_empty() {
+            return Ok(());
+        }
+        let Some(token) = self.tokens.get(mint) else {
+            if symbol.is_empty() {
+                *symbol = short_address(mint);
+            }
+            return Ok(());
+        };
+        if *decimals == 0 {
+            *decimals = token.decimals;
+        } else if *decimals != token.decimals {
+            return Err(TokenPairError::DecimalsMismatch {
+                mint: mint.to_string(),
+                expected: token.decimals,
+                actual: *decimals,
+            });
+        }
+        if symbol.is_empty() {
+            *symbol = token.display_symbol();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BONK_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn pair(base_mint: &str, quote_mint: &str, base_decimals: u8, quote_decimals: u8) -> TokenPair {
+        TokenPair {
+            base_token: String::new(),
+            quote_token: String::new(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            base_decimals,
+            quote_decimals,
+        }
+    }
+
+    #[test]
+    fn test_token_list_fills_and_validates_pairs() {
+        let json = format!(r#"{{"tokens": [{{"address": "{}", "symbol": "BONK", "name": "Bonk", "decimals": 5}}]}}"#, BONK_MINT);
+        let registry = TokenRegistry::from_json(&json).unwrap();
+        assert_eq!(registry.len(), 4);
+        let bare = format!(r#"[{{"mint": "{}", "symbol": "BONK", "name": "Bonk", "decimals": 5}}]"#, BONK_MINT);
+        assert_eq!(TokenRegistry::from_json(&bare).unwrap().get(BONK_MINT), registry.get(BONK_MINT));
+
+        let mut token_pair = pair(BONK_MINT, SOL_MINT, 0, 9);
+        registry.fill(&mut token_pair).unwrap();
+        assert_eq!((token_pair.base_token.as_str(), token_pair.quote_token.as_str()), ("BONK", "SOL"));
+        assert_eq!(token_pair.base_decimals, 5);
+
+        // Unknown mints are abbreviated, wrong decimals and self-pairs rejected
+        let mut token_pair = pair("7GCihgDB8fe6KNjn2MYtkzZcRjQy3t9GHdC8uHYmW2hr", SOL_MINT, 9, 9);
+        registry.fill(&mut token_pair).unwrap();
+        assert_eq!(token_pair.base_token, "7GCi…W2hr");
+        assert_eq!(
+            registry.fill(&mut pair(BONK_MINT, SOL_MINT, 6, 9)),
+            Err(TokenPairError::DecimalsMismatch { mint: BONK_MINT.to_string(), expected: 5, actual: 6 })
+        );
+        assert_eq!(
+            registry.fill(&mut pair(SOL_MINT, SOL_MINT, 9, 9)),
+            Err(TokenPairError::SameMint(SOL_MINT.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_mint_accounts_extend_the_registry() {
+        let address = "vPBTodkqqyeKDdeavRJtcrZ8oUgro4zJJdeSBhsqTfX";
+        let mint = Mint::decode(include_bytes!("../fixtures/token_2022_mint_ext.bin")).unwrap();
+        let mut registry = TokenRegistry::new();
+        assert!(registry.token_pair(address, USDC_MINT).is_none());
+
+        registry.apply_mint(address, mint);
+        let token = registry.get(address).unwrap();
+        assert_eq!((token.symbol.as_str(), token.name.as_str(), token.decimals), ("EXT", "Example Token", 6));
+        assert_eq!(token.extensions().len(), 3);
+        assert_eq!(registry.token_pair(address, USDC_MINT).unwrap().base_token, "EXT");
+
+        // The token list's symbol wins over the on-chain one, the mint's decimals over the list's
+        let mut usdc = Mint::decode(include_bytes!("../fixtures/spl_mint_usdc.bin")).unwrap();
+        usdc.decimals = 9;
+        registry.apply_mint(USDC_MINT, usdc);
+        let token = registry.get(USDC_MINT).unwrap();
+        assert_eq!((token.symbol.as_str(), token.decimals), ("USDC", 9));
+        assert_eq!(token.mint.as_ref().unwrap().supply, 8_500_000_000_000_000);
+    }
+}
@@ -0,0 +1,208 @@
+//! Rolling trade volume per pool, estimated from the rate stream.
+//!
+//! Market rates carry reserves, not trades, so a trade is inferred from two
+//! consecutive rates of the same pool: a swap moves the reserves in opposite
+//! directions, and the change in the quote reserve is the traded quote
+//! amount (fees included). Updates where both reserves move the same way are
+//! liquidity being added or removed and don't count.
+
+use std::collections::{HashMap, VecDeque};
+use crate::amm_types::{MarketRate, PoolLiquidity};
+
+/// Timeframes the volume filter is usually configured with.
+pub const ONE_SECOND_MS: i64 = 1_000;
+pub const ONE_MINUTE_MS: i64 = 60 * ONE_SECOND_MS;
+pub const ONE_HOUR_MS: i64 = 60 * ONE_MINUTE_MS;
+pub const ONE_DAY_MS: i64 = 24 * ONE_HOUR_MS;
+
+/// Buckets per window. Bounds memory per pool, at the cost of counting up
+/// to one bucket (1/60 of the window) of volume that has just expired.
+const BUCKETS_PER_WINDOW: i64 = 60;
+
+/// Parse a timeframe like `1s`, `5m`, `1h` or `24h` into milliseconds.
+pub fn parse_timeframe(timeframe: &str) -> Option<i64> {
+    let split = timeframe.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = timeframe.split_at(split);
+    let unit_ms = match unit {
+        "ms" => 1,
+        "s" => ONE_SECOND_MS,
+        "m" => ONE_MINUTE_MS,
+        "h" => ONE_HOUR_MS,
+        "d" => ONE_DAY_MS,
+        _ => return None,
+    };
+    count.parse::<i64>().ok()?.checked_mul(unit_ms).filter(|ms| *ms > 0)
+}
+
+/// Quote volume traded between two consecutive states of a pool.
+pub fn trade_volume(previous: &PoolLiquidity, current: &PoolLiquidity) -> u128 {
+    let base_up = current.base_reserve > previous.base_reserve;
+    let base_down = current.base_reserve < previous.base_reserve;
+    let quote_up = current.quote_reserve > previous.quote_reserve;
+    let quote_down = current.quote_reserve < previous.quote_reserve;
+    if (base_up && quote_down) || (base_down && quote_up) {
+        current.quote_reserve.abs_diff(previous.quote_reserve) as u128
+    } else {
+        0
+    }
+}
+
+/// Sum of amounts over the last `timeframe_ms`, kept in fixed-width buckets
+/// so adding and expiring are O(1) amortized.
+#[derive(Debug, Clone)]
+pub struct RollingSum {
+    timeframe_ms: i64,
+    bucket_ms: i64,
+    /// `(bucket start, sum)`, oldest first.
+    buckets: VecDeque<(i64, u128)>,
+    total: u128,
+}
+
+impl RollingSum {
+    pub fn new(timeframe_ms: i64) -> Self {
+        let timeframe_ms = timeframe_ms.max(1);
+        Self {
+            timeframe_ms,
+            bucket_ms: (timeframe_ms / BUCKETS_PER_WINDOW).max(1),
+            buckets: VecDeque::new(),
+            total: 0,
+        }
+    }
+
+    /// Add `amount` at `timestamp` (ms). Amounts that arrive late are
+    /// counted in the newest bucket, so one slow update can't be lost.
+    pub fn add(&mut self, timestamp: i64, amount: u128) {
+        let bucket = timestamp - timestamp.rem_euclid(self.bucket_ms);
+        match self.buckets.back_mut() {
+            Some((start, sum)) if *start >= bucket => *sum += amount,
+            _ => self.buckets.push_back((bucket, amount)),
+        }
+        self.total += amount;
+    }
+
+    /// Sum of the amounts in the window ending at `now` (ms).
+    pub fn total(&mut self, now: i64) -> u128 {
+        let window_start = now - self.timeframe_ms;
+        while let Some(&(start, sum)) = self.buckets.front() {
+            if start + self.bucket_ms > window_start {
+                break;
+            }
+            self.total -= sum;
+            self.buckets.pop_front();
+        }
+        self.total
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PoolVolume {
+    last_liquidity: PoolLiquidity,
+    window: RollingSum,
+}
+
+/// Rolling trade volume of every pool seen on the stream, in raw quote
+/// units. Time is stream time: the newest `MarketRate` timestamp seen.
+#[derive(Debug, Clone)]
+pub struct VolumeTracker {
+    timeframe_ms: i64,
+    pools: HashMap<String, PoolVolume>,
+    latest_timestamp: i64,
+}
+
+impl VolumeTracker {
+    pub fn new(timeframe_ms: i64) -> Self {
+        Self {
+            timeframe_ms,
+            pools: HashMap::new(),
+            latest_timestamp: i64::MIN,
+        }
+    }
+
+    pub fn timeframe_ms(&self) -> i64 {
+        self.timeframe_ms
+    }
+
+    /// Record `market_rate` and return the volume traded since the pool's
+    /// previous rate.
+    pub fn observe(&mut self, market_rate: &MarketRate) -> u128 {
+        self.latest_timestamp = self.latest_timestamp.max(market_rate.timestamp);
+        let timeframe_ms = self.timeframe_ms;
+        let pool = self
+            .pools
+            .entry(market_rate.pool_address.clone())
+            .or_insert_with(|| PoolVolume {
+                last_liquidity: market_rate.liquidity.clone(),
+                window: RollingSum::new(timeframe_ms),
+            });
+
+        let traded = trade_volume(&pool.last_liquidity, &market_rate.liquidity);
+        if traded > 0 {
+            pool.window.add(market_rate.timestamp, traded);
+        }
+        pool.last_liquidity = market_rate.liquidity.clone();
+        traded
+    }
+
+    /// Volume traded on `pool_address` over the timeframe, in raw quote units.
+    pub fn volume(&mut self, pool_address: &str) -> u128 {
+        let now = self.latest_timestamp;
+        self.pools
+            .get_mut(pool_address)
+            .map(|pool| pool.window.total(now))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn market_rate(timestamp: i64, base_reserve: u64, quote_reserve: u64) -> MarketRate {
+        MarketRate {
+            timestamp,
+            transaction_signature: format!("tx_{}", timestamp),
+            ..test_utils::market_rate("pool", base_reserve, quote_reserve)
+        }
+    }
+
+    #[test]
+    fn test_parse_timeframe() {
+        assert_eq!(parse_timeframe("1s"), Some(1_000));
+        assert_eq!(parse_timeframe("5m"), Some(300_000));
+        assert_eq!(parse_timeframe("24h"), Some(ONE_DAY_MS));
+        assert_eq!(parse_timeframe("250ms"), Some(250));
+        assert_eq!(parse_timeframe("0s"), None);
+        assert_eq!(parse_timeframe("h"), None);
+        assert_eq!(parse_timeframe("10"), None);
+    }
+
+    #[test]
+    fn test_counts_swaps_not_deposits() {
+        let mut tracker = VolumeTracker::new(ONE_MINUTE_MS);
+        assert_eq!(tracker.observe(&market_rate(0, 1_000, 150_000)), 0);
+        // Sell 10 base for 1,485 quote
+        assert_eq!(tracker.observe(&market_rate(1_000, 1_010, 148_515)), 1_485);
+        // Deposit on both sides
+        assert_eq!(tracker.observe(&market_rate(2_000, 2_020, 297_030)), 0);
+        // Buy back
+        assert_eq!(tracker.observe(&market_rate(3_000, 2_000, 300_000)), 2_970);
+        assert_eq!(tracker.volume("pool"), 4_455);
+        assert_eq!(tracker.volume("other"), 0);
+    }
+
+    #[test]
+    fn test_window_expires_old_volume() {
+        let mut window = RollingSum::new(ONE_SECOND_MS);
+        window.add(0, 100);
+        window.add(500, 50);
+        assert_eq!(window.total(999), 150);
+        // The first bucket is entirely outside (0, 1_016]
+        assert_eq!(window.total(1_016), 50);
+        // A late update lands in the newest bucket instead of being dropped
+        window.add(100, 7);
+        assert_eq!(window.total(1_016), 57);
+        assert_eq!(window.total(10_000), 0);
+        assert!(window.buckets.is_empty());
+    }
+}